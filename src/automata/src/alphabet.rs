@@ -3,6 +3,7 @@
 use crate::symbol::Symbol;
 
 use crate::prelude::*;
+use std::borrow::Cow;
 use std::collections::BTreeSet;
 use std::ops::RangeInclusive;
 
@@ -47,6 +48,7 @@ use std::ops::RangeInclusive;
 /// This type tracks these divisions explicitly for an input alphabet defined for all automata in
 /// this library as `0u64..=u64::max_value()`.
 #[derive(Clone,Debug,PartialEq,Eq)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct Segmentation {
     pub divisions : BTreeSet<Symbol>
@@ -55,8 +57,8 @@ pub struct Segmentation {
 impl Segmentation {
     /// Inserts a range of symbols into the alphabet.
     pub fn insert(&mut self,range:RangeInclusive<Symbol>) {
-        self.divisions.insert(range.start().clone());
-        let end = range.end().clone();
+        self.divisions.insert(*range.start());
+        let end = *range.end();
         end.next().for_each(|t| self.divisions.insert(t));
     }
 
@@ -109,16 +111,27 @@ impl Default for Segmentation {
 #[derive(Clone,Debug,Default,Eq,PartialEq)]
 #[allow(missing_docs)]
 pub struct SealedSegmentation {
-    pub division_map : BTreeMap<Symbol,usize>
+    pub division_map : BTreeMap<Symbol,usize>,
+    classifier       : Classifier,
 }
 
 impl SealedSegmentation {
     /// The index of the provided symbol. Please note that the index always exists, as the alphabet
     /// spans across all possible symbols.
     pub fn index_of_symbol(&self, symbol:&Symbol) -> usize {
-        self.range(symbol..).next().map(|(k,v)|{
-            if k == symbol { *v } else { v - 1 }
-        }).unwrap_or_else(|| self.len() - 1)
+        self.classifier.index_of_symbol(symbol)
+    }
+
+    /// Get the [`Classifier`] looking up the divisions of the symbols.
+    pub fn classifier(&self) -> &Classifier {
+        &self.classifier
+    }
+
+    /// The index of the division containing only the provided symbol, if there is one.
+    pub fn own_division(&self, symbol:&Symbol) -> Option<usize> {
+        let index = *self.division_map.get(symbol)?;
+        let alone = symbol.next().map(|next| self.contains_key(&next)).unwrap_or(true);
+        alone.as_some(index)
     }
 }
 
@@ -132,7 +145,102 @@ impl Deref for SealedSegmentation {
 impl From<&Segmentation> for SealedSegmentation {
     fn from(s:&Segmentation) -> Self {
         let division_map = s.divisions.iter().cloned().enumerate().map(|(ix,s)|(s,ix)).collect();
-        Self {division_map}
+        let classifier   = Classifier::new(s.divisions.iter().copied().collect());
+        Self {division_map,classifier}
+    }
+}
+
+/// The sealed segmentation is serialized as the ordered sequence of its divisions, as the indices
+/// of the divisions are implied by that order.
+#[cfg(feature="serde")]
+impl serde::Serialize for SealedSegmentation {
+    fn serialize<S:serde::Serializer>(&self, serializer:S) -> Result<S::Ok,S::Error> {
+        serializer.collect_seq(self.division_map.keys())
+    }
+}
+
+#[cfg(feature="serde")]
+impl<'de> serde::Deserialize<'de> for SealedSegmentation {
+    fn deserialize<D:serde::Deserializer<'de>>(deserializer:D) -> Result<Self,D::Error> {
+        let divisions:BTreeSet<Symbol> = serde::Deserialize::deserialize(deserializer)?;
+        Ok((&Segmentation{divisions}).into())
+    }
+}
+
+
+
+// ==================
+// === Classifier ===
+// ==================
+
+/// A lookup structure mapping the symbols to the indices of the divisions of a [`Segmentation`]
+/// containing them.
+///
+/// As the lookup is performed for each symbol of the input, the divisions of the Latin-1 symbols
+/// are looked up directly in a table, while the divisions of the other symbols are found with a
+/// binary search over the sorted starts of the divisions.
+///
+/// The classifier can be embedded in the generated code as a static, with the tables produced by
+/// [`Classifier::table`] and [`Classifier::divisions`]:
+///
+/// ```
+/// # use enso_automata::alphabet::Classifier;
+/// # use enso_automata::symbol::Symbol;
+/// static CLASSIFIER:Classifier = Classifier::from_static(
+///     &[0;Classifier::TABLE_SIZE],
+///     &[Symbol::new(0),Symbol::new(256)],
+/// );
+/// assert_eq!(CLASSIFIER.index_of_symbol(&Symbol::from('a')),0);
+/// assert_eq!(CLASSIFIER.index_of_symbol(&Symbol::from('ł')),1);
+/// ```
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
+pub struct Classifier {
+    table     : Cow<'static,[usize]>,
+    divisions : Cow<'static,[Symbol]>,
+}
+
+impl Classifier {
+    /// The number of symbols looked up directly in the table, that is the Latin-1 symbols.
+    pub const TABLE_SIZE:usize = 256;
+
+    /// Constructor, from the sorted starts of the divisions.
+    pub fn new(divisions:Vec<Symbol>) -> Self {
+        let index_in = |divisions:&[Symbol],symbol:Symbol| {
+            divisions.partition_point(|division| *division <= symbol).saturating_sub(1)
+        };
+        let symbols = (0..Self::TABLE_SIZE as u64).map(Symbol::new);
+        let table   = symbols.map(|symbol| index_in(&divisions,symbol)).collect_vec();
+        Self {table:Cow::Owned(table),divisions:Cow::Owned(divisions)}
+    }
+
+    /// Constructor, from the `table` and the `divisions` of another classifier.
+    ///
+    /// It is usable in the constant expressions, so that the generated code can embed the
+    /// classifier as a static.
+    pub const fn from_static(table:&'static [usize], divisions:&'static [Symbol]) -> Self {
+        Self {table:Cow::Borrowed(table),divisions:Cow::Borrowed(divisions)}
+    }
+
+    /// The index of the division containing the provided symbol.
+    pub fn index_of_symbol(&self, symbol:&Symbol) -> usize {
+        match usize::try_from(symbol.index).ok().and_then(|index| self.table.get(index)) {
+            Some(index) => *index,
+            None        => {
+                let following = self.divisions.partition_point(|division| division <= symbol);
+                following.saturating_sub(1)
+            }
+        }
+    }
+
+    /// Get the indices of the divisions of the symbols looked up directly.
+    pub fn table(&self) -> &[usize] {
+        &self.table
+    }
+
+    /// Get the sorted starts of the divisions.
+    pub fn divisions(&self) -> &[Symbol] {
+        &self.divisions
     }
 }
 
@@ -176,4 +284,38 @@ mod tests {
         assert_eq!(segmentation.num_divisions(), 5);
         assert!(segmentation.divisions.contains(&Symbol::from(15u64)));
     }
+
+    #[test]
+    fn sealed_segmentation_own_division() {
+        let sealed = Segmentation::from_divisions(&[0,5,6,10,u64::max_value()]).seal();
+        assert_eq!(sealed.own_division(&Symbol::from(5u64)),Some(1));
+        assert_eq!(sealed.own_division(&Symbol::from(6u64)),None);
+        assert_eq!(sealed.own_division(&Symbol::from(7u64)),None);
+        assert_eq!(sealed.own_division(&Symbol::eof()),Some(4));
+    }
+
+    #[test]
+    fn classifier_index_of_symbol() {
+        let sealed     = Segmentation::from_divisions(&[0,5,6,300,1000,u64::max_value()]).seal();
+        let classifier = sealed.classifier();
+        let symbols    = [0,4,5,6,255,256,299,300,999,1000,u64::max_value() - 1,u64::max_value()];
+        let expected   = [0,0,1,2,2,2,2,3,3,4,4,5];
+        for (symbol,expected) in symbols.iter().zip(&expected) {
+            assert_eq!(classifier.index_of_symbol(&Symbol::new(*symbol)),*expected);
+            assert_eq!(sealed.index_of_symbol(&Symbol::new(*symbol)),*expected);
+        }
+        let table     = Box::leak(classifier.table().to_vec().into_boxed_slice());
+        let divisions = Box::leak(classifier.divisions().to_vec().into_boxed_slice());
+        assert_eq!(&Classifier::from_static(table,divisions),classifier);
+    }
+
+    #[test]
+    #[cfg(feature="serde")]
+    fn sealed_segmentation_serde_round_trip() {
+        let sealed = Segmentation::from_divisions(&[0,5,10,u64::max_value()]).seal();
+        let json   = serde_json::to_string(&sealed).unwrap();
+        let loaded = serde_json::from_str::<SealedSegmentation>(&json).unwrap();
+        assert_eq!(loaded,sealed);
+        assert_eq!(loaded.index_of_symbol(&Symbol::from(7u64)),1);
+    }
 }
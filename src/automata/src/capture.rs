@@ -0,0 +1,645 @@
+//! Capture groups, recording the spans of the input on which the named sub-patterns of a pattern
+//! trigger.
+//!
+//! The capture groups are compiled to the tagged states of the [`Nfa`], with the state in which the
+//! body of a group starts tagged with [`Tag::Start`], and the state following its end with
+//! [`Tag::End`]. The conversion of such an NFA to a [`Dfa`] turns the tags into [`Operation`]s on
+//! numbered registers, carried by the transitions of the DFA, in the way of the tagged DFAs of
+//! Laurikari. Each state of the DFA knows which registers hold the positions of the tags on the
+//! paths to each of its NFA states, and the transitions move these positions between the
+//! registers, storing the current position in the input for the tags that were entered. A run of
+//! the automaton applies the operations to its [`Registers`], so that the positions are known as
+//! soon as the input is matched, without scanning it again. The [`Tags::finals`] of the accepting
+//! state then copy the positions of the path to the accepting NFA state to the output registers,
+//! from which the [`Groups`] of a pattern read the spans of its capture groups.
+//!
+//! The tags entered while following the epsilon links of the NFA are stored by the next
+//! transition, before it consumes its symbol, so that every operation stores the position at which
+//! its transition is taken.
+//!
+//! Where a pattern can trigger on the input in multiple ways, the alternatives that are listed
+//! first and the repetitions that consume the most input take precedence, as they do in the
+//! backtracking regex engines. A group that is repeated records the span of its last repetition,
+//! and the groups with the same name, as in `(?<x>a)|(?<x>b)`, are treated as a single group. The
+//! states of the DFA thus keep the paths to their NFA states in the order of their precedence,
+//! which can make the automata of the patterns with capture groups larger than the ones without.
+//! The automata of the NFAs without tagged states are not affected.
+//!
+//! The groups inside the boolean operators, that is [`Pattern::And`], [`Pattern::Minus`] and
+//! [`Pattern::Complement`], as well as inside the contexts of the lookaheads, are compiled to
+//! automata without tags, so that they never record a span.
+
+use crate::prelude::*;
+
+use crate::dfa;
+use crate::nfa;
+use crate::nfa::Nfa;
+use crate::symbol::Symbol;
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+#[cfg(doc)]
+use crate::dfa::Dfa;
+#[cfg(doc)]
+use crate::pattern::Pattern;
+
+
+
+// =============
+// === Types ===
+// =============
+
+/// A span of the input, in bytes.
+pub type Span = Range<usize>;
+
+/// The identifier of a register, holding a position in the input.
+pub type Register = usize;
+
+
+
+// ===========
+// === Tag ===
+// ===========
+
+/// A boundary of a capture group, identified by the index of the group in
+/// [`Nfa::capture_names`].
+#[derive(Clone,Copy,Debug,Eq,Hash,Ord,PartialEq,PartialOrd)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
+pub enum Tag {
+    /// The start of the group, recorded before its body consumes any input.
+    Start(usize),
+    /// The end of the group, recorded once its body has triggered.
+    End(usize),
+}
+
+impl Tag {
+    /// The index of the tag among the tags of an automaton, which is `2 * group` for the start of
+    /// a group and `2 * group + 1` for its end.
+    pub fn index(self) -> usize {
+        match self {
+            Tag::Start(group) => group * 2,
+            Tag::End(group)   => group * 2 + 1,
+        }
+    }
+}
+
+
+
+// =================
+// === Operation ===
+// =================
+
+/// An operation on the [`Registers`], carried by a transition or by an accepting state of a
+/// [`Dfa`].
+#[derive(Clone,Copy,Debug,Eq,Hash,Ord,PartialEq,PartialOrd)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
+pub enum Operation {
+    /// Store the current position in the register.
+    Set(Register),
+    /// Copy the position held by the register `from` to the `register`.
+    Copy {register:Register, from:Register},
+    /// Clear the register, as the tag it stands for was not entered.
+    Clear(Register),
+}
+
+impl Operation {
+    /// The register written by the operation.
+    pub fn register(self) -> Register {
+        match self {
+            Operation::Set(register)      => register,
+            Operation::Copy {register,..} => register,
+            Operation::Clear(register)    => register,
+        }
+    }
+}
+
+
+
+// =================
+// === Registers ===
+// =================
+
+/// The registers of a run of a [`Dfa`], holding the positions in the input at which the tags of
+/// its capture groups were entered.
+///
+/// The registers `0..2 * n`, where `n` is the number of the capture groups, are the output
+/// registers. Once the [`Tags::finals`] of an accepting state are applied, each of them holds the
+/// position of the tag with the same [index](Tag::index). The other registers are used by the
+/// transitions of the automaton.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct Registers {
+    values : Vec<Option<usize>>,
+}
+
+impl Registers {
+    /// Apply the `operations` in order, storing the `position` in the registers that are set.
+    pub fn apply(&mut self, operations:&[Operation], position:usize) {
+        for operation in operations {
+            let value = match *operation {
+                Operation::Set(_)         => Some(position),
+                Operation::Copy {from,..} => self.get(from),
+                Operation::Clear(_)       => None,
+            };
+            let register = operation.register();
+            if register >= self.values.len() {
+                self.values.resize(register + 1,None);
+            }
+            self.values[register] = value;
+        }
+    }
+
+    /// Get the position held by the `register`, if any.
+    pub fn get(&self, register:Register) -> Option<usize> {
+        self.values.get(register).copied().flatten()
+    }
+}
+
+
+
+// ============
+// === Tags ===
+// ============
+
+/// The operations on the [`Registers`] carried by a [`Dfa`], indexed by its states.
+///
+/// See the [module docs](crate::capture) for details.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
+pub struct Tags {
+    /// The names of the capture groups, as in [`Nfa::capture_names`].
+    pub names : Vec<String>,
+    /// The operations of the transitions from each state, indexed by the columns of
+    /// [`Dfa::links`]. The transitions without operations are omitted.
+    pub transitions : Vec<BTreeMap<usize,Vec<Operation>>>,
+    /// The operations of each state, storing the positions of the tags on the path to each of its
+    /// [`Dfa::sources`] in the output registers.
+    pub finals : Vec<BTreeMap<nfa::State,Vec<Operation>>>,
+}
+
+impl Tags {
+    /// Get the operations of the transition from the `state` in the `column` of [`Dfa::links`].
+    pub fn transition(&self, state:dfa::State, column:usize) -> &[Operation] {
+        let operations = self.transitions.get(state.id()).and_then(|links| links.get(&column));
+        operations.map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Get the operations storing the positions of the tags on the path to the `source` in the
+    /// output registers, once the automaton accepts in the `state`.
+    pub fn finals(&self, state:dfa::State, source:nfa::State) -> &[Operation] {
+        let operations = self.finals.get(state.id()).and_then(|finals| finals.get(&source));
+        operations.map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+
+
+// ==============
+// === Groups ===
+// ==============
+
+/// The capture groups of a pattern, reading their spans from the output registers of a [`Dfa`].
+///
+/// The names of the groups are shared with the [`Captures`], so that the captures do not borrow
+/// the groups. They are shared atomically, as the generated lexers keep their groups in `static`s.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct Groups {
+    names   : Arc<[String]>,
+    indices : Vec<usize>,
+}
+
+impl Groups {
+    /// Constructor, taking the names of the groups paired with their indices in
+    /// [`Nfa::capture_names`].
+    pub fn new<'a>(groups:impl IntoIterator<Item=(&'a str,usize)>) -> Self {
+        let groups          = groups.into_iter().map(|(name,index)| (name.to_string(),index));
+        let (names,indices) = groups.unzip::<_,_,Vec<_>,Vec<_>>();
+        let names           = Arc::from(names);
+        Self {names,indices}
+    }
+
+    /// Get the names of the groups.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Read the spans of the groups from the output `registers`.
+    ///
+    /// A group has a span if both its start and its end were recorded, with the start not past
+    /// the end.
+    pub fn captures(&self, registers:&Registers) -> Captures {
+        let spans = self.indices.iter().map(|group| {
+            let start = registers.get(Tag::Start(*group).index())?;
+            let end   = registers.get(Tag::End(*group).index())?;
+            (start <= end).as_some(start..end)
+        }).collect();
+        let names = self.names.clone();
+        Captures {names,spans}
+    }
+}
+
+
+
+// ================
+// === Captures ===
+// ================
+
+/// The spans of the input on which the capture groups of a pattern triggered, as read by its
+/// [`Groups`].
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct Captures {
+    names : Arc<[String]>,
+    spans : Vec<Option<Span>>,
+}
+
+impl Captures {
+    /// Get the span of the capture group called `name`, if it triggered.
+    pub fn get(&self, name:&str) -> Option<Span> {
+        self.iter().find(|(group,_)| *group == name).map(|(_,span)| span)
+    }
+
+    /// Iterate over the names and the spans of the capture groups that triggered, in the order of
+    /// the groups.
+    pub fn iter(&self) -> impl Iterator<Item=(&str,Span)> {
+        let names = self.names.iter().map(String::as_str);
+        names.zip(&self.spans).filter_map(|(name,span)| Some((name,span.clone()?)))
+    }
+
+    /// Check whether none of the capture groups triggered.
+    pub fn is_empty(&self) -> bool {
+        self.spans.iter().all(Option::is_none)
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Default for Captures {
+    fn default() -> Self {
+        let names = Arc::from(Vec::new());
+        let spans = default();
+        Self {names,spans}
+    }
+}
+
+
+
+// =====================
+// === Configuration ===
+// =====================
+
+/// The register standing for the current position, which the tags entered on a transition get
+/// until they are assigned a register of their own.
+const POSITION : Register = Register::MAX;
+
+/// A state of a [`Dfa`] under construction, made of the paths through the NFA that end in its NFA
+/// states.
+///
+/// The paths are kept in the order of their precedence, and are identified by the registers that
+/// hold the positions of their tags. The configurations of the automata without tagged states
+/// order their paths by their NFA states instead, as the precedence does not matter for them, so
+/// that their DFAs have the same states as the ones constructed from the sets of the NFA states.
+#[derive(Clone,Debug,Eq,Hash,PartialEq)]
+pub struct Configuration {
+    paths : Vec<Path>,
+}
+
+impl Configuration {
+    /// The configuration of the start state of the DFA of the `nfa`.
+    pub(crate) fn start(nfa:&Nfa) -> Self {
+        let tags      = if nfa.has_tags() { nfa.capture_names().len() * 2 } else { 0 };
+        let state     = nfa.start;
+        let registers = vec![None;tags];
+        let entered   = vec![false;tags];
+        let start     = Path {state,registers,entered};
+        Self::closure(nfa,vec![start],None).canonical().0
+    }
+
+    /// Get the configuration reached on the `symbol`, together with the operations of the
+    /// transition, or `None` if no path continues on the `symbol`.
+    ///
+    /// The transitions on the line anchors are taken as described in [`Nfa::next_states`].
+    pub(crate) fn next(&self, nfa:&Nfa, symbol:Symbol) -> Option<(Self,Vec<Operation>)> {
+        if !nfa.has_line_anchors() { return self.advance(nfa,symbol,None) }
+        if symbol.is_anchor() { return Some(self.follow(nfa,symbol)) }
+        let newline = Symbol::from('\n');
+        if symbol != newline && symbol != Symbol::eof() { return self.advance(nfa,symbol,None) }
+        let (line_end,mut operations) = self.follow(nfa,Symbol::line_end());
+        let line_start                = (symbol == newline).as_some(Symbol::line_start());
+        let (target,advance)          = line_end.advance(nfa,symbol,line_start)?;
+        operations.extend(advance);
+        Some((target,operations))
+    }
+
+    /// Get the exported NFA states of the configuration, in the order of their identifiers, as the
+    /// [`Dfa::sources`] of its state.
+    pub(crate) fn sources(&self, nfa:&Nfa) -> Vec<nfa::State> {
+        let sources = self.paths.iter().map(|path| path.state).filter(|state| nfa[*state].export);
+        sources.sorted().collect()
+    }
+
+    /// Get the operations storing the positions of the tags on the path to each of the exported
+    /// NFA states in the output registers, as the [`Tags::finals`] of its state.
+    pub(crate) fn finals(&self, nfa:&Nfa) -> BTreeMap<nfa::State,Vec<Operation>> {
+        let paths = self.paths.iter().filter(|path| nfa[path.state].export);
+        paths.map(|path| (path.state,path.finals())).collect()
+    }
+
+    /// Get the operations storing the positions of the tags on the path to the `source` in the
+    /// output registers, if the configuration has such a path.
+    pub(crate) fn finals_for(&self, source:nfa::State) -> Vec<Operation> {
+        let path = self.paths.iter().find(|path| path.state == source);
+        path.map(Path::finals).unwrap_or_default()
+    }
+
+    /// Get the configuration reached by consuming the `symbol`, and following the transitions on
+    /// exactly the line `anchor` afterwards if it is given, together with the operations of the
+    /// transition. Returns `None` if no path continues on the `symbol`.
+    fn advance
+    (&self, nfa:&Nfa, symbol:Symbol, anchor:Option<Symbol>) -> Option<(Self,Vec<Operation>)> {
+        let roots = self.paths.iter().flat_map(|path| {
+            let links = nfa[path.state].links().iter();
+            let links = links.filter(move |link| link.symbols.contains(&symbol));
+            links.map(move |link| path.advanced_to(link.target))
+        }).collect_vec();
+        (!roots.is_empty()).as_some_from(|| Self::closure(nfa,roots,anchor).canonical())
+    }
+
+    /// Get the configuration extended with the paths following the transitions on exactly the
+    /// line `anchor`, together with the operations of the transition on the anchor.
+    fn follow(&self, nfa:&Nfa, anchor:Symbol) -> (Self,Vec<Operation>) {
+        Self::closure(nfa,self.paths.clone(),Some(anchor)).canonical()
+    }
+
+    /// Get the configuration of the `roots`, extended with the paths following their epsilon
+    /// links, and the transitions on exactly the line `anchor` if it is given.
+    ///
+    /// The states are visited depth-first in the order of the paths and of the links, so that each
+    /// state is reached by the path that takes precedence. The paths enter the tags of the states
+    /// they visit.
+    fn closure(nfa:&Nfa, roots:Vec<Path>, anchor:Option<Symbol>) -> Self {
+        let mut visited  = vec![false;nfa.states().len()];
+        let mut paths    = vec![];
+        let mut to_visit = roots.into_iter().rev().collect_vec();
+        while let Some(mut path) = to_visit.pop() {
+            if visited[path.state.id()] { continue }
+            visited[path.state.id()] = true;
+            let state = &nfa[path.state];
+            for tag in &state.tags {
+                path.entered[tag.index()] = true;
+            }
+            let anchors = anchor.into_iter().flat_map(|anchor| {
+                let links = state.links().iter();
+                links.filter(move |link| link.symbols == (anchor..=anchor)).map(|link| link.target)
+            });
+            let targets = state.epsilon_links().iter().copied().chain(anchors).collect_vec();
+            to_visit.extend(targets.into_iter().rev().map(|target| path.moved_to(target)));
+            paths.push(path);
+        }
+        Self {paths}
+    }
+
+    /// Get the canonical form of the configuration, together with the operations moving the
+    /// positions to the registers that it uses.
+    ///
+    /// The registers of each tag are numbered in the order in which they first occur on the
+    /// paths, so that the configurations differing only in the numbering of their registers have
+    /// the same canonical form. The positions of the tags entered on the transition are stored in
+    /// the registers that they get, once the other positions are moved.
+    fn canonical(mut self) -> (Self,Vec<Operation>) {
+        let tags = self.paths.first().map(|path| path.registers.len()).unwrap_or_default();
+        if tags == 0 {
+            self.paths.sort_by_key(|path| path.state);
+            return (self,default())
+        }
+        let mut operations = vec![];
+        for tag in 0..tags {
+            let mut old_registers:Vec<Register> = vec![];
+            for path in &mut self.paths {
+                if let Some(old) = path.registers[tag] {
+                    let index = old_registers.iter().position(|register| *register == old);
+                    let index = index.unwrap_or_else(|| {
+                        old_registers.push(old);
+                        old_registers.len() - 1
+                    });
+                    path.registers[tag] = Some(register(tags,tag,index));
+                }
+            }
+            let moved  = old_registers.iter().enumerate().filter(|(_,old)| **old != POSITION);
+            let copies = moved.map(|(index,old)| (register(tags,tag,index),*old));
+            let copies = copies.filter(|(register,from)| register != from).collect_vec();
+            let used   = old_registers.iter().filter(|old| **old != POSITION).map(|old| old / tags);
+            let unused = used.max().unwrap_or_default().max(old_registers.len());
+            let spare  = register(tags,tag,unused);
+            operations.extend(sequential_copies(copies,spare));
+            let set = old_registers.iter().position(|old| *old == POSITION);
+            operations.extend(set.map(|index| Operation::Set(register(tags,tag,index))));
+        }
+        (self,operations)
+    }
+}
+
+
+// === Path ===
+
+/// A path through an NFA, ending in the `state`.
+///
+/// The `registers` hold the register with the position at which the path last entered each of
+/// the tags, indexed by [`Tag::index`]. The tags `entered` in the epsilon closure that the path
+/// last went through are still to be stored at the position of the next transition.
+#[derive(Clone,Debug,Eq,Hash,PartialEq)]
+struct Path {
+    state     : nfa::State,
+    registers : Vec<Option<Register>>,
+    entered   : Vec<bool>,
+}
+
+impl Path {
+    /// Get the path continuing this one in the `state` by consuming a symbol, with the entered
+    /// tags stored at the current position.
+    fn advanced_to(&self, state:nfa::State) -> Self {
+        let registers = self.registers.iter().zip(&self.entered).map(|(register,entered)| {
+            if *entered { Some(POSITION) } else { *register }
+        }).collect();
+        let entered = vec![false;self.entered.len()];
+        Self {state,registers,entered}
+    }
+
+    /// Get the path continuing this one in the `state` without consuming a symbol.
+    fn moved_to(&self, state:nfa::State) -> Self {
+        Self {state,..self.clone()}
+    }
+
+    /// Get the operations storing the positions of the tags on the path in the output registers.
+    fn finals(&self) -> Vec<Operation> {
+        let registers = self.registers.iter().zip(&self.entered).enumerate();
+        registers.map(|(tag,(register,entered))| match register {
+            _ if *entered  => Operation::Set(tag),
+            Some(register) => Operation::Copy {register:tag,from:*register},
+            None           => Operation::Clear(tag),
+        }).collect()
+    }
+}
+
+
+// === Utilities ===
+
+/// Get the register with the `index` among the registers of the `tag`, in an automaton with the
+/// given number of `tags`. The registers of the tags are interleaved, and follow the output
+/// registers.
+fn register(tags:usize, tag:usize, index:usize) -> Register {
+    (index + 1) * tags + tag
+}
+
+/// Get the operations performing the `copies` of the positions, given as the pairs of their target
+/// and source registers, as if they were performed at once.
+///
+/// Every register is the target of at most one copy. The copies are ordered so that no position is
+/// overwritten before it is copied, and the cycles of copies are broken by moving a position to
+/// the `spare` register first.
+fn sequential_copies(mut copies:Vec<(Register,Register)>, spare:Register) -> Vec<Operation> {
+    let mut operations = vec![];
+    while !copies.is_empty() {
+        let is_free = |register:&Register| copies.iter().all(|(_,from)| from != register);
+        match copies.iter().position(|(register,_)| is_free(register)) {
+            Some(ix) => {
+                let (register,from) = copies.remove(ix);
+                operations.push(Operation::Copy {register,from});
+            },
+            None => {
+                let (register,_) = copies[0];
+                operations.push(Operation::Copy {register:spare,from:register});
+                for (_,from) in &mut copies {
+                    if *from == register { *from = spare }
+                }
+            },
+        }
+    }
+    operations
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dfa::Dfa;
+    use crate::pattern::Pattern;
+
+    fn dfa_for(pattern:&Pattern) -> (Dfa,nfa::State) {
+        let mut nfa = Nfa::new();
+        let end     = nfa.new_pattern(nfa.start,pattern);
+        (Dfa::from(&nfa),end)
+    }
+
+    fn captures(regex:&str, input:&str, line_start:bool) -> Option<Captures> {
+        let (dfa,end) = dfa_for(&Pattern::parse(regex).unwrap());
+        dfa.captures(input,line_start,end)
+    }
+
+    fn assert_captures(regex:&str, input:&str, expected:&[(&str,Span)]) {
+        let found = captures(regex,input,true).unwrap();
+        assert_eq!(found.iter().collect_vec(),expected,"{:?} on {:?}",regex,input);
+    }
+
+    #[test]
+    fn captures_sequence() {
+        let regex = "(?<name>[a-z]+)=(?<value>[0-9]+)";
+        assert_captures(regex,"abc=123",&[("name",0..3),("value",4..7)]);
+        assert_eq!(captures(regex,"abc=123",true).unwrap().get("value"),Some(4..7));
+        assert_eq!(captures(regex,"abc=123",true).unwrap().get("other"),None);
+    }
+
+    #[test]
+    fn captures_alternatives() {
+        let regex = "(?<x>a)|(?<y>b)|(?<x>c)";
+        assert_captures(regex,"b",&[("y",0..1)]);
+        assert_eq!(captures(regex,"c",true).unwrap().get("x"),Some(0..1));
+        assert!(captures("(?<x>a)?b","b",true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn captures_precedence() {
+        assert_captures("(?<x>a*)(?<y>a*)","aaa",&[("x",0..3),("y",3..3)]);
+        assert_captures("(?<x>a|ab)(?<y>b?)","ab",&[("x",0..1),("y",1..2)]);
+    }
+
+    #[test]
+    fn captures_repetitions() {
+        assert_captures("((?<x>[ab])c)*","acbc",&[("x",2..3)]);
+        assert_captures("(?<x>(?<y>a)+)","aaa",&[("x",0..3),("y",2..3)]);
+        assert_captures("(?<x>a)*(?<y>a*)b","aab",&[("x",1..2),("y",2..2)]);
+    }
+
+    #[test]
+    fn captures_bytes() {
+        assert_captures("z(?<x>ł+)ż","złłż",&[("x",1..5)]);
+    }
+
+    #[test]
+    fn captures_line_anchors() {
+        let regex = "(?<x>^a)?a*(?<y>\n^a)?";
+        assert_eq!(captures(regex,"aa",true).unwrap().get("x"),Some(0..1));
+        assert_eq!(captures(regex,"aa",false).unwrap().get("x"),None);
+        assert_eq!(captures(regex,"a\na",false).unwrap().get("y"),Some(1..3));
+        assert_eq!(captures("a(?<x>$)?\n?","a\n",true).unwrap().get("x"),Some(1..1));
+    }
+
+    #[test]
+    fn captures_eof() {
+        assert_captures("a(?<x>b?)\\z","ab",&[("x",1..2)]);
+    }
+
+    #[test]
+    fn captures_lookahead_body() {
+        let body      = Pattern::capture("x",&Pattern::char('a'));
+        let context   = Pattern::capture("y",&Pattern::char('b'));
+        let (dfa,end) = dfa_for(&body.followed_by(&context));
+        let found     = dfa.captures("ab",true,end).unwrap();
+        assert_eq!(found.iter().collect_vec(),vec![("x",0..1)]);
+    }
+
+    #[test]
+    fn captures_no_match() {
+        assert_eq!(captures("(?<x>a)b","ac",true),None);
+        let (dfa,_) = dfa_for(&Pattern::parse("(?<x>a)b").unwrap());
+        assert_eq!(dfa.tags.names,vec!["x".to_string()]);
+    }
+
+    #[test]
+    fn captures_minimized() {
+        let regex     = "(?<x>a|ab)(?<y>b?)c|(?<y>[ab]+)d";
+        let (dfa,end) = dfa_for(&Pattern::parse(regex).unwrap());
+        let minimized = dfa.minimize();
+        for input in &["abc","abbc","ac","abd","bbad"] {
+            let expected = dfa.captures(input,true,end);
+            assert!(expected.is_some());
+            assert_eq!(minimized.captures(input,true,end),expected,"{:?}",input);
+        }
+    }
+
+    #[test]
+    fn captures_without_tags() {
+        let (dfa,_) = dfa_for(&Pattern::parse("(a|b)*c").unwrap());
+        assert_eq!(dfa.tags,default());
+    }
+
+    #[test]
+    fn sequential_copies_break_cycles() {
+        let copies     = vec![(1,2),(2,1),(3,4)];
+        let operations = sequential_copies(copies,5);
+        let mut registers = Registers::default();
+        registers.apply(&[Operation::Set(1)],10);
+        registers.apply(&[Operation::Set(2)],20);
+        registers.apply(&[Operation::Set(4)],40);
+        registers.apply(&operations,0);
+        let values = (1..=4).map(|register| registers.get(register)).collect_vec();
+        assert_eq!(values,vec![Some(20),Some(10),Some(40),Some(40)]);
+    }
+}
@@ -1,5 +1,7 @@
 //! Implementation of core data structures.
 
+pub mod comb;
 pub mod matrix;
 
+pub use comb::CombMatrix;
 pub use matrix::Matrix;
@@ -0,0 +1,256 @@
+//! A compressed representation of a sparse 2D matrix.
+
+use crate::prelude::*;
+
+use crate::data::matrix::Matrix;
+
+use std::cmp::Reverse;
+use std::mem::size_of;
+use std::ops::Index;
+
+
+
+// ==================
+// === CombMatrix ===
+// ==================
+
+/// A read-only 2D matrix compressed with row displacement, also known as a comb vector.
+///
+/// Each row is encoded as a set of differences, either from its default value, which is the value
+/// that occurs in it most often, or from another row used as its template. The differences of all
+/// the rows are packed into a single vector, with every row placed at a displacement chosen so
+/// that its values fall into the gaps between the values of the other rows, like the teeth of
+/// interleaved combs. Each slot of the packed vector remembers the row it belongs to, so that a
+/// lookup is a constant time operation:
+///
+/// ```text
+/// value(row,column) = if      check[base[row] + column] == row { values[base[row] + column] }
+///                     else if template[row] exists          { value(template[row],column) }
+///                     else                                      { defaults[row]               }
+/// ```
+///
+/// The rows used as templates have no templates themselves. This representation is well suited for
+/// transition tables of automata, where most of the transitions from a state lead to the same
+/// target, and where many states differ only in a few transitions, like the states of an
+/// identifier that can also be a prefix of a keyword.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
+pub struct CombMatrix<T> {
+    rows      : usize,
+    columns   : usize,
+    defaults  : Vec<T>,
+    templates : Vec<usize>,
+    base      : Vec<usize>,
+    check     : Vec<usize>,
+    values    : Vec<T>,
+}
+
+impl<T:Copy> CombMatrix<T> {
+    /// The marker of the slots of the packed vector that do not belong to any row, and of the rows
+    /// that have no template.
+    const EMPTY : usize = usize::max_value();
+
+    /// Get the number of rows in the matrix.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Get the number of columns in the matrix.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Indexing with bounds checking.
+    pub fn safe_index(&self, row:usize, column:usize) -> Option<T> {
+        (row < self.rows && column < self.columns).as_some_from(|| self[(row,column)])
+    }
+
+    /// The number of slots in the packed vector.
+    pub fn packed_len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// The number of bytes occupied by the contents of the matrix, not counting the size of the
+    /// matrix structure itself.
+    pub fn memory_size(&self) -> usize {
+        let values  = (self.defaults.len() + self.values.len()) * size_of::<T>();
+        let indices = self.templates.len() + self.base.len() + self.check.len();
+        let indices = indices * size_of::<usize>();
+        values + indices
+    }
+
+    /// Decompress the matrix.
+    pub fn to_matrix(&self) -> Matrix<T> {
+        let matrix = (0..self.rows).flat_map(|row| {
+            (0..self.columns).map(move |column| self[(row,column)])
+        }).collect();
+        Matrix {rows:self.rows,columns:self.columns,matrix}
+    }
+}
+
+
+// === Trait Impls ===
+
+impl<T:Copy> Index<(usize,usize)> for CombMatrix<T> {
+    type Output = T;
+    fn index(&self, (row,column):(usize,usize)) -> &T {
+        let mut row = row;
+        loop {
+            let ix = self.base[row] + column;
+            if self.check.get(ix) == Some(&row) { return &self.values[ix] }
+            match self.templates[row] {
+                template if template == Self::EMPTY => return &self.defaults[row],
+                template                            => row = template,
+            }
+        }
+    }
+}
+
+impl<T:Copy+Default+Eq+Hash> From<&Matrix<T>> for CombMatrix<T> {
+    /// Compress the `matrix`.
+    ///
+    /// Every row uses as its template the earlier row that it differs from the least, unless it
+    /// differs from its default value even less. The rows are then placed in the first position
+    /// that fits, starting from the rows with the most differences.
+    fn from(matrix:&Matrix<T>) -> Self {
+        let rows     = matrix.rows;
+        let columns  = matrix.columns;
+        let row      = |row:usize| &matrix.matrix[row*columns..(row+1)*columns];
+        // Ties are broken by the first occurrence, so that the compression is deterministic.
+        let defaults = (0..rows).map(|ix| {
+            let mut counts:HashMap<T,(usize,Reverse<usize>)> = default();
+            for (column,&value) in row(ix).iter().enumerate() {
+                counts.entry(value).or_insert((0,Reverse(column))).0 += 1;
+            }
+            let most_common = counts.into_iter().max_by_key(|&(_,count)| count);
+            most_common.map(|(value,_)| value).unwrap_or_default()
+        }).collect_vec();
+        let differences = |ix:usize, template:Option<usize>| {
+            let differences = row(ix).iter().enumerate().filter(|&(column,value)| match template {
+                Some(template) => *value != row(template)[column],
+                None           => *value != defaults[ix],
+            });
+            differences.map(|(column,_)| column).collect_vec()
+        };
+        let mut templates     = vec![Self::EMPTY;rows];
+        let mut template_rows = vec![];
+        let entries           = (0..rows).map(|ix| {
+            let own     = differences(ix,None);
+            let similar = template_rows.iter().map(|&template| {
+                (differences(ix,Some(template)),template)
+            }).min_by_key(|(entries,_)| entries.len());
+            match similar {
+                Some((entries,template)) if entries.len() < own.len() => {
+                    templates[ix] = template;
+                    entries
+                },
+                _ => {
+                    template_rows.push(ix);
+                    own
+                },
+            }
+        }).collect_vec();
+        let mut order = (0..rows).collect_vec();
+        order.sort_by_key(|&ix| Reverse(entries[ix].len()));
+
+        let mut base       = vec![0;rows];
+        let mut check      = Vec::<usize>::new();
+        let mut values     = Vec::<T>::new();
+        let mut first_free = 0_usize;
+        for ix in order {
+            let entries = &entries[ix];
+            let first   = match entries.first() {
+                Some(&first) => first,
+                None         => continue,
+            };
+            let fits = |offset:usize| entries.iter().all(|column| {
+                check.get(offset + column).copied().unwrap_or(Self::EMPTY) == Self::EMPTY
+            });
+            let mut offset = first_free.saturating_sub(first);
+            while !fits(offset) {
+                offset += 1;
+            }
+            let end = offset + entries.last().unwrap() + 1;
+            if check.len() < end {
+                check.resize(end,Self::EMPTY);
+                values.resize(end,default());
+            }
+            for &column in entries {
+                check[offset + column]  = ix;
+                values[offset + column] = row(ix)[column];
+            }
+            base[ix] = offset;
+            while check.get(first_free).copied().unwrap_or(Self::EMPTY) != Self::EMPTY {
+                first_free += 1;
+            }
+        }
+        Self {rows,columns,defaults,templates,base,check,values}
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(rows:Vec<Vec<usize>>) -> Matrix<usize> {
+        let columns = rows.first().map_or(0,|row| row.len());
+        Matrix {rows:rows.len(),columns,matrix:rows.concat()}
+    }
+
+    #[test]
+    fn round_trip() {
+        let matrix = matrix(vec!
+            [ vec![0,0,1,0,0,0,2,0]
+            , vec![3,3,3,3,3,3,3,3]
+            , vec![0,4,0,0,5,0,0,0]
+            , vec![6,7,6,6,6,6,6,8]
+            , vec![0,0,0,0,0,0,0,0]
+            ]);
+        let comb = CombMatrix::from(&matrix);
+        assert_eq!(comb.to_matrix(),matrix);
+        assert_eq!(comb.safe_index(3,7),Some(8));
+        assert_eq!(comb.safe_index(3,8),None);
+        assert_eq!(comb.safe_index(5,0),None);
+    }
+
+    #[test]
+    fn rows_are_interleaved() {
+        let matrix = matrix(vec!
+            [ vec![1,0,1,0,1,0]
+            , vec![0,2,0,2,0,0]
+            , vec![0,0,0,0,0,3]
+            ]);
+        let comb = CombMatrix::from(&matrix);
+        assert_eq!(comb.to_matrix(),matrix);
+        assert_eq!(comb.packed_len(),7);
+    }
+
+    #[test]
+    fn similar_rows_use_templates() {
+        let matrix = matrix(vec!
+            [ vec![1,2,1,2,1,2,1,2]
+            , vec![1,2,1,2,1,2,1,3]
+            , vec![4,2,1,2,1,2,1,2]
+            , vec![0,0,0,0,0,0,0,0]
+            ]);
+        let comb  = CombMatrix::from(&matrix);
+        let empty = CombMatrix::<usize>::EMPTY;
+        assert_eq!(comb.to_matrix(),matrix);
+        assert_eq!(comb.templates,vec![empty,0,0,empty]);
+        assert_eq!(comb.check.iter().filter(|&&owner| owner != empty).count(),6);
+    }
+
+    #[test]
+    fn empty() {
+        let matrix = Matrix::<usize>::default();
+        let comb   = CombMatrix::from(&matrix);
+        assert_eq!(comb.to_matrix(),matrix);
+        assert_eq!(comb.memory_size(),0);
+    }
+}
@@ -13,6 +13,7 @@ use std::ops::IndexMut;
 
 /// An efficient 2D matrix implemented on top of [`std::vec::Vec`].
 #[derive(Clone,Debug,Default,PartialEq,Eq)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct Matrix<T> {
     pub rows    : usize,
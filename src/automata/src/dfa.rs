@@ -2,12 +2,35 @@
 
 use crate::prelude::*;
 
+use crate::symbol::Names;
 use crate::symbol::Symbol;
 use crate::alphabet;
+use crate::capture;
+use crate::capture::Captures;
+use crate::capture::Configuration;
+use crate::capture::Groups;
+use crate::capture::Registers;
 use crate::state;
 use crate::data::matrix::Matrix;
 use crate::nfa;
 use crate::nfa::Nfa;
+use crate::visualization::Diagram;
+
+use lazy_reader::BookmarkManager;
+use lazy_reader::ReaderOps;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::collections::hash_map::Entry;
+use std::iter;
+use std::ops::RangeInclusive;
+
+pub mod compressed;
+pub mod examples;
+pub mod lazy;
+
+pub use compressed::CompressedDfa;
+pub use examples::Examples;
+pub use lazy::LazyDfa;
 
 
 
@@ -36,6 +59,7 @@ pub type State = state::State<Dfa>;
 ///  └───┘       └───┘       └───┘       └───┘
 /// ```
 #[derive(Clone,Debug,Default,Eq,PartialEq)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
 pub struct Dfa {
     /// A set of disjoint intervals over the allowable input alphabet.
     pub alphabet : alphabet::SealedSegmentation,
@@ -56,6 +80,9 @@ pub struct Dfa {
     pub links : Matrix<State>,
     /// For each DFA state contains a list of NFA states it was constructed from.
     pub sources : Vec<Vec<nfa::State>>,
+    /// The operations on the registers recording the capture groups, carried by the transitions
+    /// and the accepting states. It is empty for the automata without capture groups.
+    pub tags : capture::Tags,
 }
 
 impl Dfa {
@@ -64,27 +91,619 @@ impl Dfa {
 }
 
 impl Dfa {
+    /// Iterate over the states of the automaton, in the order of the rows of the transition
+    /// matrix.
+    pub fn states(&self) -> impl Iterator<Item=State> {
+        self.links.row_indices().map(State::new)
+    }
+
     /// Simulate the DFA transition with the provided input symbol.
+    ///
+    /// The automata without transitions on a line anchor stay in the `current_state` on it.
     pub fn next_state(&self, current_state:State, symbol:&Symbol) -> State {
+        if symbol.is_anchor() && self.alphabet.own_division(symbol).is_none() {
+            return current_state
+        }
         let ix = self.alphabet.index_of_symbol(&symbol);
         self.links.safe_index(current_state.id(),ix).unwrap_or_default()
     }
 
+    /// Simulate the DFA transition with the provided input symbol just like [`Dfa::next_state`],
+    /// applying the operations of the transition to the `registers` at the `position` in the
+    /// input.
+    pub fn next_state_with
+    (&self, current_state:State, symbol:&Symbol, registers:&mut Registers, position:usize)
+    -> State {
+        let stays = symbol.is_anchor() && self.alphabet.own_division(symbol).is_none();
+        if !stays {
+            let column = self.alphabet.index_of_symbol(symbol);
+            registers.apply(self.tags.transition(current_state,column),position);
+        }
+        self.next_state(current_state,symbol)
+    }
+
+    /// The ranges of symbols covered by the divisions of the alphabet, in the order of the
+    /// columns of the transition matrix.
+    pub(crate) fn division_ranges(&self) -> Vec<RangeInclusive<Symbol>> {
+        let divisions = self.alphabet.division_map.keys().copied().collect_vec();
+        divisions.iter().enumerate().map(|(ix,start)| {
+            let next = divisions.get(ix+1);
+            let end  = next.map(|next| Symbol::from(next.index - 1)).unwrap_or_else(Symbol::max);
+            *start..=end
+        }).collect()
+    }
+
     /// Convert the automata to GraphViz Dot code for the deubgging purposes.
+    ///
+    /// Use [`Diagram`] to highlight the accepting states, or to render the automaton in other
+    /// formats.
     pub fn as_graphviz_code(&self) -> String {
-        let mut out = String::new();
-        for row in 0 .. self.links.rows {
-            out += &format!("node_{}[label=\"{}\"]\n",row,row);
-            for column in 0 .. self.links.columns {
-                let state = self.links[(row,column)];
-                if !state.is_invalid() {
-                    out += &format!("node_{} -> node_{}\n",row,state.id());
+        Diagram::graphviz().render_dfa(self)
+    }
+}
+
+
+// === Minimization ===
+
+impl Dfa {
+    /// Minimize the automaton, merging states that have identical [`Dfa::sources`].
+    ///
+    /// This is the most conservative form of minimization, as it has no knowledge of which sources
+    /// are accepting. Use [`Dfa::minimize_by`] to merge states that are accepting for the same
+    /// reason but were constructed from different NFA states.
+    pub fn minimize(&self) -> Self {
+        self.minimize_by(|state| self.sources[state.id()].clone())
+    }
+
+    /// Minimize the automaton using [Hopcroft's algorithm](https://en.wikipedia.org/wiki/DFA_minimization#Hopcroft's_algorithm).
+    ///
+    /// Two states are merged only if they share the same `class` and if, for every input, they
+    /// transition to states that are merged as well. The `class` of a state is thus the
+    /// information that must be kept distinct in the resulting automaton (e.g. the rule accepted in
+    /// that state). The sources of the merged states are combined, and the resulting states are
+    /// numbered in the breadth-first order of their discovery from [`Dfa::START_STATE`], just as
+    /// they are by the NFA -> DFA conversion. The states are merged only if their [`Dfa::tags`]
+    /// are the same as well, so that the merged states keep the operations on the registers.
+    ///
+    /// The asymptotic complexity is `O(n * k * log n)`, where `n` is the number of states and `k`
+    /// is the number of alphabet divisions.
+    pub fn minimize_by<K:Eq+Hash>(&self, class:impl Fn(State) -> K) -> Self {
+        let rows    = self.links.rows;
+        let columns = self.links.columns;
+        // The invalid state is represented explicitly as the last state, so that the transition
+        // function is total.
+        let dead    = rows;
+        let target  = |row:usize, column:usize| {
+            let state = self.links[(row,column)];
+            if state.is_invalid() { dead } else { state.id() }
+        };
+        let mut inverse = vec![vec![vec![];rows+1];columns];
+        for row in 0..rows {
+            for column in 0..columns {
+                inverse[column][target(row,column)].push(row);
+            }
+        }
+
+        let tags_class = |row:usize| {
+            let transitions = self.tags.transitions.get(row);
+            let finals      = self.tags.finals.get(row);
+            (class(State::new(row)),transitions,finals)
+        };
+        let mut blocks:Vec<Vec<usize>> = default();
+        let mut class_blocks           = HashMap::new();
+        let mut block_of = (0..rows).map(|row| {
+            let block = *class_blocks.entry(tags_class(row)).or_insert_with(|| {
+                blocks.push(default());
+                blocks.len() - 1
+            });
+            blocks[block].push(row);
+            block
+        }).collect_vec();
+        blocks.push(vec![dead]);
+        block_of.push(blocks.len() - 1);
+
+        let mut work_list:Vec<(usize,usize)>        = default();
+        let mut in_work_list:HashSet<(usize,usize)> = default();
+        for block in 0..blocks.len() {
+            for column in 0..columns {
+                work_list.push((block,column));
+                in_work_list.insert((block,column));
+            }
+        }
+        while let Some(splitter) = work_list.pop() {
+            in_work_list.remove(&splitter);
+            let (splitter_block,column)                = splitter;
+            let mut touched:BTreeMap<usize,Vec<usize>> = default();
+            for &state in &blocks[splitter_block] {
+                for &source in &inverse[column][state] {
+                    touched.entry(block_of[source]).or_default().push(source);
                 }
             }
+            for (block,mut inside) in touched {
+                inside.sort_unstable();
+                inside.dedup();
+                if inside.len() == blocks[block].len() { continue }
+                let outside = blocks[block].iter().copied().filter(|state| {
+                    inside.binary_search(state).is_err()
+                }).collect_vec();
+                let new_block    = blocks.len();
+                let (kept,moved) = if inside.len() <= outside.len() {
+                    (outside,inside)
+                } else {
+                    (inside,outside)
+                };
+                for &state in &moved {
+                    block_of[state] = new_block;
+                }
+                blocks[block] = kept;
+                blocks.push(moved);
+                for column in 0..columns {
+                    let entry = if in_work_list.contains(&(block,column)) {
+                        (new_block,column)
+                    } else if blocks[block].len() <= blocks[new_block].len() {
+                        (block,column)
+                    } else {
+                        (new_block,column)
+                    };
+                    if in_work_list.insert(entry) {
+                        work_list.push(entry);
+                    }
+                }
+            }
+        }
+
+        let dead_block      = block_of[dead];
+        let mut block_state = vec![State::INVALID;blocks.len()];
+        let mut order       = vec![];
+        if rows > 0 {
+            block_state[block_of[0]] = Dfa::START_STATE;
+            order.push(block_of[0]);
+        }
+        let mut links = Matrix::new(0,columns);
+        let mut ix    = 0;
+        while ix < order.len() {
+            let block          = order[ix];
+            let representative = blocks[block][0];
+            links.new_row();
+            for column in 0..columns {
+                let target_block = block_of[target(representative,column)];
+                if target_block != dead_block {
+                    if block_state[target_block].is_invalid() {
+                        block_state[target_block] = State::new(order.len());
+                        order.push(target_block);
+                    }
+                    links[(ix,column)] = block_state[target_block];
+                }
+            }
+            ix += 1;
         }
-        let opts = "node [shape=circle style=filled fillcolor=\"#4385f5\" fontcolor=\"#FFFFFF\" \
-                    color=white penwidth=5.0 margin=0.1 width=0.5 height=0.5 fixedsize=true]";
-        format!("digraph G {{\n{}\n{}\n}}\n",opts,out)
+        let sources = order.iter().map(|&block| {
+            let mut sources = blocks[block].iter().flat_map(|&state| {
+                self.sources[state].iter().copied()
+            }).collect_vec();
+            sources.sort_unstable();
+            sources.dedup();
+            sources
+        }).collect_vec();
+        let representatives = order.iter().map(|&block| blocks[block][0]).collect_vec();
+        let transitions     = representatives.iter().filter_map(|&row| {
+            self.tags.transitions.get(row).cloned()
+        }).collect();
+        let finals          = representatives.iter().filter_map(|&row| {
+            self.tags.finals.get(row).cloned()
+        }).collect();
+        let names    = self.tags.names.clone();
+        let tags     = capture::Tags {names,transitions,finals};
+        let alphabet = self.alphabet.clone();
+        Dfa {alphabet,links,sources,tags}
+    }
+}
+
+
+// === Language Analysis ===
+
+/// A shortest input on which an analysis of the languages accepted by automata failed.
+///
+/// See [`Dfa::is_equivalent`] for the meaning of the counterexample in each of the analyses.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct Counterexample {
+    /// The symbols of the input.
+    pub symbols : Vec<Symbol>,
+}
+
+impl Counterexample {
+    /// Display the input as in the [`Display`] implementation, with the symbols that have a name
+    /// in the `names` displayed as that name in angle brackets instead.
+    pub fn display_with(&self, names:&Names) -> String {
+        self.symbols.iter().map(|symbol| {
+            let char = symbol.char();
+            match char {
+                _ if names.get(*symbol).is_some() => format!("<{}>",names.name(*symbol)),
+                Some(char)                        => char.to_string(),
+                None if *symbol == Symbol::eof()  => "<eof>".into(),
+                None                              => format!("<{}>",symbol.index),
+            }
+        }).collect()
+    }
+}
+
+impl Display for Counterexample {
+    /// Displays the input as a string, with [`Symbol::eof`] displayed as `<eof>` and all the other
+    /// symbols that are not characters displayed as their index in angle brackets.
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{}",self.display_with(&default()))
+    }
+}
+
+impl Dfa {
+    /// Check whether the automaton accepts the same language as the `other` automaton.
+    ///
+    /// An input is accepted by an automaton if it leads from [`Dfa::START_STATE`] to a state with
+    /// any of its [`Dfa::sources`] contained in `accepting` (respectively in `other_accepting`).
+    /// Unlike in [`Dfa::longest_match`], [`Symbol::eof`] is not implicitly appended to the input,
+    /// and is treated as any other symbol instead. The line anchors are handled just as in
+    /// [`Dfa::longest_match`], with the input starting at the start of a line and ending at the end
+    /// of a line. If the languages differ, the shortest input that is accepted by exactly one of
+    /// the automata is returned.
+    pub fn is_equivalent
+    (&self, accepting:&[nfa::State], other:&Dfa, other_accepting:&[nfa::State])
+    -> Result<(),Counterexample> {
+        self.check_product(accepting,other,other_accepting,|this,other| this != other)
+    }
+
+    /// Check whether every input accepted by the automaton is also accepted by the `other`
+    /// automaton.
+    ///
+    /// If it is not, the shortest input that is accepted by the automaton but not by the `other`
+    /// automaton is returned. See [`Dfa::is_equivalent`] for the meaning of acceptance.
+    pub fn is_subset_of
+    (&self, accepting:&[nfa::State], other:&Dfa, other_accepting:&[nfa::State])
+    -> Result<(),Counterexample> {
+        self.check_product(accepting,other,other_accepting,|this,other| this && !other)
+    }
+
+    /// Check whether the automaton accepts no input at all.
+    ///
+    /// If it accepts some input, the shortest accepted input is returned. See
+    /// [`Dfa::is_equivalent`] for the meaning of acceptance.
+    pub fn is_empty(&self, accepting:&[nfa::State]) -> Result<(),Counterexample> {
+        self.is_subset_of(accepting,&Dfa::default(),&[])
+    }
+
+    /// Check whether the automaton accepts the empty input. See [`Dfa::is_equivalent`] for the
+    /// meaning of acceptance.
+    pub fn accepts_empty_string(&self, accepting:&[nfa::State]) -> bool {
+        self.accepts_input_ending_in(self.line_start_state(),accepting)
+    }
+
+    /// Check whether the input leading to the `state` is accepted, with the transition on
+    /// [`Symbol::line_end`] taken at its end.
+    fn accepts_input_ending_in(&self, state:State, accepting:&[nfa::State]) -> bool {
+        let line_end = self.next_state(state,&Symbol::line_end());
+        self.is_accepting(state,accepting) || self.is_accepting(line_end,accepting)
+    }
+
+    /// Check whether the `state` is accepting. See [`Dfa::is_equivalent`] for details.
+    fn is_accepting(&self, state:State, accepting:&[nfa::State]) -> bool {
+        let sources = self.sources.get(state.id()).map(|sources| sources.iter());
+        sources.map(|mut sources| sources.any(|source| accepting.contains(source))) == Some(true)
+    }
+
+    /// The start state of the automaton, or [`State::INVALID`] if the automaton has no states.
+    fn start_state(&self) -> State {
+        if self.links.rows > 0 { Dfa::START_STATE } else { State::INVALID }
+    }
+
+    /// The state reached from the start state on an input starting at the start of a line, or
+    /// [`State::INVALID`] if the automaton has no states.
+    fn line_start_state(&self) -> State {
+        let start = self.start_state();
+        if start.is_invalid() { start } else { start_at(&mut &*self,true) }
+    }
+
+    /// Run both automata in lockstep, searching in breadth-first order for the shortest input after
+    /// which the acceptance of the automata is `rejected`.
+    ///
+    /// Both alphabets are merged, so that the automata can be run over the divisions of the merged
+    /// alphabet, represented by one of their symbols each. The line anchors are never a part of
+    /// the input, so that their divisions are skipped.
+    fn check_product
+    ( &self
+    , accepting       : &[nfa::State]
+    , other           : &Dfa
+    , other_accepting : &[nfa::State]
+    , rejected        : impl Fn(bool,bool) -> bool
+    ) -> Result<(),Counterexample> {
+        let divisions = self.alphabet.keys().chain(other.alphabet.keys()).cloned();
+        let divisions = divisions.collect::<BTreeSet<_>>().into_iter().collect_vec();
+        let ends      = divisions.iter().skip(1).map(|next| next.index - 1);
+        let ends      = ends.chain(iter::once(Symbol::max().index));
+        let inputs    = divisions.iter().zip(ends).filter(|(start,_)| !start.is_anchor());
+        let inputs    = inputs.map(|(start,end)| {
+            // Prefer printable characters, to make the counterexamples readable.
+            let printable = start.index.max('!' as u64);
+            let printable = printable <= end && printable <= '~' as u64;
+            if printable { Symbol::from(start.index.max('!' as u64)) } else { *start }
+        }).collect_vec();
+
+        let step = |dfa:&Dfa, state:State, symbol:&Symbol| {
+            if state.is_invalid() { state } else { dfa.next_state(state,symbol) }
+        };
+        let start       = (self.line_start_state(),other.line_start_state());
+        let mut parents = HashMap::<(State,State),Option<((State,State),usize)>>::new();
+        let mut queue   = VecDeque::new();
+        parents.insert(start,None);
+        queue.push_back(start);
+        while let Some(pair) = queue.pop_front() {
+            let (this,that)    = pair;
+            let this_accepting = self.accepts_input_ending_in(this,accepting);
+            let that_accepting = other.accepts_input_ending_in(that,other_accepting);
+            if rejected(this_accepting,that_accepting) {
+                let mut symbols = vec![];
+                let mut current = pair;
+                while let Some((parent,symbol)) = parents[&current] {
+                    symbols.push(inputs[symbol]);
+                    current = parent;
+                }
+                symbols.reverse();
+                return Err(Counterexample {symbols})
+            }
+            if this.is_invalid() && that.is_invalid() { continue }
+            for (ix,symbol) in inputs.iter().enumerate() {
+                let next = (step(self,this,symbol),step(other,that,symbol));
+                if let Entry::Vacant(entry) = parents.entry(next) {
+                    entry.insert(Some((pair,ix)));
+                    queue.push_back(next);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+
+// === Matching ===
+
+/// A match found by running a [`Dfa`] over some input.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct Match {
+    /// The length of the matched input, in bytes.
+    pub length : usize,
+    /// The DFA state in which the match ended.
+    ///
+    /// For a [`LazyDfa`] this is the identifier of the state in its cache, which is only valid
+    /// until the cache is cleared, or [`State::INVALID`] if the match was found by simulating the
+    /// NFA.
+    pub state : State,
+    /// The accepting NFA states that the final DFA state was constructed from.
+    pub sources : Vec<nfa::State>,
+}
+
+/// An automaton that can be run over the input one symbol at a time.
+///
+/// It abstracts over the eagerly and the lazily constructed DFAs, so that both can share the
+/// implementation of matching.
+pub(crate) trait Automaton {
+    /// The position of the automaton in its states.
+    type Cursor;
+    /// The cursor pointing to the start state.
+    fn start(&mut self) -> Self::Cursor;
+    /// Transition from the `cursor` on `symbol`, returning `None` if the input is rejected.
+    fn step(&mut self, cursor:&Self::Cursor, symbol:&Symbol) -> Option<Self::Cursor>;
+    /// Transition from the `cursor` on `symbol` just like [`Automaton::step`], but keeping the
+    /// `cursor` valid, so that the search can continue from it.
+    fn peek(&mut self, cursor:&Self::Cursor, symbol:&Symbol) -> Option<Self::Cursor> {
+        self.step(cursor,symbol)
+    }
+    /// Create a match of the given `length` if the state under `cursor` is accepting.
+    fn accept(&self, cursor:&Self::Cursor, length:usize, accepting:&[nfa::State]) -> Option<Match>;
+}
+
+impl Automaton for &Dfa {
+    type Cursor = State;
+
+    fn start(&mut self) -> State {
+        Dfa::START_STATE
+    }
+
+    fn step(&mut self, cursor:&State, symbol:&Symbol) -> Option<State> {
+        let state = self.next_state(*cursor,symbol);
+        (!state.is_invalid()).as_some(state)
+    }
+
+    fn accept(&self, cursor:&State, length:usize, accepting:&[nfa::State]) -> Option<Match> {
+        accept_state(&self.sources,*cursor,length,accepting)
+    }
+}
+
+/// Create a match of the given `length` if the `state` of an automaton with the given `sources`
+/// is accepting.
+pub(crate) fn accept_state
+(sources:&[Vec<nfa::State>], state:State, length:usize, accepting:&[nfa::State]) -> Option<Match> {
+    let sources = sources.get(state.id())?.iter();
+    let sources = sources.filter(|source| accepting.contains(source)).copied().collect_vec();
+    (!sources.is_empty()).as_some_from(|| Match {length,state,sources})
+}
+
+/// Find the longest prefix of `input` that is accepted by the `automaton`.
+pub(crate) fn longest_match<A:Automaton>
+(automaton:&mut A, input:&str, accepting:&[nfa::State]) -> Option<Match> {
+    let symbols = input.chars().map(|char| (Symbol::from(char),char.len_utf8()));
+    longest_match_symbols(automaton,symbols,accepting)
+}
+
+/// Find the longest prefix of the `symbols`, paired with their lengths in bytes, that is accepted
+/// by the `automaton`. The [`Symbol::eof`] is appended to the `symbols`.
+///
+/// The `symbols` start at the start of a line.
+pub(crate) fn longest_match_symbols<A:Automaton>
+(automaton:&mut A, symbols:impl Iterator<Item=(Symbol,usize)>, accepting:&[nfa::State])
+-> Option<Match> {
+    let symbols    = symbols.chain(iter::once((Symbol::eof(),0)));
+    let mut cursor = start_at(automaton,true);
+    let mut length = 0;
+    let mut result = automaton.accept(&cursor,length,accepting);
+    for (symbol,size) in symbols {
+        if let Some(found) = accept_line_end(automaton,&cursor,&symbol,length,accepting) {
+            result = Some(found);
+        }
+        match automaton.step(&cursor,&symbol) {
+            Some(next) => cursor = next,
+            None       => break,
+        }
+        length += size;
+        if let Some(found) = automaton.accept(&cursor,length,accepting) {
+            result = Some(found);
+        }
+    }
+    result
+}
+
+/// The cursor pointing to the start state of the `automaton`, after the transition on
+/// [`Symbol::line_start`] if the input starts at the start of a line.
+fn start_at<A:Automaton>(automaton:&mut A, line_start:bool) -> A::Cursor {
+    let cursor = automaton.start();
+    if !line_start { return cursor }
+    automaton.step(&cursor,&Symbol::line_start()).unwrap_or(cursor)
+}
+
+/// Create a match of the given `length` if the `symbol` ends a line, and the state reached from
+/// the `cursor` through the transition on [`Symbol::line_end`] is accepting.
+///
+/// The transition is only [peeked](Automaton::peek), as the search continues from the `cursor`.
+fn accept_line_end<A:Automaton>
+(automaton:&mut A, cursor:&A::Cursor, symbol:&Symbol, length:usize, accepting:&[nfa::State])
+-> Option<Match> {
+    let ends_line = *symbol == Symbol::from('\n') || *symbol == Symbol::eof();
+    if !ends_line { return None }
+    let line_end = automaton.peek(cursor,&Symbol::line_end())?;
+    automaton.accept(&line_end,length,accepting)
+}
+
+/// Check whether the input of the `reader` is at the start of a line, judging by its result.
+pub(crate) fn starts_line<R:ReaderOps>(reader:&R) -> bool {
+    reader.result().chars().last().map(|char| char == '\n').unwrap_or(true)
+}
+
+/// Find the longest match for the `automaton` in the input provided by the `reader`, starting at
+/// the start of a line if `line_start` is set.
+pub(crate) fn longest_match_in<A:Automaton,R:ReaderOps>
+( automaton  : &mut A
+, reader     : &mut R
+, bookmarks  : &mut BookmarkManager
+, accepting  : &[nfa::State]
+, line_start : bool
+) -> Option<Match> {
+    let bookmark        = bookmarks.matched_bookmark;
+    let start           = reader.result().len();
+    let mut cursor      = start_at(automaton,line_start);
+    let mut result      = automaton.accept(&cursor,0,accepting);
+    let mut matched_len = start;
+    let mut matched_eof = reader.character().is_eof();
+    let mut overshot    = false;
+    bookmarks.bookmark(bookmark,reader);
+    loop {
+        let char   = reader.character();
+        let symbol = Symbol::from(u64::from(char));
+        let length = reader.result().len() - start;
+        if let Some(found) = accept_line_end(automaton,&cursor,&symbol,length,accepting) {
+            bookmarks.bookmark(bookmark,reader);
+            matched_len = reader.result().len();
+            matched_eof = char.is_eof();
+            overshot    = false;
+            result      = Some(found);
+        }
+        match automaton.step(&cursor,&symbol) {
+            Some(next) => cursor = next,
+            None       => break,
+        }
+        if let Ok(char) = char.char { reader.append_result(char) }
+        reader.advance_char(bookmarks);
+        overshot   = true;
+        let length = reader.result().len() - start;
+        if let Some(found) = automaton.accept(&cursor,length,accepting) {
+            bookmarks.bookmark(bookmark,reader);
+            matched_len = reader.result().len();
+            matched_eof = reader.character().is_eof();
+            overshot    = false;
+            result      = Some(found);
+        }
+        if char.is_eof() { break }
+    }
+    if overshot {
+        // The reader does not track the position of the end of the input, so there is nothing
+        // to rewind to other than the result.
+        if matched_eof { reader.truncate_match(matched_len) }
+        else           { bookmarks.rewind(bookmark,reader) }
+    }
+    result
+}
+
+impl Dfa {
+    /// Find the longest prefix of `input` that is accepted by the automaton.
+    ///
+    /// A DFA state is accepting if any of its [`Dfa::sources`] is contained in `accepting`. The end
+    /// of the input is fed to the automaton as [`Symbol::eof`], so that rules triggering on it can
+    /// be tested as well. The input starts at the start of a line, and the transitions on the line
+    /// anchors are taken as described in [`Symbol::line_start`] and [`Symbol::line_end`].
+    pub fn longest_match(&self, input:&str, accepting:&[nfa::State]) -> Option<Match> {
+        longest_match(&mut &*self,input,accepting)
+    }
+
+    /// Check whether the whole `input` is accepted by the automaton.
+    ///
+    /// See [`Dfa::longest_match`] for the meaning of `accepting`.
+    pub fn matches(&self, input:&str, accepting:&[nfa::State]) -> bool {
+        let result = self.longest_match(input,accepting);
+        result.map(|found| found.length == input.len()).unwrap_or(false)
+    }
+
+    /// Get the spans of the capture groups of the pattern ending in the `accepting` NFA state on
+    /// the whole `input`, which `line_start` tells whether it starts a line.
+    ///
+    /// The spans are recorded by the operations of the [`Dfa::tags`] while the automaton runs over
+    /// the `input`. The end of the `input` is taken to end a line, and the [`Symbol::eof`] is fed
+    /// to the automaton if it does not accept otherwise. Returns `None` if the pattern does not
+    /// trigger on the `input`.
+    pub fn captures(&self, input:&str, line_start:bool, accepting:nfa::State) -> Option<Captures> {
+        let mut registers = Registers::default();
+        let mut state     = self.start_state();
+        if line_start {
+            let next = self.next_state_with(state,&Symbol::line_start(),&mut registers,0);
+            if !next.is_invalid() { state = next }
+        }
+        for (offset,char) in input.char_indices() {
+            state = self.next_state_with(state,&Symbol::from(char),&mut registers,offset);
+        }
+        let end             = input.len();
+        let accepts         = |state:State| self.is_accepting(state,&[accepting]);
+        let mut at_line_end = registers.clone();
+        let line_end        = self.next_state_with(state,&Symbol::line_end(),&mut at_line_end,end);
+        let (state,mut registers) = if accepts(line_end) { (line_end,at_line_end) } else {
+            let eof = self.next_state_with(state,&Symbol::eof(),&mut registers,end);
+            (eof,registers)
+        };
+        if !accepts(state) { return None }
+        registers.apply(self.tags.finals(state,accepting),end);
+        let groups = Groups::new(self.tags.names.iter().map(String::as_str).zip(0..));
+        Some(groups.captures(&registers))
+    }
+
+    /// Find the longest match for the automaton in the input provided by the `reader`.
+    ///
+    /// The current character of the reader is the first one to be matched, just as in the lexers
+    /// generated by the flexer. The matched characters are appended to the result of the reader,
+    /// which is left positioned right after the match, or where it started if there is no match.
+    /// The [`BookmarkManager::matched_bookmark`] is used to remember the end of the longest match
+    /// found so far. See [`Dfa::longest_match`] for the meaning of `accepting`.
+    ///
+    /// The match starts at the start of a line if the result of the reader is empty or ends with a
+    /// `'\n'`, as the reader does not remember the characters before its result.
+    pub fn longest_match_in<R:ReaderOps>
+    (&self, reader:&mut R, bookmarks:&mut BookmarkManager, accepting:&[nfa::State])
+    -> Option<Match> {
+        let line_start = starts_line(reader);
+        longest_match_in(&mut &*self,reader,bookmarks,accepting,line_start)
     }
 }
 
@@ -115,12 +734,32 @@ impl From<&Nfa> for Dfa {
     /// Transforms an Nfa into a Dfa, based on the algorithm described
     /// [here](https://www.youtube.com/watch?v=taClnxU-nao).
     /// The asymptotic complexity is quadratic in number of states.
+    ///
+    /// A state of the NFA may have multiple transitions on the same symbol, as is the case for the
+    /// automata built by [`Nfa::without_epsilons`].
+    ///
+    /// If the NFA has transitions on the line anchors, the columns of the anchors and of the
+    /// symbols ending a line are computed separately, so that the transitions on the anchors are
+    /// taken before or after these symbols, as described in [`Symbol::line_start`] and
+    /// [`Symbol::line_end`].
+    ///
+    /// The NFAs with tagged states are transformed by [`Dfa::from_tagged`] instead, so that the
+    /// automaton records the spans of the capture groups.
     fn from(nfa:&Nfa) -> Self {
-        let     nfa_mat     = nfa.nfa_matrix();
+        if nfa.has_tags() { return Dfa::from_tagged(nfa) }
+        let     alphabet    = nfa.alphabet.seal();
         let     eps_mat     = nfa.eps_matrix();
         let mut dfa_mat     = Matrix::new(0,nfa.alphabet.divisions.len());
         let mut dfa_eps_ixs = Vec::<nfa::StateSetId>::new();
         let mut dfa_eps_map = HashMap::<nfa::StateSetId,State>::new();
+        let     closure     = |states:nfa::StateSetId| {
+            states.iter().flat_map(|state| eps_mat[state.id()].iter().copied()).collect()
+        };
+        let line_symbols = nfa::line_symbols();
+        let line_symbols = line_symbols.iter().filter(|_| nfa.has_line_anchors());
+        let line_symbols = line_symbols.filter_map(|symbol| {
+            alphabet.own_division(symbol).map(|column| (*symbol,column))
+        }).collect_vec();
 
         dfa_eps_ixs.push(eps_mat[0].clone());
         dfa_eps_map.insert(eps_mat[0].clone(),Dfa::START_STATE);
@@ -128,14 +767,20 @@ impl From<&Nfa> for Dfa {
         let mut i = 0;
         while i < dfa_eps_ixs.len()  {
             dfa_mat.new_row();
-            for voc_ix in 0..nfa.alphabet.divisions.len() {
-                let mut eps_set = nfa::StateSetId::new();
-                for &eps_ix in &dfa_eps_ixs[i] {
-                    let tgt = nfa_mat[(eps_ix.id(),voc_ix)];
-                    if tgt != nfa::State::INVALID {
-                        eps_set.extend(eps_mat[tgt.id()].iter());
+            let mut eps_sets = vec![nfa::StateSetId::new();nfa.alphabet.divisions.len()];
+            for &eps_ix in &dfa_eps_ixs[i] {
+                for link in nfa[eps_ix].links() {
+                    let start = alphabet.index_of_symbol(link.symbols.start());
+                    let end   = alphabet.index_of_symbol(link.symbols.end());
+                    for eps_set in &mut eps_sets[start..=end] {
+                        eps_set.extend(eps_mat[link.target.id()].iter());
                     }
                 }
+            }
+            for &(symbol,column) in &line_symbols {
+                eps_sets[column] = nfa.next_states(&dfa_eps_ixs[i],symbol,closure);
+            }
+            for (voc_ix,eps_set) in eps_sets.into_iter().enumerate() {
                 if !eps_set.is_empty() {
                     dfa_mat[(i,voc_ix)] = match dfa_eps_map.get(&eps_set) {
                         Some(&id) => id,
@@ -156,9 +801,58 @@ impl From<&Nfa> for Dfa {
             sources.push(epss.into_iter().filter(|state| nfa[*state].export).collect_vec());
         }
 
-        let alphabet = (&nfa.alphabet).into();
-        let links    = dfa_mat;
-        Dfa {alphabet,links,sources}
+        let links = dfa_mat;
+        let tags  = default();
+        Dfa {alphabet,links,sources,tags}
+    }
+}
+
+impl Dfa {
+    /// Transforms an NFA with tagged states into a Dfa carrying the operations on the registers
+    /// that record the spans of the capture groups, as described in the [`crate::capture`] module.
+    ///
+    /// The states of the automaton are built from the [`Configuration`]s of the paths through the
+    /// NFA, in the breadth-first order of their discovery, and the transitions on the line anchors
+    /// are taken just as in the [`From`] implementation.
+    fn from_tagged(nfa:&Nfa) -> Self {
+        let alphabet           = nfa.alphabet.seal();
+        let columns            = alphabet.division_map.iter();
+        let columns            = columns.map(|(symbol,column)| (*symbol,*column)).collect_vec();
+        let start              = Configuration::start(nfa);
+        let mut links          = Matrix::new(0,columns.len());
+        let mut transitions    = vec![];
+        let mut configurations = vec![start.clone()];
+        let mut states         = HashMap::new();
+        states.insert(start,Dfa::START_STATE);
+        let mut ix = 0;
+        while ix < configurations.len() {
+            links.new_row();
+            let mut operations = BTreeMap::new();
+            for &(symbol,column) in &columns {
+                let next = configurations[ix].next(nfa,symbol);
+                if let Some((target,target_operations)) = next {
+                    let state = match states.entry(target) {
+                        Entry::Occupied(entry) => *entry.get(),
+                        Entry::Vacant(entry)   => {
+                            let state = State::new(configurations.len());
+                            configurations.push(entry.key().clone());
+                            *entry.insert(state)
+                        },
+                    };
+                    links[(ix,column)] = state;
+                    if !target_operations.is_empty() {
+                        operations.insert(column,target_operations);
+                    }
+                }
+            }
+            transitions.push(operations);
+            ix += 1;
+        }
+        let sources = configurations.iter().map(|config| config.sources(nfa)).collect();
+        let finals  = configurations.iter().map(|config| config.finals(nfa)).collect();
+        let names   = nfa.capture_names().clone();
+        let tags    = capture::Tags {names,transitions,finals};
+        Dfa {alphabet,links,sources,tags}
     }
 }
 
@@ -175,6 +869,11 @@ pub mod tests {
     use crate::nfa;
     use test::Bencher;
     use crate::nfa::tests::NfaTest;
+    use crate::nfa::tests::nfa_for;
+    use crate::nfa::tests::nfa_for_regexes;
+    use crate::pattern::Pattern;
+    use lazy_reader::Reader;
+    use lazy_reader::decoder::DecoderUTF8;
 
 
     // === Utilities ===
@@ -343,6 +1042,355 @@ pub mod tests {
         assert_eq!(get_name(&nfa,&dfa,make_state(4)),Some(&String::from("rule_2")));
     }
 
+    #[test]
+    fn dfa_minimize_minimal() {
+        let nfa = nfa::tests::pattern_seq();
+        let dfa = Dfa::from(&nfa.nfa);
+        assert_eq!(dfa.minimize(),dfa);
+    }
+
+    #[test]
+    fn dfa_minimize_named_rules() {
+        let nfa       = nfa::tests::named_rules();
+        let dfa       = Dfa::from(&nfa.nfa);
+        let minimized = dfa.minimize_by(|state| get_name(&nfa,&dfa,state).cloned());
+        assert_same_alphabet(&minimized,&nfa);
+        let expected = Matrix::from(
+            vec![
+                vec![invalid() , 1         , 2         , invalid()],
+                vec![invalid() , 1         , invalid() , invalid()],
+                vec![invalid() , invalid() , 2         , invalid()],
+            ]
+        );
+        assert_same_matrix(&minimized,&expected);
+        assert_eq!(minimized.sources.len(),3);
+        assert_eq!(get_name(&nfa,&minimized,make_state(0)),None);
+        assert_eq!(get_name(&nfa,&minimized,make_state(1)),Some(&String::from("rule_1")));
+        assert_eq!(get_name(&nfa,&minimized,make_state(2)),Some(&String::from("rule_2")));
+    }
+
+    #[test]
+    fn dfa_minimize_keeps_classes_apart() {
+        let nfa       = nfa::tests::named_rules();
+        let dfa       = Dfa::from(&nfa.nfa);
+        let minimized = dfa.minimize_by(|state| get_name(&nfa,&dfa,state).is_some());
+        let expected  = Matrix::from(
+            vec![
+                vec![invalid() , 1         , 2         , invalid()],
+                vec![invalid() , 1         , invalid() , invalid()],
+                vec![invalid() , invalid() , 2         , invalid()],
+            ]
+        );
+        assert_same_matrix(&minimized,&expected);
+        let merged = dfa.minimize_by(|_| ());
+        assert_eq!(merged.links.rows,3);
+    }
+
+    /// The automaton built from [`nfa_for`], together with the end states of the `patterns`.
+    pub(crate) fn dfa_for(patterns:&[Pattern]) -> (Dfa,Vec<nfa::State>) {
+        let (nfa,ends) = nfa_for(patterns);
+        (Dfa::from(&nfa),ends)
+    }
+
+    /// Just like [`dfa_for`], but with the patterns parsed from the `regexes`.
+    pub(crate) fn dfa_for_regexes(regexes:&[&str]) -> (Dfa,Vec<nfa::State>) {
+        let (nfa,ends) = nfa_for_regexes(regexes);
+        (Dfa::from(&nfa),ends)
+    }
+
+    #[test]
+    fn dfa_longest_match() {
+        let (dfa,ends) = dfa_for_regexes(&["a+b?"]);
+        assert_eq!(dfa.longest_match("aabc",&ends).map(|m| m.length),Some(3));
+        assert_eq!(dfa.longest_match("aac",&ends).map(|m| m.length),Some(2));
+        assert_eq!(dfa.longest_match("c",&ends),None);
+        assert_eq!(dfa.longest_match("",&ends),None);
+        assert!(dfa.matches("aaab",&ends));
+        assert!(!dfa.matches("aaba",&ends));
+    }
+
+    #[test]
+    fn dfa_longest_match_sources() {
+        let (dfa,ends) = dfa_for_regexes(&["[a-z]+","if","ł*"]);
+        let found      = dfa.longest_match("if x",&ends).unwrap();
+        assert_eq!(found.length,2);
+        assert_eq!(found.sources,vec![ends[0],ends[1]]);
+        let found = dfa.longest_match("ifs",&ends).unwrap();
+        assert_eq!(found.sources,vec![ends[0]]);
+        let found = dfa.longest_match("łłx",&ends).unwrap();
+        assert_eq!(found.length,4);
+        assert_eq!(found.sources,vec![ends[2]]);
+        let found = dfa.longest_match("",&ends).unwrap();
+        assert_eq!(found.length,0);
+        assert_eq!(found.state,Dfa::START_STATE);
+    }
+
+    #[test]
+    fn dfa_pattern_repeat() {
+        let (dfa,ends) = dfa_for_regexes(&["[0-9]{2,3}"]);
+        assert!(dfa.matches("12",&ends));
+        assert!(dfa.matches("123",&ends));
+        assert!(!dfa.matches("1",&ends));
+        assert!(!dfa.matches("1234",&ends));
+        let (dfa,ends) = dfa_for_regexes(&["[0-9]{2,}"]);
+        assert!(dfa.matches("12",&ends));
+        assert!(dfa.matches("12345",&ends));
+        assert!(!dfa.matches("1",&ends));
+        let (dfa,ends) = dfa_for_regexes(&["(ab){0,2}"]);
+        assert!(dfa.matches("",&ends));
+        assert!(dfa.matches("abab",&ends));
+        assert!(!dfa.matches("aba",&ends));
+    }
+
+    #[test]
+    fn dfa_pattern_and() {
+        let letters    = Pattern::parse("[a-z]+").unwrap();
+        let three      = Pattern::parse("...").unwrap();
+        let (dfa,ends) = dfa_for(&[letters.and(&three)]);
+        assert!(dfa.matches("abc",&ends));
+        assert!(!dfa.matches("ab",&ends));
+        assert!(!dfa.matches("ab1",&ends));
+        assert!(!dfa.matches("abcd",&ends));
+    }
+
+    #[test]
+    fn dfa_pattern_minus() {
+        let identifier = Pattern::parse("[a-z]+").unwrap();
+        let keywords   = Pattern::parse("if|then").unwrap();
+        let (dfa,ends) = dfa_for(&[identifier.minus(&keywords)]);
+        assert!(dfa.matches("iff",&ends));
+        assert!(dfa.matches("x",&ends));
+        assert!(!dfa.matches("if",&ends));
+        assert!(!dfa.matches("then",&ends));
+        assert_eq!(dfa.longest_match("if",&ends).map(|m| m.length),Some(1));
+    }
+
+    #[test]
+    fn dfa_pattern_complement() {
+        let (dfa,ends) = dfa_for(&[Pattern::char('a').complement()]);
+        assert!(dfa.matches("",&ends));
+        assert!(dfa.matches("b",&ends));
+        assert!(dfa.matches("aa",&ends));
+        assert!(!dfa.matches("a",&ends));
+        assert!(!dfa.matches("\0",&ends));
+    }
+
+    #[test]
+    fn dfa_pattern_complement_line_anchors() {
+        let line       = Pattern::line_start() >> Pattern::char('a').complement();
+        let (dfa,ends) = dfa_for(&[line >> Pattern::line_end()]);
+        assert!(dfa.matches("",&ends));
+        assert!(dfa.matches("bc",&ends));
+        assert!(dfa.matches("b\nc",&ends));
+        assert!(!dfa.matches("a",&ends));
+        assert!(!dfa.matches("\0",&ends));
+        assert_eq!(dfa.longest_match("a\n",&ends).map(|m| m.length),Some(2));
+        let (dfa,ends) = dfa_for(&[Pattern::char('a').complement()]);
+        assert_eq!(dfa.next_state(Dfa::START_STATE,&Symbol::line_end()),Dfa::START_STATE);
+        assert!(dfa.alphabet.own_division(&Symbol::line_start()).is_none());
+    }
+
+    #[test]
+    fn dfa_pattern_comment() {
+        let any        = Pattern::any().many();
+        let body       = (&any >> Pattern::all_of("*/") >> &any).complement();
+        let comment    = Pattern::all_of("/*") >> body >> Pattern::all_of("*/");
+        let (dfa,ends) = dfa_for(&[comment]);
+        assert!(dfa.matches("/* a */",&ends));
+        assert!(dfa.matches("/* a **/",&ends));
+        assert!(!dfa.matches("/* a */ b */",&ends));
+        assert_eq!(dfa.longest_match("/* a */ b */",&ends).map(|m| m.length),Some(7));
+    }
+
+    #[test]
+    fn dfa_line_anchors() {
+        let (dfa,ends) = dfa_for_regexes(&["^#[a-z]*","[a-z]+$","^$","([a-z]|\n)*^;","a$^"]);
+        let found      = |input:&str| dfa.longest_match(input,&ends).map(|m| (m.length,m.sources));
+        assert_eq!(found("#if"),Some((3,vec![ends[0]])));
+        assert_eq!(found("a#if"),None);
+        assert_eq!(found("abc"),Some((3,vec![ends[1]])));
+        assert_eq!(found("abc\nd"),Some((3,vec![ends[1]])));
+        assert_eq!(found("abc d"),None);
+        assert_eq!(found(""),Some((0,vec![ends[2]])));
+        assert_eq!(found("ab\n;"),Some((4,vec![ends[3]])));
+        assert_eq!(found("ab;"),None);
+        assert!(!dfa.matches("a",&[ends[4]]));
+    }
+
+    #[test]
+    fn dfa_line_anchors_are_not_symbols() {
+        let (dfa,ends) = dfa_for_regexes(&["^[^b]*$"]);
+        assert!(dfa.matches("ac",&ends));
+        assert!(!dfa.matches("acb",&ends));
+        let (dfa,ends) = dfa_for_regexes(&["^[^b]"]);
+        assert!(dfa.matches("a",&ends));
+        assert!(!dfa.matches("",&ends));
+        let (dfa,_) = dfa_for_regexes(&["[^b]"]);
+        assert_eq!(dfa.next_state(Dfa::START_STATE,&Symbol::line_start()),Dfa::START_STATE);
+    }
+
+    #[test]
+    fn dfa_longest_match_in_reader() {
+        let (dfa,ends)    = dfa_for_regexes(&["a+b?","abc"]);
+        let mut bookmarks = BookmarkManager::new();
+        let mut reader    = Reader::new("aabab".as_bytes(),DecoderUTF8());
+        reader.advance_char(&mut bookmarks);
+        let found = dfa.longest_match_in(&mut reader,&mut bookmarks,&ends).unwrap();
+        assert_eq!(found.length,3);
+        assert_eq!(reader.pop_result(),"aab");
+        assert_eq!(reader.character().char,Ok('a'));
+        let found = dfa.longest_match_in(&mut reader,&mut bookmarks,&ends).unwrap();
+        assert_eq!(found.length,2);
+        assert_eq!(reader.pop_result(),"ab");
+        assert!(reader.character().is_eof());
+        assert_eq!(dfa.longest_match_in(&mut reader,&mut bookmarks,&ends),None);
+    }
+
+    #[test]
+    fn dfa_longest_match_in_reader_line_anchors() {
+        let (dfa,ends)    = dfa_for_regexes(&["^a+","a+$","a+|\n"]);
+        let mut bookmarks = BookmarkManager::new();
+        let mut reader    = Reader::new("aa\naa".as_bytes(),DecoderUTF8());
+        reader.advance_char(&mut bookmarks);
+        let mut tokens = vec![];
+        while let Some(found) = dfa.longest_match_in(&mut reader,&mut bookmarks,&ends) {
+            tokens.push((reader.result().clone(),found.sources));
+            if reader.character().is_eof() { break }
+        }
+        let expected = vec!
+            [ ("aa".to_string()    ,vec![ends[0],ends[1],ends[2]])
+            , ("aa\n".to_string()  ,vec![ends[2]])
+            , ("aa\naa".to_string(),vec![ends[0],ends[1],ends[2]])
+            ];
+        assert_eq!(tokens,expected);
+    }
+
+    #[test]
+    fn dfa_longest_match_in_reader_rewinds() {
+        let (dfa,ends)    = dfa_for_regexes(&["abcd"]);
+        let mut bookmarks = BookmarkManager::new();
+        let mut reader    = Reader::new("abcx".as_bytes(),DecoderUTF8());
+        reader.advance_char(&mut bookmarks);
+        assert_eq!(dfa.longest_match_in(&mut reader,&mut bookmarks,&ends),None);
+        assert_eq!(reader.result(),"");
+        assert_eq!(reader.character().char,Ok('a'));
+    }
+
+    #[test]
+    #[cfg(feature="serde")]
+    fn dfa_serde_round_trip() {
+        let (dfa,ends) = dfa_for_regexes(&["[a-z_][a-z0-9_]*","[0-9]+","\\s+"]);
+        let json       = serde_json::to_string(&dfa).unwrap();
+        let loaded:Dfa = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded,dfa);
+        assert_eq!(loaded.longest_match("snake_case42 ",&ends).map(|m| m.length),Some(12));
+        let nfa_json    = serde_json::to_string(&nfa::tests::pattern_range().nfa).unwrap();
+        let loaded:Nfa  = serde_json::from_str(&nfa_json).unwrap();
+        assert_eq!(Dfa::from(&loaded),Dfa::from(&nfa::tests::pattern_range().nfa));
+    }
+
+    fn counterexample(result:Result<(),Counterexample>) -> Option<String> {
+        result.err().map(|counterexample| counterexample.to_string())
+    }
+
+    #[test]
+    fn dfa_is_equivalent() {
+        let (dfa_1,ends_1) = dfa_for_regexes(&["(a|b)*"]);
+        let (dfa_2,ends_2) = dfa_for_regexes(&["(a*b*)*"]);
+        assert_eq!(dfa_1.is_equivalent(&ends_1,&dfa_2,&ends_2),Ok(()));
+        let (dfa_3,ends_3) = dfa_for_regexes(&["[ab]*b"]);
+        let result         = dfa_1.is_equivalent(&ends_1,&dfa_3,&ends_3);
+        assert_eq!(counterexample(result),Some("".into()));
+        let (dfa_1,ends_1) = dfa_for_regexes(&["[a-z]+"]);
+        let (dfa_2,ends_2) = dfa_for_regexes(&["[a-y]+"]);
+        let result         = dfa_1.is_equivalent(&ends_1,&dfa_2,&ends_2);
+        assert_eq!(counterexample(result),Some("z".into()));
+        let (dfa_1,ends_1) = dfa_for_regexes(&["ab|abc"]);
+        let (dfa_2,ends_2) = dfa_for_regexes(&["ab"]);
+        let result         = dfa_2.is_equivalent(&ends_2,&dfa_1,&ends_1);
+        assert_eq!(counterexample(result),Some("abc".into()));
+    }
+
+    #[test]
+    fn dfa_is_equivalent_to_minimized() {
+        let (dfa,ends) = dfa_for_regexes(&["[a-z_][a-z0-9_]*","[0-9]+(\\.[0-9]+)?","\\s+"]);
+        let minimized  = dfa.minimize_by(|state| dfa.sources[state.id()].clone());
+        assert_eq!(dfa.is_equivalent(&ends,&minimized,&ends),Ok(()));
+        assert_eq!(minimized.is_equivalent(&ends,&dfa,&ends),Ok(()));
+    }
+
+    #[test]
+    fn dfa_is_subset_of() {
+        let (dfa_1,ends_1) = dfa_for_regexes(&["[0-9]+"]);
+        let (dfa_2,ends_2) = dfa_for_regexes(&["\\w+"]);
+        assert_eq!(dfa_1.is_subset_of(&ends_1,&dfa_2,&ends_2),Ok(()));
+        let result = dfa_2.is_subset_of(&ends_2,&dfa_1,&ends_1);
+        assert_eq!(counterexample(result),Some("A".into()));
+        let (dfa_3,ends_3) = dfa_for_regexes(&["[0-9]*"]);
+        let result         = dfa_3.is_subset_of(&ends_3,&dfa_1,&ends_1);
+        assert_eq!(counterexample(result),Some("".into()));
+    }
+
+    #[test]
+    fn dfa_is_empty() {
+        let (dfa,ends) = dfa_for(&[Pattern::char('a').and(&Pattern::char('b'))]);
+        assert_eq!(dfa.is_empty(&ends),Ok(()));
+        assert_eq!(Dfa::default().is_empty(&[]),Ok(()));
+        let (dfa,ends) = dfa_for_regexes(&["x(ab)*yz?"]);
+        assert_eq!(counterexample(dfa.is_empty(&ends)),Some("xy".into()));
+        let (dfa,ends) = dfa_for(&[Pattern::char('a') >> Pattern::eof()]);
+        assert_eq!(counterexample(dfa.is_empty(&ends)),Some("a<eof>".into()));
+        assert_eq!(dfa.is_empty(&[]),Ok(()));
+    }
+
+    #[test]
+    fn counterexample_display_with_names() {
+        let symbols        = vec![Symbol::from('a'),Symbol::from(' '),Symbol::eof()];
+        let counterexample = Counterexample {symbols};
+        let mut names      = Names::new();
+        names.insert(Symbol::from(' '),"space");
+        names.insert(Symbol::eof(),"end");
+        assert_eq!(counterexample.to_string(),"a <eof>");
+        assert_eq!(counterexample.display_with(&names),"a<space><end>");
+    }
+
+    #[test]
+    fn dfa_accepts_empty_string() {
+        let (dfa,ends) = dfa_for_regexes(&["a*"]);
+        assert!(dfa.accepts_empty_string(&ends));
+        let (dfa,ends) = dfa_for_regexes(&["a+"]);
+        assert!(!dfa.accepts_empty_string(&ends));
+        assert!(!Dfa::default().accepts_empty_string(&ends));
+        for regex in &["^","$","^$","^a*$"] {
+            let (dfa,ends) = dfa_for_regexes(&[regex]);
+            assert!(dfa.accepts_empty_string(&ends),"{}",regex);
+        }
+        let (dfa,ends) = dfa_for_regexes(&["\\n^"]);
+        assert!(!dfa.accepts_empty_string(&ends));
+    }
+
+    #[test]
+    fn dfa_is_equivalent_with_line_anchors() {
+        let equivalent = |lhs:&str, rhs:&str| {
+            let (dfa_1,ends_1) = dfa_for_regexes(&[lhs]);
+            let (dfa_2,ends_2) = dfa_for_regexes(&[rhs]);
+            counterexample(dfa_1.is_equivalent(&ends_1,&dfa_2,&ends_2))
+        };
+        assert_eq!(equivalent("^a","a"),None);
+        assert_eq!(equivalent("a$","a"),None);
+        assert_eq!(equivalent("^a*$","a*"),None);
+        assert_eq!(equivalent("a\\n^b","a\\nb"),None);
+        assert_eq!(equivalent("a$\\n","a\\n"),None);
+        assert_eq!(equivalent("a$b","a[^\\s\\S]"),None);
+        assert_eq!(equivalent("a^","a"),Some("a".into()));
+        assert_eq!(equivalent("a+$|b","a+|b"),None);
+        assert_eq!(equivalent("(a$)*","a*"),Some("aa".into()));
+        let (dfa,ends) = dfa_for_regexes(&["a$\\n^b"]);
+        assert_eq!(counterexample(dfa.is_empty(&ends)),Some("a\nb".into()));
+        let (dfa,ends) = dfa_for_regexes(&["a$b"]);
+        assert_eq!(dfa.is_empty(&ends),Ok(()));
+    }
+
     // === The Benchmarks ===
 
     #[bench]
@@ -0,0 +1,195 @@
+//! A deterministic finite automaton with a compressed transition table.
+
+use crate::prelude::*;
+
+use crate::alphabet;
+use crate::capture;
+use crate::data::CombMatrix;
+use crate::dfa;
+use crate::dfa::Automaton;
+use crate::dfa::Dfa;
+use crate::dfa::Match;
+use crate::dfa::State;
+use crate::nfa;
+use crate::symbol::Symbol;
+
+use lazy_reader::BookmarkManager;
+use lazy_reader::ReaderOps;
+use std::mem::size_of;
+
+
+
+// =====================
+// === CompressedDfa ===
+// =====================
+
+/// A [`Dfa`] with the transition table compressed into a [`CombMatrix`].
+///
+/// Most of the transitions from a state of a lexer's automaton lead to the same target, usually
+/// the invalid state, which makes the dense transition matrix of a [`Dfa`] mostly redundant,
+/// especially for alphabets divided into many ranges of Unicode characters. The compressed
+/// automaton stores only the transitions that differ from the most common one in each state,
+/// while keeping the lookup of a transition a constant time operation.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
+pub struct CompressedDfa {
+    /// A set of disjoint intervals over the allowable input alphabet.
+    pub alphabet : alphabet::SealedSegmentation,
+    /// The compressed transition matrix, equivalent to [`Dfa::links`].
+    pub links : CombMatrix<State>,
+    /// For each DFA state contains a list of NFA states it was constructed from.
+    pub sources : Vec<Vec<nfa::State>>,
+    /// The operations on the registers recording the capture groups, as in [`Dfa::tags`].
+    pub tags : capture::Tags,
+}
+
+impl CompressedDfa {
+    /// Simulate the DFA transition with the provided input symbol.
+    ///
+    /// It is equivalent to [`Dfa::next_state`] of the automaton that was compressed.
+    pub fn next_state(&self, current_state:State, symbol:&Symbol) -> State {
+        if symbol.is_anchor() && self.alphabet.own_division(symbol).is_none() {
+            return current_state
+        }
+        let ix = self.alphabet.index_of_symbol(symbol);
+        self.links.safe_index(current_state.id(),ix).unwrap_or_default()
+    }
+
+    /// The number of bytes occupied by the transition table.
+    pub fn links_memory_size(&self) -> usize {
+        self.links.memory_size()
+    }
+
+    /// Decompress the automaton.
+    pub fn decompress(&self) -> Dfa {
+        let alphabet = self.alphabet.clone();
+        let links    = self.links.to_matrix();
+        let sources  = self.sources.clone();
+        let tags     = self.tags.clone();
+        Dfa {alphabet,links,sources,tags}
+    }
+
+    /// Find the longest prefix of `input` that is accepted by the automaton.
+    ///
+    /// See [`Dfa::longest_match`] for the meaning of `accepting`.
+    pub fn longest_match(&self, input:&str, accepting:&[nfa::State]) -> Option<Match> {
+        dfa::longest_match(&mut &*self,input,accepting)
+    }
+
+    /// Check whether the whole `input` is accepted by the automaton.
+    ///
+    /// See [`Dfa::longest_match`] for the meaning of `accepting`.
+    pub fn matches(&self, input:&str, accepting:&[nfa::State]) -> bool {
+        let result = self.longest_match(input,accepting);
+        result.map(|found| found.length == input.len()).unwrap_or(false)
+    }
+
+    /// Find the longest match for the automaton in the input provided by the `reader`.
+    ///
+    /// See [`Dfa::longest_match_in`] for details.
+    pub fn longest_match_in<R:ReaderOps>
+    (&self, reader:&mut R, bookmarks:&mut BookmarkManager, accepting:&[nfa::State])
+    -> Option<Match> {
+        let line_start = dfa::starts_line(reader);
+        dfa::longest_match_in(&mut &*self,reader,bookmarks,accepting,line_start)
+    }
+}
+
+impl Dfa {
+    /// Compress the transition table of the automaton.
+    pub fn compress(&self) -> CompressedDfa {
+        CompressedDfa::from(self)
+    }
+
+    /// The number of bytes occupied by the transition table.
+    ///
+    /// It can be compared with [`CompressedDfa::links_memory_size`] to check whether the
+    /// compression pays off.
+    pub fn links_memory_size(&self) -> usize {
+        self.links.matrix.len() * size_of::<State>()
+    }
+}
+
+
+// === Trait Impls ===
+
+impl From<&Dfa> for CompressedDfa {
+    fn from(dfa:&Dfa) -> Self {
+        let alphabet = dfa.alphabet.clone();
+        let links    = CombMatrix::from(&dfa.links);
+        let sources  = dfa.sources.clone();
+        let tags     = dfa.tags.clone();
+        Self {alphabet,links,sources,tags}
+    }
+}
+
+impl From<&CompressedDfa> for Dfa {
+    fn from(dfa:&CompressedDfa) -> Self {
+        dfa.decompress()
+    }
+}
+
+impl Automaton for &CompressedDfa {
+    type Cursor = State;
+
+    fn start(&mut self) -> State {
+        Dfa::START_STATE
+    }
+
+    fn step(&mut self, cursor:&State, symbol:&Symbol) -> Option<State> {
+        let state = self.next_state(*cursor,symbol);
+        (!state.is_invalid()).as_some(state)
+    }
+
+    fn accept(&self, cursor:&State, length:usize, accepting:&[nfa::State]) -> Option<Match> {
+        dfa::accept_state(&self.sources,*cursor,length,accepting)
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dfa::tests::dfa_for;
+    use crate::dfa::tests::dfa_for_regexes;
+    use crate::pattern::Pattern;
+
+    #[test]
+    fn compressed_dfa_is_equivalent() {
+        let patterns   = ["[a-z_][a-z_0-9]*","if","[0-9]+(\\.[0-9]+)?","\\s+","\"[^\"]*\""];
+        let (dfa,ends) = dfa_for_regexes(&patterns);
+        let compressed = dfa.compress();
+        assert_eq!(compressed.decompress(),dfa);
+        let symbols = (0..0x80).chain(vec![0x3000,0x10FFFF,Symbol::eof().index]).map(Symbol::from);
+        let symbols = symbols.collect_vec();
+        for row in 0..=dfa.links.rows {
+            let state = if row == dfa.links.rows { State::INVALID } else { State::new(row) };
+            for symbol in &symbols {
+                assert_eq!(compressed.next_state(state,symbol),dfa.next_state(state,symbol));
+            }
+        }
+        for input in &["if","iffy","12.5","  ","\"a b\"","\"a b","-"] {
+            let expected = dfa.longest_match(input,&ends);
+            assert_eq!(compressed.longest_match(input,&ends),expected);
+        }
+    }
+
+    #[test]
+    fn compressed_dfa_is_smaller() {
+        let identifier = Pattern::xid_start() >> Pattern::xid_continue().many();
+        let keywords   = ["if","else","while","return","match"].iter();
+        let keywords   = keywords.map(|keyword| Pattern::all_of(keyword));
+        let patterns   = keywords.chain(vec![identifier,Pattern::whitespace().many1()]);
+        let (dfa,ends) = dfa_for(&patterns.collect_vec());
+        let compressed = dfa.compress();
+        assert!(compressed.links_memory_size() * 4 < dfa.links_memory_size());
+        assert!(compressed.matches("変数_1",&ends));
+        assert!(!compressed.matches("1変数",&ends));
+    }
+}
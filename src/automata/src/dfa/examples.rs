@@ -0,0 +1,429 @@
+//! Generation of example inputs that are accepted or rejected by a [`Dfa`], for the purposes of
+//! testing.
+
+use crate::prelude::*;
+
+use crate::dfa::Dfa;
+use crate::dfa::State;
+use crate::nfa;
+use crate::symbol::Symbol;
+
+#[cfg(feature="examples")]
+use rand::Rng;
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+
+
+
+// ================
+// === Examples ===
+// ================
+
+/// A generator of inputs that are accepted, or rejected, by a [`Dfa`].
+///
+/// An input is accepted if it leads from the start state of the automaton to an accepting state.
+/// Just as in [`Dfa::is_equivalent`], [`Symbol::eof`](crate::symbol::Symbol::eof) is not appended
+/// to the input. The inputs consist only of characters, so that the transitions on the symbols
+/// that are not characters are never taken, except for the transitions on the line anchors taken
+/// at the start and at the end of the input.
+#[derive(Clone,Debug)]
+pub struct Examples<'a> {
+    dfa       : &'a Dfa,
+    /// For each state, including the invalid state as the last one, whether it is accepting.
+    accepting : Vec<bool>,
+    /// For each column of the transition matrix, the ranges of characters it contains.
+    chars : Vec<Vec<RangeInclusive<char>>>,
+    /// For each state, the length of the shortest input leading to an accepting state.
+    to_accept : Vec<Option<usize>>,
+    /// For each state, the length of the shortest input leading to a rejecting state.
+    to_reject : Vec<Option<usize>>,
+}
+
+impl<'a> Examples<'a> {
+    /// Constructor.
+    fn new(dfa:&'a Dfa, accept:impl Fn(State) -> bool) -> Self {
+        let rows      = dfa.links.rows;
+        let accepting = (0..rows).map(|ix| {
+            let state = State::new(ix);
+            accept(state) || accept(dfa.next_state(state,&Symbol::line_end()))
+        });
+        let accepting = accepting.chain(iter::once(false));
+        let accepting = accepting.collect_vec();
+        let chars     = dfa.division_ranges().iter().map(|range| {
+            let start = u32::try_from(range.start().index).ok();
+            let end   = u32::try_from(range.end().index).unwrap_or(u32::max_value());
+            let chars = start.map(|start| char_ranges(start,end));
+            chars.unwrap_or_default()
+        }).collect_vec();
+        let mut this = Self {dfa,accepting,chars,to_accept:default(),to_reject:default()};
+        let rejecting = this.accepting.iter().map(|accepting| !accepting).collect_vec();
+        this.to_accept = this.distances(&this.accepting);
+        this.to_reject = this.distances(&rejecting);
+        this
+    }
+
+    /// Enumerate the accepted inputs in the order of increasing length.
+    ///
+    /// The inputs that differ only in characters from the same division of the alphabet lead
+    /// through the same states of the automaton, so that only one of them is enumerated, with the
+    /// printable ASCII characters preferred.
+    pub fn accepted(&self) -> Accepted<'_,'a> {
+        let examples = self;
+        let mut queue = VecDeque::new();
+        if self.to_accept[self.start()].is_some() {
+            queue.push_back((self.start(),String::new()));
+        }
+        Accepted {examples,queue}
+    }
+
+    /// Sample an accepted input of at most `max_length` characters, using the `rng`.
+    ///
+    /// Returns `None` if there is no such input. Requires the `examples` feature.
+    #[cfg(feature="examples")]
+    pub fn sample_accepted(&self, rng:&mut impl Rng, max_length:usize) -> Option<String> {
+        self.sample(rng,max_length,&self.accepting,&self.to_accept)
+    }
+
+    /// Sample a rejected input of at most `max_length` characters, using the `rng`.
+    ///
+    /// Returns `None` if there is no such input. Requires the `examples` feature.
+    #[cfg(feature="examples")]
+    pub fn sample_rejected(&self, rng:&mut impl Rng, max_length:usize) -> Option<String> {
+        let rejecting = self.accepting.iter().map(|accepting| !accepting).collect_vec();
+        self.sample(rng,max_length,&rejecting,&self.to_reject)
+    }
+
+    /// The index of the start state, which is the invalid state if the automaton has no states.
+    fn start(&self) -> usize {
+        let start = self.dfa.start_state();
+        if start.is_invalid() { self.dead() } else {
+            self.dfa.next_state(start,&Symbol::line_start()).id()
+        }
+    }
+
+    /// The index of the invalid state.
+    fn dead(&self) -> usize {
+        self.dfa.links.rows
+    }
+
+    /// The index of the state reached from the state with index `state` through the `column`.
+    fn next(&self, state:usize, column:usize) -> usize {
+        if state == self.dead() { return state }
+        let next = self.dfa.links[(state,column)];
+        if next.is_invalid() { self.dead() } else { next.id() }
+    }
+
+    /// The columns that contain any characters.
+    fn columns(&self) -> impl Iterator<Item=usize> + '_ {
+        self.chars.iter().enumerate().filter(|(_,chars)| !chars.is_empty()).map(|(ix,_)| ix)
+    }
+
+    /// For each state, compute the length of the shortest input leading to a `target` state.
+    fn distances(&self, target:&[bool]) -> Vec<Option<usize>> {
+        let mut predecessors = vec![vec![];target.len()];
+        for state in 0..target.len() {
+            for column in self.columns() {
+                predecessors[self.next(state,column)].push(state);
+            }
+        }
+        let mut distances = target.iter().map(|target| target.as_some(0)).collect_vec();
+        let mut queue     = (0..target.len()).filter(|ix| target[*ix]).collect::<VecDeque<_>>();
+        while let Some(state) = queue.pop_front() {
+            let distance = distances[state].unwrap_or_default() + 1;
+            for &predecessor in &predecessors[state] {
+                if distances[predecessor].is_none() {
+                    distances[predecessor] = Some(distance);
+                    queue.push_back(predecessor);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Run a random walk through the automaton that ends in a `target` state after at most
+    /// `max_length` steps, choosing uniformly at each step between ending the walk, if possible,
+    /// and each of the transitions from which a `target` state is still reachable.
+    #[cfg(feature="examples")]
+    fn sample
+    ( &self
+    , rng        : &mut impl Rng
+    , max_length : usize
+    , target     : &[bool]
+    , distances  : &[Option<usize>]
+    ) -> Option<String> {
+        let mut state  = self.start();
+        let mut result = String::new();
+        if distances[state]? > max_length { return None }
+        for remaining in (0..=max_length).rev() {
+            let moves = self.columns().filter(|column| {
+                let distance = distances[self.next(state,*column)];
+                distance.map(|distance| distance < remaining).unwrap_or(false)
+            }).collect_vec();
+            let choice = rng.gen_range(0..moves.len() + usize::from(target[state]));
+            match moves.get(choice) {
+                None         => break,
+                Some(column) => {
+                    result.push(self.random_char(rng,*column));
+                    state = self.next(state,*column);
+                }
+            }
+        }
+        Some(result)
+    }
+
+    /// A random character from the `column`.
+    #[cfg(feature="examples")]
+    fn random_char(&self, rng:&mut impl Rng, column:usize) -> char {
+        let ranges = &self.chars[column];
+        let size   = |range:&RangeInclusive<_>| u32::from(*range.end()) - u32::from(*range.start());
+        let total  = ranges.iter().map(|range| size(range) + 1).sum::<u32>();
+        let mut ix = rng.gen_range(0..total);
+        for range in ranges {
+            if ix <= size(range) {
+                return std::char::from_u32(u32::from(*range.start()) + ix).unwrap()
+            }
+            ix -= size(range) + 1;
+        }
+        unreachable!("The index is smaller than the total size of the ranges.")
+    }
+
+    /// The character representing the `column`, preferably a printable ASCII character.
+    fn representative(&self, column:usize) -> char {
+        let ranges    = &self.chars[column];
+        let printable = ranges.iter().find_map(|range| {
+            let char = (*range.start()).max('!');
+            (char <= *range.end() && char <= '~').as_some(char)
+        });
+        printable.unwrap_or_else(|| *ranges[0].start())
+    }
+}
+
+impl Dfa {
+    /// Get a generator of the inputs that are accepted or rejected by the automaton, where an
+    /// input is accepted if it leads to a state with any of its [`Dfa::sources`] contained in
+    /// `accepting`.
+    pub fn examples(&self, accepting:&[nfa::State]) -> Examples<'_> {
+        Examples::new(self,|state| self.is_accepting(state,accepting))
+    }
+
+    /// Get a generator of the inputs that are accepted or rejected by the automaton, where an
+    /// input is accepted if it leads to a state for which `accept` returns `true`.
+    pub fn examples_by(&self, accept:impl Fn(State) -> bool) -> Examples<'_> {
+        Examples::new(self,accept)
+    }
+}
+
+/// Split the range of codes `start..=end` into the ranges of characters it contains.
+fn char_ranges(start:u32, end:u32) -> Vec<RangeInclusive<char>> {
+    let end   = end.min(u32::from(std::char::MAX));
+    let parts = [(start,end.min(0xD7FF)),(start.max(0xE000),end)];
+    parts.iter().filter(|(start,end)| start <= end).map(|&(start,end)| {
+        std::char::from_u32(start).unwrap()..=std::char::from_u32(end).unwrap()
+    }).collect()
+}
+
+
+
+// ================
+// === Accepted ===
+// ================
+
+/// An iterator over the accepted inputs of a [`Dfa`], in the order of increasing length.
+///
+/// See [`Examples::accepted`] for details.
+#[derive(Clone,Debug)]
+pub struct Accepted<'e,'a> {
+    examples : &'e Examples<'a>,
+    queue    : VecDeque<(usize,String)>,
+}
+
+impl Iterator for Accepted<'_,'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let examples = self.examples;
+        while let Some((state,input)) = self.queue.pop_front() {
+            for column in examples.columns() {
+                let next = examples.next(state,column);
+                if examples.to_accept[next].is_some() {
+                    let mut input = input.clone();
+                    input.push(examples.representative(column));
+                    self.queue.push_back((next,input));
+                }
+            }
+            if examples.accepting[state] { return Some(input) }
+        }
+        None
+    }
+}
+
+
+
+// ==================
+// === Strategies ===
+// ==================
+
+#[cfg(all(feature="proptest",feature="examples"))]
+impl<'a> Examples<'a> {
+    /// A [`proptest`] strategy generating the accepted inputs of at most `max_length` characters.
+    ///
+    /// The inputs are sampled with [`Examples::sample_accepted`], with the random number generator
+    /// seeded by the test runner, so that the failures can be reproduced.
+    pub fn accepted_strategy(&self, max_length:usize)
+    -> impl proptest::strategy::Strategy<Value=String> + 'a {
+        self.strategy(max_length,|examples,rng,max_length| examples.sample_accepted(rng,max_length))
+    }
+
+    /// A [`proptest`] strategy generating the rejected inputs of at most `max_length` characters.
+    ///
+    /// See [`Examples::accepted_strategy`] for details.
+    pub fn rejected_strategy(&self, max_length:usize)
+    -> impl proptest::strategy::Strategy<Value=String> + 'a {
+        self.strategy(max_length,|examples,rng,max_length| examples.sample_rejected(rng,max_length))
+    }
+
+    /// A strategy generating the inputs with the provided `sample` function.
+    fn strategy
+    ( &self
+    , max_length : usize
+    , sample     : fn(&Examples,&mut rand::rngs::StdRng,usize) -> Option<String>
+    ) -> impl proptest::strategy::Strategy<Value=String> + 'a {
+        use proptest::strategy::Strategy;
+        use rand::SeedableRng;
+        let examples = self.clone();
+        proptest::num::u64::ANY.prop_filter_map("There are no such inputs.",move |seed| {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            sample(&examples,&mut rng,max_length)
+        })
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dfa::tests::dfa_for_regexes;
+
+    #[test]
+    fn accepted_in_order_of_length() {
+        let (dfa,ends) = dfa_for_regexes(&["a(b|cd)*"]);
+        let examples   = dfa.examples(&ends);
+        let accepted   = examples.accepted().take(6).collect_vec();
+        assert_eq!(accepted,vec!["a","ab","abb","acd","abbb","abcd"]);
+        let (dfa,ends) = dfa_for_regexes(&["if|in|x?"]);
+        let accepted   = dfa.examples(&ends).accepted().collect_vec();
+        assert_eq!(accepted,vec!["","x","if","in"]);
+        let (dfa,_)    = dfa_for_regexes(&["a"]);
+        assert_eq!(dfa.examples(&[]).accepted().next(),None);
+    }
+
+    #[test]
+    fn accepted_prefers_printable_characters() {
+        let (dfa,ends) = dfa_for_regexes(&["[\\t-z]"]);
+        assert_eq!(dfa.examples(&ends).accepted().collect_vec(),vec!["!"]);
+        let (dfa,ends) = dfa_for_regexes(&["[\\u{80}-\\u{90}]|\\t"]);
+        assert_eq!(dfa.examples(&ends).accepted().collect_vec(),vec!["\t","\u{80}"]);
+    }
+
+    #[test]
+    fn examples_by() {
+        let (dfa,ends) = dfa_for_regexes(&["a+"]);
+        let accepting  = |state:State| dfa.sources[state.id()].contains(&ends[0]);
+        let examples   = dfa.examples_by(accepting);
+        assert_eq!(examples.accepted().take(2).collect_vec(),vec!["a","aa"]);
+    }
+
+    #[test]
+    fn examples_with_line_anchors() {
+        let (dfa,ends) = dfa_for_regexes(&["^a+$|b\\n^c"]);
+        let examples   = dfa.examples(&ends);
+        assert_eq!(examples.accepted().take(4).collect_vec(),vec!["a","aa","aaa","b\nc"]);
+    }
+
+    #[cfg(feature="examples")]
+    mod sampling {
+        use super::*;
+
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        #[test]
+        fn sample_accepted() {
+            let (dfa,ends) = dfa_for_regexes(&["[a-z_][a-z_0-9]*|[0-9]+(\\.[0-9]+)?|日本+"]);
+            let examples   = dfa.examples(&ends);
+            let mut rng    = StdRng::seed_from_u64(42);
+            for _ in 0..100 {
+                let input = examples.sample_accepted(&mut rng,10).unwrap();
+                assert!(input.chars().count() <= 10);
+                assert!(dfa.matches(&input,&ends),"{:?}",input);
+            }
+            assert_eq!(examples.sample_accepted(&mut rng,0),None);
+            let (dfa,ends) = dfa_for_regexes(&["abc"]);
+            assert_eq!(dfa.examples(&ends).sample_accepted(&mut rng,2),None);
+            assert_eq!(dfa.examples(&ends).sample_accepted(&mut rng,3),Some("abc".into()));
+        }
+
+        #[test]
+        fn sample_rejected() {
+            let (dfa,ends) = dfa_for_regexes(&["[a-z]+"]);
+            let examples   = dfa.examples(&ends);
+            let mut rng    = StdRng::seed_from_u64(7);
+            for _ in 0..100 {
+                let input = examples.sample_rejected(&mut rng,5).unwrap();
+                assert!(input.chars().count() <= 5);
+                assert!(!dfa.matches(&input,&ends),"{:?}",input);
+            }
+            let (dfa,ends) = dfa_for_regexes(&["a*"]);
+            assert_eq!(dfa.examples(&ends).sample_rejected(&mut rng,0),None);
+        }
+
+        #[test]
+        fn sampling_is_deterministic() {
+            let (dfa,ends) = dfa_for_regexes(&["[a-z]+[0-9]*"]);
+            let examples   = dfa.examples(&ends);
+            let sample     = |seed| examples.sample_accepted(&mut StdRng::seed_from_u64(seed),8);
+            assert_eq!(sample(1),sample(1));
+        }
+
+        #[test]
+        fn sample_with_line_anchors() {
+            let (dfa,ends) = dfa_for_regexes(&["^a+$|b\\n^c"]);
+            let examples   = dfa.examples(&ends);
+            let mut rng    = StdRng::seed_from_u64(0);
+            for _ in 0..100 {
+                let input = examples.sample_rejected(&mut rng,4).unwrap();
+                assert!(!dfa.matches(&input,&ends),"{:?}",input);
+            }
+        }
+    }
+
+    #[cfg(all(feature="proptest",feature="examples"))]
+    mod strategies {
+        use super::*;
+
+        use proptest::test_runner::TestRunner;
+
+        #[test]
+        fn accepted_and_rejected_strategies() {
+            let (dfa,ends)  = dfa_for_regexes(&["[a-z]+[0-9]?"]);
+            let examples    = dfa.examples(&ends);
+            let mut runner  = TestRunner::default();
+            let accepted    = examples.accepted_strategy(6);
+            let rejected    = examples.rejected_strategy(6);
+            runner.run(&accepted,|input| {
+                assert!(dfa.matches(&input,&ends));
+                Ok(())
+            }).unwrap();
+            runner.run(&rejected,|input| {
+                assert!(!dfa.matches(&input,&ends));
+                Ok(())
+            }).unwrap();
+        }
+    }
+}
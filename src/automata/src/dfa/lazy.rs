@@ -0,0 +1,584 @@
+//! A deterministic finite automaton that is constructed lazily, while matching the input.
+
+use crate::prelude::*;
+
+use crate::alphabet;
+use crate::capture::Configuration;
+use crate::capture::Operation;
+use crate::capture::Registers;
+use crate::dfa;
+use crate::dfa::Automaton;
+use crate::dfa::Match;
+use crate::dfa::State;
+use crate::nfa;
+use crate::nfa::Nfa;
+use crate::symbol::Symbol;
+
+use lazy_reader::BookmarkManager;
+use lazy_reader::ReaderOps;
+use std::rc::Rc;
+
+
+
+// ==============
+// === Config ===
+// ==============
+
+/// The configuration of a [`LazyDfa`].
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub struct Config {
+    /// The maximum number of DFA states kept in the cache. When the cache is full, it is cleared.
+    pub cache_capacity : usize,
+    /// The number of times the cache may be cleared during a single search before the automaton
+    /// gives up on caching and falls back to simulating the NFA for the rest of the search.
+    pub max_cache_clears : usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let cache_capacity   = 4096;
+        let max_cache_clears = 8;
+        Self {cache_capacity,max_cache_clears}
+    }
+}
+
+
+
+// =============
+// === Cache ===
+// =============
+
+/// A DFA state known to the cache.
+#[derive(Clone,Debug)]
+struct CachedState {
+    /// The paths through the NFA that the DFA state is made of.
+    configuration : Rc<Configuration>,
+    /// The transitions discovered so far, together with their operations on the registers,
+    /// indexed by the alphabet division.
+    links : HashMap<usize,(State,Vec<Operation>)>,
+}
+
+/// The DFA states discovered so far.
+#[derive(Clone,Debug,Default)]
+struct Cache {
+    states     : Vec<CachedState>,
+    index      : HashMap<Rc<Configuration>,State>,
+    /// The number of times the cache has been cleared, telling apart the cursors pointing to the
+    /// states stored before and after each clearing.
+    generation : usize,
+}
+
+impl Cache {
+    /// Get the state made of the `configuration`, adding it to the cache if needed.
+    fn state(&mut self, configuration:Rc<Configuration>) -> State {
+        if let Some(state) = self.index.get(&configuration) {
+            return *state
+        }
+        let state = State::new(self.states.len());
+        let links = default();
+        self.index.insert(configuration.clone(),state);
+        self.states.push(CachedState {configuration,links});
+        state
+    }
+
+    /// The cursor pointing to the cached `state`.
+    fn cursor(&self, state:State) -> Cursor {
+        let generation    = self.generation;
+        let configuration = self.states[state.id()].configuration.clone();
+        Cursor::Cached {state,generation,configuration}
+    }
+
+    /// Remove all the states from the cache, starting its next generation.
+    fn clear(&mut self) {
+        self.states.clear();
+        self.index.clear();
+        self.generation += 1;
+    }
+}
+
+
+
+// ==============
+// === Cursor ===
+// ==============
+
+/// The position of a [`LazyDfa`] during a search.
+#[derive(Clone,Debug)]
+pub enum Cursor {
+    /// A state stored in the cache.
+    ///
+    /// Once the cache is cleared, the `state` no longer refers to the same paths through the NFA,
+    /// so that the cursor is stepped like a [`Cursor::Simulated`] one from its `configuration`.
+    Cached {
+        /// The state in the cache.
+        state : State,
+        /// The number of times the cache had been cleared when the state was stored in it.
+        generation : usize,
+        /// The paths through the NFA that the state is made of.
+        configuration : Rc<Configuration>,
+    },
+    /// The paths through the NFA, used when the automaton falls back to simulating the NFA.
+    Simulated(Rc<Configuration>),
+}
+
+impl Cursor {
+    /// The paths through the NFA that the state under the cursor is made of.
+    fn configuration(&self) -> &Rc<Configuration> {
+        match self {
+            Cursor::Cached {configuration,..} => configuration,
+            Cursor::Simulated(configuration)  => configuration,
+        }
+    }
+}
+
+
+
+// ===============
+// === LazyDfa ===
+// ===============
+
+/// A deterministic finite automaton that determinizes the underlying [`Nfa`] on demand.
+///
+/// Converting an NFA to a [`dfa::Dfa`] up-front computes every reachable DFA state, which can be
+/// prohibitively expensive for automata with large alphabets, like the ones built from Unicode
+/// classes. Instead, the lazy automaton computes the DFA states only when they are reached by the
+/// input, and caches them together with the transitions taken between them. The cache is bounded
+/// by [`Config::cache_capacity`], and is cleared when it becomes full. If the cache is cleared
+/// more than [`Config::max_cache_clears`] times during a single search, the rest of the search is
+/// performed by simulating the NFA directly, without caching any states.
+///
+/// The states are built from the same [`Configuration`]s as the states of a [`dfa::Dfa`], so that
+/// the transitions carry the same operations on the registers recording the capture groups.
+#[derive(Clone,Debug)]
+pub struct LazyDfa {
+    nfa      : Nfa,
+    alphabet : alphabet::SealedSegmentation,
+    config   : Config,
+    start    : Rc<Configuration>,
+    cache    : Cache,
+    clears   : usize,
+}
+
+impl LazyDfa {
+    /// Constructor.
+    pub fn new(nfa:Nfa) -> Self {
+        Self::with_config(nfa,default())
+    }
+
+    /// Constructor with a custom configuration.
+    pub fn with_config(nfa:Nfa, config:Config) -> Self {
+        let alphabet = nfa.alphabet().seal();
+        let start    = Rc::new(Configuration::start(&nfa));
+        let cache    = default();
+        let clears   = 0;
+        Self {nfa,alphabet,config,start,cache,clears}
+    }
+
+    /// The underlying NFA.
+    pub fn nfa(&self) -> &Nfa {
+        &self.nfa
+    }
+
+    /// The number of DFA states that are currently cached.
+    pub fn cached_states(&self) -> usize {
+        self.cache.states.len()
+    }
+
+    /// Remove all the cached DFA states.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear()
+    }
+
+    /// The cursor pointing to the start state of the automaton, from which it can be run one symbol
+    /// at a time with [`LazyDfa::next_state`].
+    pub fn start_state(&mut self) -> Cursor {
+        Automaton::start(self)
+    }
+
+    /// Transition from the `cursor` on `symbol`, returning `None` if the input is rejected.
+    ///
+    /// The transitions on the line anchors are taken just as in [`dfa::Dfa::next_state`]. The cache
+    /// is cleared when it becomes full, after which the other cursors remain valid, but are stepped
+    /// by simulating the NFA, as their states are no longer cached.
+    pub fn next_state(&mut self, cursor:&Cursor, symbol:&Symbol) -> Option<Cursor> {
+        Automaton::step(self,cursor,symbol)
+    }
+
+    /// Transition from the `cursor` on `symbol` just like [`LazyDfa::next_state`], applying the
+    /// operations of the transition to the `registers` at the `position` in the input, as in
+    /// [`dfa::Dfa::next_state_with`].
+    pub fn next_state_with
+    (&mut self, cursor:&Cursor, symbol:&Symbol, registers:&mut Registers, position:usize)
+    -> Option<Cursor> {
+        self.transition(cursor,symbol,true,Some((registers,position)))
+    }
+
+    /// The exported NFA states that the state under the `cursor` is made of, just like the
+    /// [`dfa::Dfa::sources`] of a state of an eagerly constructed automaton.
+    pub fn sources(&self, cursor:&Cursor) -> Vec<nfa::State> {
+        cursor.configuration().sources(&self.nfa)
+    }
+
+    /// The operations storing the positions of the tags on the path to the `source` in the output
+    /// registers, once the automaton accepts under the `cursor`, just like the
+    /// [`dfa::Dfa::tags`] of a state of an eagerly constructed automaton.
+    pub fn finals(&self, cursor:&Cursor, source:nfa::State) -> Vec<Operation> {
+        cursor.configuration().finals_for(source)
+    }
+
+    /// Find the longest prefix of `input` that is accepted by the automaton.
+    ///
+    /// See [`dfa::Dfa::longest_match`] for details.
+    pub fn longest_match(&mut self, input:&str, accepting:&[nfa::State]) -> Option<Match> {
+        dfa::longest_match(self,input,accepting)
+    }
+
+    /// Check whether the whole `input` is accepted by the automaton.
+    ///
+    /// See [`dfa::Dfa::longest_match`] for the meaning of `accepting`.
+    pub fn matches(&mut self, input:&str, accepting:&[nfa::State]) -> bool {
+        let result = self.longest_match(input,accepting);
+        result.map(|found| found.length == input.len()).unwrap_or(false)
+    }
+
+    /// Find the longest match for the automaton in the input provided by the `reader`.
+    ///
+    /// See [`dfa::Dfa::longest_match_in`] for details.
+    pub fn longest_match_in<R:ReaderOps>
+    (&mut self, reader:&mut R, bookmarks:&mut BookmarkManager, accepting:&[nfa::State])
+    -> Option<Match> {
+        let line_start = dfa::starts_line(reader);
+        dfa::longest_match_in(self,reader,bookmarks,accepting,line_start)
+    }
+
+    /// Add the state made of the `configuration` to the cache, clearing it first if it is full and
+    /// `may_clear` is set.
+    ///
+    /// Returns `None` if the cache is full and may not be cleared, or if it has been cleared too
+    /// many times during the current search.
+    fn cache_state(&mut self, configuration:Rc<Configuration>, may_clear:bool) -> Option<State> {
+        let is_cached = self.cache.index.contains_key(&configuration);
+        if !is_cached && self.cache.states.len() >= self.config.cache_capacity {
+            if !may_clear || self.clears >= self.config.max_cache_clears { return None }
+            self.clears += 1;
+            self.cache.clear();
+        }
+        Some(self.cache.state(configuration))
+    }
+
+    /// Transition from the `cursor` on `symbol`, clearing the cache if it is full and `may_clear`
+    /// is set. Otherwise, the target state is simulated if it does not fit in the cache. A cursor
+    /// pointing to a state of a previous generation of the cache is stepped by simulating the NFA.
+    ///
+    /// The operations of the transition are applied to the `registers`, if given together with
+    /// the position in the input.
+    fn transition
+    ( &mut self
+    , cursor    : &Cursor
+    , symbol    : &Symbol
+    , may_clear : bool
+    , registers : Option<(&mut Registers,usize)>
+    ) -> Option<Cursor> {
+        if symbol.is_anchor() && self.alphabet.own_division(symbol).is_none() {
+            return Some(cursor.clone())
+        }
+        match cursor {
+            Cursor::Cached {state,generation,..} if *generation == self.cache.generation => {
+                let division = self.alphabet.index_of_symbol(symbol);
+                let cached   = &self.cache.states[state.id()];
+                if let Some((target,operations)) = cached.links.get(&division) {
+                    if target.is_invalid() { return None }
+                    apply_operations(registers,operations);
+                    return Some(self.cache.cursor(*target))
+                }
+                let configuration = cached.configuration.clone();
+                let next          = configuration.next(&self.nfa,*symbol);
+                let (targets,operations) = match next {
+                    Some(next) => next,
+                    None       => {
+                        let invalid = (State::INVALID,default());
+                        self.cache.states[state.id()].links.insert(division,invalid);
+                        return None
+                    },
+                };
+                apply_operations(registers,&operations);
+                let targets = Rc::new(targets);
+                match self.cache_state(targets.clone(),may_clear) {
+                    None         => Some(Cursor::Simulated(targets)),
+                    Some(target) => {
+                        // The source state is gone if the cache has just been cleared.
+                        if self.cache.generation == *generation {
+                            let link = (target,operations);
+                            self.cache.states[state.id()].links.insert(division,link);
+                        }
+                        Some(self.cache.cursor(target))
+                    }
+                }
+            },
+            _ => {
+                let (targets,operations) = cursor.configuration().next(&self.nfa,*symbol)?;
+                apply_operations(registers,&operations);
+                Some(Cursor::Simulated(Rc::new(targets)))
+            },
+        }
+    }
+}
+
+/// Apply the `operations` of a transition to the `registers`, if given together with the position
+/// in the input.
+fn apply_operations(registers:Option<(&mut Registers,usize)>, operations:&[Operation]) {
+    if let Some((registers,position)) = registers {
+        registers.apply(operations,position)
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Automaton for LazyDfa {
+    type Cursor = Cursor;
+
+    fn start(&mut self) -> Cursor {
+        self.clears = 0;
+        if self.cache.states.len() >= self.config.cache_capacity {
+            self.cache.clear();
+        }
+        let state = self.cache.state(self.start.clone());
+        self.cache.cursor(state)
+    }
+
+    fn step(&mut self, cursor:&Cursor, symbol:&Symbol) -> Option<Cursor> {
+        self.transition(cursor,symbol,true,None)
+    }
+
+    /// Never clears the cache, so that the `cursor` keeps pointing to a cached state.
+    fn peek(&mut self, cursor:&Cursor, symbol:&Symbol) -> Option<Cursor> {
+        self.transition(cursor,symbol,false,None)
+    }
+
+    fn accept(&self, cursor:&Cursor, length:usize, accepting:&[nfa::State]) -> Option<Match> {
+        let state = match cursor {
+            Cursor::Cached {state,generation,..} if *generation == self.cache.generation => *state,
+            _ => State::INVALID,
+        };
+        let sources = self.sources(cursor).into_iter();
+        let sources = sources.filter(|source| accepting.contains(source)).collect_vec();
+        (!sources.is_empty()).as_some_from(|| Match {length,state,sources})
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::capture::Groups;
+    use crate::dfa::Dfa;
+    use crate::nfa::tests::nfa_for_regexes;
+    use crate::pattern::Pattern;
+    use lazy_reader::Reader;
+    use lazy_reader::decoder::DecoderUTF8;
+
+    fn assert_same_as_dfa(lazy:&mut LazyDfa, ends:&[nfa::State], inputs:&[&str]) {
+        let dfa = Dfa::from(lazy.nfa());
+        for input in inputs {
+            let expected = dfa.longest_match(input,ends).map(|m| (m.length,m.sources));
+            let result   = lazy.longest_match(input,ends).map(|m| (m.length,m.sources));
+            assert_eq!(result,expected,"Different results for `{}`.",input);
+        }
+    }
+
+    /// Step the `cursor` of the `lazy` automaton and the `state` of the eagerly constructed `dfa`
+    /// over the `input`, checking that they go through the same states.
+    fn step_both
+    (lazy:&mut LazyDfa, dfa:&Dfa, mut cursor:Cursor, mut state:State, input:&str)
+    -> (Cursor,State) {
+        for char in input.chars() {
+            let symbol = Symbol::from(char);
+            state      = dfa.next_state(state,&symbol);
+            cursor     = lazy.next_state(&cursor,&symbol).unwrap();
+            assert_eq!(lazy.sources(&cursor),dfa.sources[state.id()],"{:?}",input);
+        }
+        (cursor,state)
+    }
+
+    const INPUTS : &[&str] = &["","if","iffy","if1","123","12a","a_b_c","ab ab","🦀","x9y8z7"];
+
+    #[test]
+    fn lazy_dfa_matches_like_dfa() {
+        let (nfa,ends) = nfa_for_regexes(&["[a-z_][a-z_0-9]*","if","[0-9]+","[a-z]+ [a-z]+"]);
+        let mut lazy   = LazyDfa::new(nfa);
+        assert_same_as_dfa(&mut lazy,&ends,INPUTS);
+        let states = lazy.cached_states();
+        assert!(states > 0);
+        assert_same_as_dfa(&mut lazy,&ends,INPUTS);
+        assert_eq!(lazy.cached_states(),states);
+    }
+
+    #[test]
+    fn lazy_dfa_line_anchors() {
+        let (nfa,ends) = nfa_for_regexes(&["^[a-z]+","[a-z]+$","^$","([a-z]|\\n)*^;","a$^"]);
+        let mut lazy   = LazyDfa::new(nfa);
+        let inputs     = ["","abc","abc\nd","abc d","ab\n;","ab;","a","\n\n"];
+        assert_same_as_dfa(&mut lazy,&ends,&inputs);
+        assert_same_as_dfa(&mut lazy,&ends,&inputs);
+        let (nfa,ends) = nfa_for_regexes(&["[^b]+"]);
+        let mut lazy   = LazyDfa::new(nfa);
+        assert_same_as_dfa(&mut lazy,&ends,&inputs);
+    }
+
+    #[test]
+    fn lazy_dfa_clears_full_cache() {
+        let (nfa,ends)       = nfa_for_regexes(&["[a-z_][a-z_0-9]*","if","[0-9]+","[a-z]+ [a-z]+"]);
+        let cache_capacity   = 2;
+        let max_cache_clears = 100;
+        let config           = Config {cache_capacity,max_cache_clears};
+        let mut lazy         = LazyDfa::with_config(nfa,config);
+        assert_same_as_dfa(&mut lazy,&ends,INPUTS);
+        assert!(lazy.cached_states() <= cache_capacity);
+    }
+
+    #[test]
+    fn lazy_dfa_full_cache_at_line_end() {
+        let inputs = ["a","aa\nb","aab","b\naa","ba\n","a\na\n","aaa\nbb"];
+        for cache_capacity in 1..=2 {
+            let (nfa,ends)       = nfa_for_regexes(&["a+$|b","a+\n?"]);
+            let dfa              = Dfa::from(&nfa);
+            let max_cache_clears = 100;
+            let config           = Config {cache_capacity,max_cache_clears};
+            let mut lazy         = LazyDfa::with_config(nfa,config);
+            assert_same_as_dfa(&mut lazy,&ends,&inputs);
+            for input in &inputs {
+                let mut bookmarks = BookmarkManager::new();
+                let mut reader    = Reader::new(input.as_bytes(),DecoderUTF8());
+                reader.advance_char(&mut bookmarks);
+                let found    = lazy.longest_match_in(&mut reader,&mut bookmarks,&ends[..1]);
+                let expected = dfa.longest_match(input,&ends[..1]);
+                assert_eq!(found.map(|m| m.length),expected.map(|m| m.length),"{:?}",input);
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_dfa_falls_back_to_nfa_simulation() {
+        let (nfa,ends)       = nfa_for_regexes(&["[a-z_][a-z_0-9]*","if","[0-9]+","[a-z]+ [a-z]+"]);
+        let cache_capacity   = 1;
+        let max_cache_clears = 0;
+        let config           = Config {cache_capacity,max_cache_clears};
+        let mut lazy         = LazyDfa::with_config(nfa,config);
+        assert_same_as_dfa(&mut lazy,&ends,INPUTS);
+        let found = lazy.longest_match("iffy",&ends).unwrap();
+        assert_eq!(found.state,State::INVALID);
+    }
+
+    #[test]
+    fn lazy_dfa_step_by_step() {
+        let (nfa,_) = nfa_for_regexes(&["a+","ab","^b"]);
+        let dfa     = Dfa::from(&nfa);
+        let inputs  = ["aa","ab","b","ba"];
+        for cache_capacity in 1..=4 {
+            let max_cache_clears = 100;
+            let config           = Config {cache_capacity,max_cache_clears};
+            let mut lazy         = LazyDfa::with_config(nfa.clone(),config);
+            for (input,line_start) in inputs.iter().flat_map(|i| vec![(i,false),(i,true)]) {
+                let mut cursor = lazy.start_state();
+                let mut state  = Dfa::START_STATE;
+                assert_eq!(lazy.sources(&cursor),dfa.sources[state.id()]);
+                let symbols = line_start.as_some(Symbol::line_start()).into_iter();
+                let symbols = symbols.chain(input.chars().map(Symbol::from));
+                for symbol in symbols {
+                    state    = dfa.next_state(state,&symbol);
+                    let next = lazy.next_state(&cursor,&symbol);
+                    assert_eq!(next.is_none(),state.is_invalid(),"{:?}",input);
+                    match next {
+                        Some(next) => cursor = next,
+                        None       => break,
+                    }
+                    assert_eq!(lazy.sources(&cursor),dfa.sources[state.id()],"{:?}",input);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_dfa_cursor_outlives_cache() {
+        let (nfa,_)          = nfa_for_regexes(&["ab+c","abd","b+"]);
+        let dfa              = Dfa::from(&nfa);
+        let cache_capacity   = 2;
+        let max_cache_clears = 100;
+        let config           = Config {cache_capacity,max_cache_clears};
+        let mut lazy         = LazyDfa::with_config(nfa,config);
+        let start            = Dfa::START_STATE;
+        // The cache is cleared explicitly, and then reused by another cursor.
+        let cursor       = lazy.start_state();
+        let (kept,state) = step_both(&mut lazy,&dfa,cursor,start,"a");
+        lazy.clear_cache();
+        let cursor = lazy.start_state();
+        step_both(&mut lazy,&dfa,cursor,start,"bbb");
+        let next = lazy.next_state(&kept,&Symbol::from('b')).unwrap();
+        assert!(matches!(next,Cursor::Simulated(_)));
+        step_both(&mut lazy,&dfa,kept,state,"bbc");
+        // The cache is cleared when it becomes full, while stepping another cursor.
+        let cursor              = lazy.start_state();
+        let (kept,state)        = step_both(&mut lazy,&dfa,cursor,start,"a");
+        let generation          = lazy.cache.generation;
+        let (other,other_state) = step_both(&mut lazy,&dfa,kept.clone(),state,"b");
+        assert!(lazy.cache.generation > generation);
+        step_both(&mut lazy,&dfa,other,other_state,"bc");
+        step_both(&mut lazy,&dfa,kept,state,"bd");
+    }
+
+    #[test]
+    fn lazy_dfa_captures() {
+        let pattern = Pattern::parse("(?<x>a|ab)(?<y>b?)c|(?<y>[ab]+)d").unwrap();
+        let mut nfa = Nfa::new();
+        let end     = nfa.new_pattern(nfa.start,pattern);
+        let dfa     = Dfa::from(&nfa);
+        let groups  = Groups::new(nfa.capture_names().iter().map(String::as_str).zip(0..));
+        for &(cache_capacity,max_cache_clears) in &[(1,0),(2,100),(4096,8)] {
+            let config   = Config {cache_capacity,max_cache_clears};
+            let mut lazy = LazyDfa::with_config(nfa.clone(),config);
+            for input in &["abc","abbc","ac","abd","bbad"] {
+                let mut registers = Registers::default();
+                let mut cursor    = lazy.start_state();
+                for (offset,char) in input.char_indices() {
+                    let symbol = Symbol::from(char);
+                    let next   = lazy.next_state_with(&cursor,&symbol,&mut registers,offset);
+                    cursor     = next.unwrap();
+                }
+                registers.apply(&lazy.finals(&cursor,end),input.len());
+                let expected = dfa.captures(input,true,end);
+                assert_eq!(Some(groups.captures(&registers)),expected,"{:?}",input);
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_dfa_unicode() {
+        let pattern  = Pattern::xid_start() >> Pattern::xid_continue().many();
+        let mut nfa  = Nfa::new();
+        let end      = nfa.new_pattern(nfa.start,pattern);
+        let mut lazy = LazyDfa::new(nfa);
+        assert!(lazy.matches("変数_1",&[end]));
+        assert_eq!(lazy.longest_match("Δx-y",&[end]).map(|m| m.length),Some(3));
+        assert!(!lazy.matches("1x",&[end]));
+    }
+
+    #[test]
+    fn lazy_dfa_longest_match_in_reader() {
+        let (nfa,ends)    = nfa_for_regexes(&["a+b?","abc"]);
+        let mut lazy      = LazyDfa::new(nfa);
+        let mut bookmarks = BookmarkManager::new();
+        let mut reader    = Reader::new("aabab".as_bytes(),DecoderUTF8());
+        reader.advance_char(&mut bookmarks);
+        let found = lazy.longest_match_in(&mut reader,&mut bookmarks,&ends).unwrap();
+        assert_eq!(found.length,3);
+        assert_eq!(reader.pop_result(),"aab");
+        assert_eq!(reader.character().char,Ok('a'));
+    }
+}
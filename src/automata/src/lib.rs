@@ -13,12 +13,15 @@
 #![feature(test)]
 
 pub mod alphabet;
+pub mod capture;
 pub mod data;
 pub mod dfa;
 pub mod nfa;
 pub mod pattern;
 pub mod state;
 pub mod symbol;
+pub mod utf8;
+pub mod visualization;
 
 pub use dfa::Dfa;
 pub use nfa::Nfa;
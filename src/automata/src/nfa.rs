@@ -3,13 +3,19 @@
 use crate::prelude::*;
 
 use crate::alphabet;
+use crate::capture::Tag;
+use crate::dfa;
+use crate::dfa::Dfa;
 use crate::pattern::Pattern;
 use crate::state::Transition;
 use crate::state;
 use crate::symbol::Symbol;
 use crate::data::matrix::Matrix;
+use crate::visualization::Diagram;
 
+use std::collections::BTreeMap;
 use std::collections::BTreeSet;
+use std::ops::RangeFrom;
 use std::ops::RangeInclusive;
 
 
@@ -46,21 +52,32 @@ pub type StateSetId = BTreeSet<State>;
 ///  │ 0 │ ----> │ 1 │ -> │ 2 │ ----> │ 3 │ -> │ 3 │ ----> │ 3 │
 ///  └───┘       └───┘ ε  └───┘       └───┘ ε  └───┘       └───┘
 /// ```
+///
+/// The `lookaheads` map the final states of the patterns built with a [`Pattern::Lookahead`] as
+/// their outermost pattern to the states in which the bodies of the lookaheads end.
+///
+/// The `capture_names` are the names of the [`Pattern::Capture`] groups, indexed by the [`Tag`]s
+/// of the states in which the groups start and end. See the [`crate::capture`] module for details.
 #[derive(Clone,Debug,PartialEq,Eq)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct Nfa {
-    pub        start    : State,
-    pub(crate) alphabet : alphabet::Segmentation,
-    pub(crate) states   : Vec<state::Data>,
+    pub        start      : State,
+    pub(crate) alphabet   : alphabet::Segmentation,
+    pub(crate) states     : Vec<state::Data>,
+    pub(crate) lookaheads : BTreeMap<State,State>,
+    pub(crate) captures   : Vec<String>,
 }
 
 impl Nfa {
     /// Constructor.
     pub fn new() -> Self {
-        let start    = default();
-        let alphabet = default();
-        let states   = default();
-        Self {start,alphabet,states}.init_start_state()
+        let start      = default();
+        let alphabet   = default();
+        let states     = default();
+        let lookaheads = default();
+        let captures   = default();
+        Self {start,alphabet,states,lookaheads,captures}.init_start_state()
     }
 
     /// Initialize the start state of the automaton.
@@ -95,6 +112,21 @@ impl Nfa {
         &self.alphabet
     }
 
+    /// Get the states in which the bodies of the lookaheads end, indexed by the final states of
+    /// the patterns containing them.
+    ///
+    /// Only the patterns built by [`Nfa::new_pattern`] with a [`Pattern::Lookahead`] as their
+    /// outermost pattern are included, as the nested lookaheads consume their context just like
+    /// a sequence does.
+    pub fn lookaheads(&self) -> &BTreeMap<State,State> {
+        &self.lookaheads
+    }
+
+    /// Get the names of the capture groups, indexed by the [`Tag`]s of the states.
+    pub fn capture_names(&self) -> &Vec<String> {
+        &self.captures
+    }
+
     /// Creates an epsilon transition between two states.
     ///
     /// Whenever the automaton happens to be in `source` state it can immediately transition to the
@@ -122,8 +154,25 @@ impl Nfa {
     /// Transforms a pattern to connected NFA states by using the algorithm described
     /// [here](https://www.youtube.com/watch?v=RYNN-tb9WxI).
     /// The asymptotic complexity is linear in number of symbols.
+    ///
+    /// The pattern is [simplified](Pattern::simplify) first, so that it does not produce states
+    /// for redundant nesting of the pattern. If the pattern is a [`Pattern::Lookahead`], the state
+    /// in which its body ends is recorded in the [`Nfa::lookaheads`].
     pub fn new_pattern(&mut self, source:State, pattern:impl AsRef<Pattern>) -> State {
-        let pattern = pattern.as_ref();
+        match pattern.as_ref().simplify() {
+            Pattern::Lookahead {body,context,negated} => {
+                let body_end = self.build_pattern(source,&body);
+                let end      = self.build_context(body_end,&context,negated);
+                self.lookaheads.insert(end,body_end);
+                end
+            },
+            pattern => self.build_pattern(source,&pattern),
+        }
+    }
+
+    /// Transforms an already simplified pattern to connected NFA states, returning the final
+    /// state.
+    fn build_pattern(&mut self, source:State, pattern:&Pattern) -> State {
         let current = self.new_state();
         self.connect(source,current);
         let state = match pattern {
@@ -132,9 +181,16 @@ impl Nfa {
                 self.connect_via(current,state,range);
                 state
             },
+            Pattern::Class(ranges) => {
+                let state = self.new_state();
+                for range in ranges {
+                    self.connect_via(current,state,range);
+                }
+                state
+            },
             Pattern::Many(body) => {
                 let s1 = self.new_state();
-                let s2 = self.new_pattern(s1,body);
+                let s2 = self.build_pattern(s1,body);
                 let s3 = self.new_state();
                 self.connect(current,s1);
                 self.connect(current,s3);
@@ -142,19 +198,63 @@ impl Nfa {
                 self.connect(s3,s1);
                 s3
             },
+            Pattern::Repeat {body,min,max} => {
+                let state = (0..*min).fold(current,|s,_| self.build_pattern(s,body.as_ref()));
+                match max {
+                    None      => self.build_pattern(state,&body.many()),
+                    Some(max) => {
+                        let end   = self.new_state();
+                        let state = (*min..*max).fold(state,|s,_| {
+                            self.connect(s,end);
+                            self.build_pattern(s,body.as_ref())
+                        });
+                        self.connect(state,end);
+                        end
+                    }
+                }
+            },
             Pattern::Seq(patterns) => {
-                patterns.iter().fold(current,|s,pat| self.new_pattern(s,pat))
+                patterns.iter().fold(current,|s,pat| self.build_pattern(s,pat))
             },
             Pattern::Or(patterns) => {
-                let states = patterns.iter().map(|pat| self.new_pattern(current,pat)).collect_vec();
+                let states = patterns.iter().map(|pat| self.build_pattern(current,pat));
+                let states = states.collect_vec();
                 let end    = self.new_state();
                 for state in states {
                     self.connect(state,end);
                 }
                 end
             },
-            Pattern::Always => current,
-            Pattern::Never  => self.new_state(),
+            Pattern::And(patterns) => {
+                let operands = patterns.iter().collect_vec();
+                self.new_product_pattern(current,&operands,|accepted| accepted.iter().all(|t| *t))
+            },
+            Pattern::Minus(lhs,rhs) => {
+                self.new_product_pattern(current,&[lhs,rhs],|accepted| accepted[0] && !accepted[1])
+            },
+            Pattern::Complement(body) => {
+                let universe = Pattern::symbols(complement_universe()).many();
+                self.new_product_pattern(current,&[&universe,body],|accepted| {
+                    accepted[0] && !accepted[1]
+                })
+            },
+            Pattern::Lookahead {body,context,negated} => {
+                let body_end = self.build_pattern(current,body);
+                self.build_context(body_end,context,*negated)
+            },
+            Pattern::Capture {name,body} => {
+                let group = self.capture_group(name);
+                self[current].tags.push(Tag::Start(group));
+                let body_end = self.build_pattern(current,body);
+                let state    = self.new_state();
+                self.connect(body_end,state);
+                self[state].tags.push(Tag::End(group));
+                state
+            },
+            Pattern::LineStart => self.new_anchor(current,Symbol::line_start()),
+            Pattern::LineEnd   => self.new_anchor(current,Symbol::line_end()),
+            Pattern::Always    => current,
+            Pattern::Never     => self.new_state(),
         };
         self[state].export = true;
         state
@@ -165,16 +265,26 @@ impl Nfa {
     /// `new_pattern`, but it consumes an explicit target state.
     /// The asymptotic complexity is linear in number of symbols.
     pub fn new_pattern_to(&mut self, source:State, target:State, pattern:impl AsRef<Pattern>) {
-        let pattern = pattern.as_ref();
+        self.build_pattern_to(source,target,&pattern.as_ref().simplify())
+    }
+
+    /// Transforms an already simplified pattern to connected NFA states ending in the `target`
+    /// state.
+    fn build_pattern_to(&mut self, source:State, target:State, pattern:&Pattern) {
         let current = self.new_state();
         self.connect(source,current);
         match pattern {
             Pattern::Range(range) => {
                 self.connect_via(current,target,range);
             },
+            Pattern::Class(ranges) => {
+                for range in ranges {
+                    self.connect_via(current,target,range);
+                }
+            },
             Pattern::Many(body) => {
                 let s1 = self.new_state();
-                let s2 = self.new_pattern(s1,body);
+                let s2 = self.build_pattern(s1,body);
                 let target = self.new_state();
                 self.connect(current,s1);
                 self.connect(current,target);
@@ -182,15 +292,22 @@ impl Nfa {
                 self.connect(target,s1);
             },
             Pattern::Seq(patterns) => {
-                let out = patterns.iter().fold(current,|s,pat| self.new_pattern(s,pat));
+                let out = patterns.iter().fold(current,|s,pat| self.build_pattern(s,pat));
                 self.connect(out,target)
             },
             Pattern::Or(patterns) => {
-                let states = patterns.iter().map(|pat| self.new_pattern(current,pat)).collect_vec();
+                let states = patterns.iter().map(|pat| self.build_pattern(current,pat));
+                let states = states.collect_vec();
                 for state in states {
                     self.connect(state,target);
                 }
             },
+            Pattern::Repeat{..} | Pattern::And(_) | Pattern::Minus(..) | Pattern::Complement(_)
+            | Pattern::Lookahead{..} | Pattern::Capture{..} | Pattern::LineStart
+            | Pattern::LineEnd => {
+                let out = self.build_pattern(current,pattern);
+                self.connect(out,target)
+            },
             Pattern::Always => {
                 self.connect(current,target)
             },
@@ -199,6 +316,248 @@ impl Nfa {
         self[target].export = true;
     }
 
+    /// Creates a transition on the line `anchor`, returning its target state.
+    ///
+    /// The anchor, as well as the symbols that end a line, get divisions of their own in the
+    /// alphabet, so that the transitions on them can be computed by [`Nfa::next_states`].
+    fn new_anchor(&mut self, source:State, anchor:Symbol) -> State {
+        for symbol in line_symbols().iter() {
+            self.alphabet.insert(*symbol..=*symbol);
+        }
+        let state = self.new_state();
+        self.connect_via(source,state,&(anchor..=anchor));
+        state
+    }
+
+    /// Remove the tags of the `states`, so that the capture groups inside them never record a span.
+    fn remove_tags(&mut self, states:RangeFrom<usize>) {
+        for state in &mut self.states[states] {
+            state.tags.clear();
+        }
+    }
+
+    /// Get the index of the capture group called `name`, registering the group if it is new.
+    fn capture_group(&mut self, name:&str) -> usize {
+        self.captures.iter().position(|group| group == name).unwrap_or_else(|| {
+            self.captures.push(name.into());
+            self.captures.len() - 1
+        })
+    }
+
+    /// Transforms a boolean combination of patterns to connected NFA states, returning the final
+    /// state.
+    ///
+    /// Each of the `operands` is compiled to a separate DFA, and the resulting automata are run in
+    /// lockstep by the means of the product construction. A state of the product is accepting if
+    /// `accept` returns `true` for the acceptance of the corresponding operand states. The dead
+    /// state of an operand is tracked explicitly, as the product may still accept after one of the
+    /// operands has rejected the input, as is the case for [`Pattern::Minus`].
+    fn new_product_pattern
+    (&mut self, source:State, operands:&[&Pattern], accept:impl Fn(&[bool]) -> bool) -> State {
+        let automata = operands.iter().map(|pattern| {
+            let mut nfa   = Nfa::new();
+            let end       = nfa.build_pattern(nfa.start,pattern);
+            nfa.remove_tags(0..);
+            let dfa       = Dfa::from(&nfa);
+            let accepting = dfa.sources.iter().map(|sources| sources.contains(&end)).collect_vec();
+            (dfa,accepting)
+        }).collect_vec();
+        let ranges       = common_ranges(automata.iter().map(|(dfa,_)| dfa));
+        let is_accepting = |key:&[dfa::State]| {
+            let accepted = automata.iter().zip(key).map(|((_,accepting),state)| {
+                !state.is_invalid() && accepting[state.id()]
+            }).collect_vec();
+            accept(&accepted)
+        };
+        let dead          = vec![dfa::State::INVALID;automata.len()];
+        let dead_is_live  = is_accepting(&dead);
+        let start_key     = vec![Dfa::START_STATE;automata.len()];
+        let start         = self.new_state();
+        let end           = self.new_state();
+        let mut states    = HashMap::new();
+        let mut to_visit  = vec![start_key.clone()];
+        states.insert(start_key,start);
+        self.connect(source,start);
+        while let Some(key) = to_visit.pop() {
+            let state = states[&key];
+            if is_accepting(&key) {
+                self.connect(state,end);
+            }
+            let mut links:Vec<(RangeInclusive<Symbol>,State)> = vec![];
+            for range in &ranges {
+                let target_key = automata.iter().zip(&key).map(|((dfa,_),state)| {
+                    dfa.next_state(*state,range.start())
+                }).collect_vec();
+                if target_key == dead && !dead_is_live { continue }
+                let target = *states.entry(target_key.clone()).or_insert_with(|| {
+                    to_visit.push(target_key);
+                    self.new_state()
+                });
+                merge_link(&mut links,range,target);
+            }
+            for (range,target) in links {
+                self.connect_via(state,target,&range);
+            }
+        }
+        end
+    }
+
+    /// Transforms the context of a lookahead to connected NFA states, returning the final state.
+    ///
+    /// A negated context is transformed to the shortest inputs proving that the `context` does not
+    /// trigger. These lead its DFA through the states that do not accept, but still can, to a
+    /// state that cannot accept anymore.
+    fn build_context(&mut self, source:State, context:&Pattern, negated:bool) -> State {
+        if !negated {
+            let first = self.states.len();
+            let end   = self.build_pattern(source,context);
+            self.remove_tags(first..);
+            return end
+        }
+        let mut nfa   = Nfa::new();
+        let end       = nfa.build_pattern(nfa.start,context);
+        nfa.remove_tags(0..);
+        let dfa       = Dfa::from(&nfa);
+        let accepting = dfa.sources.iter().map(|sources| sources.contains(&end)).collect_vec();
+        let live      = live_states(&dfa,&accepting);
+        let witness_end = self.new_state_exported();
+        let start       = Dfa::START_STATE;
+        if accepting[start.id()] { return witness_end }
+        let ranges        = dfa.division_ranges();
+        let witness_start = self.new_state();
+        let mut states    = HashMap::new();
+        let mut to_visit  = vec![start];
+        states.insert(start,witness_start);
+        self.connect(source,witness_start);
+        while let Some(dfa_state) = to_visit.pop() {
+            let state = states[&dfa_state];
+            let mut links:Vec<(RangeInclusive<Symbol>,State)> = vec![];
+            for (column,range) in ranges.iter().enumerate() {
+                let target = dfa.links[(dfa_state.id(),column)];
+                let target = match target {
+                    _ if target.is_invalid() || !live[target.id()] => witness_end,
+                    _ if accepting[target.id()]                    => continue,
+                    _ => *states.entry(target).or_insert_with(|| {
+                        to_visit.push(target);
+                        self.new_state()
+                    }),
+                };
+                merge_link(&mut links,range,target);
+            }
+            for (range,target) in links {
+                self.connect_via(state,target,&range);
+            }
+        }
+        witness_end
+    }
+
+    /// Check whether the end of the `body` of a lookahead is ambiguous, that is whether the body
+    /// can trigger on an input extended by a part of its trailing context.
+    ///
+    /// The lexers generated by the flexer rewind the input to the last point at which the body
+    /// triggered, which is wrong for such lookaheads, as for `a*` followed by `ab` on the input
+    /// `aab`. The body and the context are run in lockstep from each of the states in which the
+    /// body triggers, and the end is ambiguous if the body triggers again after consuming some
+    /// input that the context can still be completed from. The line anchors do not consume input,
+    /// so that the body triggering again just after them is not ambiguous.
+    pub(crate) fn lookahead_is_ambiguous(body:&Pattern, context:&Pattern, negated:bool) -> bool {
+        let mut body_nfa    = Nfa::new();
+        let body_end        = body_nfa.build_pattern(body_nfa.start,body);
+        body_nfa.remove_tags(0..);
+        let mut context_nfa = Nfa::new();
+        let context_end     = context_nfa.build_context(context_nfa.start,context,negated);
+        let body_dfa        = Dfa::from(&body_nfa);
+        let context_dfa     = Dfa::from(&context_nfa);
+        let accepting       = |dfa:&Dfa, end:State| {
+            dfa.sources.iter().map(|sources| sources.contains(&end)).collect_vec()
+        };
+        let body_accepting  = accepting(&body_dfa,body_end);
+        let context_live    = live_states(&context_dfa,&accepting(&context_dfa,context_end));
+        let ranges          = common_ranges([&body_dfa,&context_dfa].iter().copied());
+        let body_ends       = body_dfa.states().filter(|state| body_accepting[state.id()]);
+        let mut to_visit    = body_ends.map(|state| (state,Dfa::START_STATE,false)).collect_vec();
+        let mut visited     = HashSet::new();
+        while let Some(key@(body_state,context_state,consumed)) = to_visit.pop() {
+            if !visited.insert(key) { continue }
+            for range in &ranges {
+                let body_state    = body_dfa.next_state(body_state,range.start());
+                let context_state = context_dfa.next_state(context_state,range.start());
+                if body_state.is_invalid() || context_state.is_invalid() { continue }
+                if !context_live[context_state.id()] { continue }
+                let consumed = consumed || !range.start().is_anchor();
+                if consumed && body_accepting[body_state.id()] { return true }
+                to_visit.push((body_state,context_state,consumed));
+            }
+        }
+        false
+    }
+
+    /// Check whether any state of the automaton is tagged by a capture group.
+    pub fn has_tags(&self) -> bool {
+        self.states.iter().any(|state| !state.tags.is_empty())
+    }
+
+    /// Check whether the automaton contains any transitions on the line anchors.
+    pub(crate) fn has_line_anchors(&self) -> bool {
+        let divisions = &self.alphabet.divisions;
+        divisions.contains(&Symbol::line_start()) || divisions.contains(&Symbol::line_end())
+    }
+
+    /// Compute the states reachable from the `states` on the `symbol`, with the `closure`
+    /// computing the epsilon closure of a set of states.
+    ///
+    /// The line anchors are never a part of the input, so that they are handled here:
+    /// - A transition on [`Symbol::line_start`] or [`Symbol::line_end`] keeps the `states`, adding
+    ///   the states reachable through the transitions on the anchor.
+    /// - The transitions on [`Symbol::line_end`] are taken before a `'\n'` and [`Symbol::eof`].
+    /// - The transitions on [`Symbol::line_start`] are taken after a `'\n'`.
+    ///
+    /// A line anchor only triggers the transitions on exactly the anchor, so that the patterns
+    /// like [`Pattern::any`] never trigger on them.
+    pub(crate) fn next_states
+    (&self, states:&StateSetId, symbol:Symbol, closure:impl Fn(StateSetId) -> StateSetId)
+    -> StateSetId {
+        if !self.has_line_anchors() { return self.move_on(states,symbol,&closure) }
+        if symbol.is_anchor() { return self.follow_anchor(states.clone(),symbol,&closure) }
+        let newline = Symbol::from('\n');
+        let states  = if symbol == newline || symbol == Symbol::eof() {
+            Cow::Owned(self.follow_anchor(states.clone(),Symbol::line_end(),&closure))
+        } else {
+            Cow::Borrowed(states)
+        };
+        let targets = self.move_on(&states,symbol,&closure);
+        if symbol != newline { targets } else {
+            self.follow_anchor(targets,Symbol::line_start(),&closure)
+        }
+    }
+
+    /// Compute the closure of the states reachable from the `states` on the `symbol`, ignoring
+    /// the line anchors.
+    fn move_on
+    (&self, states:&StateSetId, symbol:Symbol, closure:impl Fn(StateSetId) -> StateSetId)
+    -> StateSetId {
+        let links   = states.iter().flat_map(|state| self[*state].links());
+        let targets = links.filter(|link| link.symbols.contains(&symbol)).map(|link| link.target);
+        closure(targets.collect())
+    }
+
+    /// Extend the `states` with all the states reachable from them through the transitions on
+    /// exactly the line `anchor`.
+    fn follow_anchor
+    (&self, mut states:StateSetId, anchor:Symbol, closure:impl Fn(StateSetId) -> StateSetId)
+    -> StateSetId {
+        let mut to_visit = states.iter().copied().collect_vec();
+        while let Some(state) = to_visit.pop() {
+            let links   = self[state].links().iter();
+            let links   = links.filter(|link| link.symbols == (anchor..=anchor));
+            let targets = closure(links.map(|link| link.target).collect());
+            for target in targets {
+                if states.insert(target) { to_visit.push(target) }
+            }
+        }
+        states
+    }
+
     /// Merges states that are connected by epsilon links, using an algorithm based on the one shown
     /// [here](https://www.youtube.com/watch?v=taClnxU-nao).
     pub fn eps_matrix(&self) -> Vec<StateSetId> {
@@ -229,6 +588,74 @@ impl Nfa {
         states
     }
 
+    /// Get an equivalent automaton without the states that are only reachable through epsilon
+    /// links, together with the identifiers that the states of this automaton have in it.
+    ///
+    /// Only the start state, the targets of the transitions and the exported states are kept, so
+    /// that the DFA built from the result has the same [`Dfa::sources`] as the one built from this
+    /// automaton, up to the identifiers of the states. Every other state is bypassed, with its
+    /// transitions and epsilon links moved to the kept states that reach it through epsilon links.
+    /// This should be done once the construction of the automaton is complete.
+    ///
+    /// The kept states are renumbered in the order of their identifiers, so that the start state
+    /// keeps its identifier. The returned vector maps the identifiers of the states of this
+    /// automaton to the new ones, or to [`State::INVALID`] if the state is dropped. The
+    /// [`Nfa::lookaheads`] are renumbered here, while the data attached to the states elsewhere,
+    /// such as the rule names of the flexer, has to be renumbered by the caller.
+    ///
+    /// The [`Tag`]s of the states are dropped, as the capture groups can only be resolved on the
+    /// automaton with the epsilon links in place.
+    pub fn without_epsilons(&self) -> (Self,Vec<State>) {
+        let mut kept = self.states.iter().map(|state| state.export).collect_vec();
+        kept[self.start.id()] = true;
+        for link in self.states.iter().flat_map(|state| &state.links) {
+            kept[link.target.id()] = true;
+        }
+        let mut renumbered = vec![State::INVALID;self.states.len()];
+        let kept_ids       = (0..self.states.len()).filter(|ix| kept[*ix]);
+        for (new_ix,ix) in kept_ids.clone().enumerate() {
+            renumbered[ix] = State::new(new_ix);
+        }
+        let renumber = |state:&State| renumbered[state.id()];
+        let states   = kept_ids.map(|ix| {
+            let state           = State::new(ix);
+            let (merged,linked) = self.bypass(state,&kept);
+            let linked          = linked.iter().filter(|target| **target != state);
+            let epsilon_links   = linked.map(renumber).collect();
+            let links           = merged.iter().flat_map(|merged| self[*merged].links.iter());
+            let links           = links.map(|link| (link.symbols.clone(),renumber(&link.target)));
+            let mut links       = links.collect_vec();
+            links.sort_by_key(|(symbols,target)| (*symbols.start(),*symbols.end(),*target));
+            links.dedup();
+            let links  = links.into_iter().map(|(symbols,target)| Transition::new(symbols,target));
+            let links  = links.collect();
+            let export = self[state].export;
+            state::Data {epsilon_links,links,export,..default()}
+        }).collect();
+        let start      = renumber(&self.start);
+        let alphabet   = self.alphabet.clone();
+        let lookaheads = self.lookaheads.iter().map(|(k,v)| (renumber(k),renumber(v))).collect();
+        let captures   = self.captures.clone();
+        (Self {start,alphabet,states,lookaheads,captures},renumbered)
+    }
+
+    /// Get the `state` together with the states that are not `kept` and are reachable from it
+    /// through epsilon links without passing through the `kept` states, and the `kept` states in
+    /// which these paths end.
+    fn bypass(&self, state:State, kept:&[bool]) -> (StateSetId,StateSetId) {
+        let mut merged   = StateSetId::new();
+        let mut linked   = StateSetId::new();
+        let mut to_visit = vec![state];
+        while let Some(current) = to_visit.pop() {
+            if merged.insert(current) {
+                for &target in &self[current].epsilon_links {
+                    if kept[target.id()] { linked.insert(target); } else { to_visit.push(target) }
+                }
+            }
+        }
+        (merged,linked)
+    }
+
     /// Computes a transition matrix `(state, symbol) => state` for the Nfa, ignoring epsilon links.
     pub fn nfa_matrix(&self) -> Matrix<State> {
         let mut matrix = Matrix::new(self.states.len(),self.alphabet.divisions.len());
@@ -243,25 +670,11 @@ impl Nfa {
     }
 
     /// Convert the automata to a GraphViz Dot code for the deubgging purposes.
+    ///
+    /// Use [`Diagram`] to highlight the accepting states, or to render the automaton in other
+    /// formats.
     pub fn as_graphviz_code(&self) -> String {
-        let mut out = String::new();
-        for (ix,state) in self.states.iter().enumerate() {
-            let opts = if state.export { "" } else {
-                "[fillcolor=\"#EEEEEE\" fontcolor=\"#888888\"]"
-            };
-            out += &format!("node_{}[label=\"{}\"]{}\n",ix,ix,opts);
-            for link in &state.links {
-                out += &format!(
-                    "node_{} -> node_{}[label=\"{}\"]\n",ix,link.target.id(),link.display_symbols()
-                );
-            }
-            for link in &state.epsilon_links {
-                out += &format!("node_{} -> node_{}[style=dashed]\n",ix,link.id());
-            }
-        }
-        let opts = "node [shape=circle style=filled fillcolor=\"#4385f5\" fontcolor=\"#FFFFFF\" \
-        color=white penwidth=5.0 margin=0.1 width=0.5 height=0.5 fixedsize=true]";
-        format!("digraph G {{\n{}\n{}\n}}\n",opts,out)
+        Diagram::graphviz().render_nfa(self)
     }
 }
 
@@ -285,6 +698,75 @@ impl IndexMut<State> for Nfa {
 }
 
 
+// === Utilities ===
+
+/// The line anchors, followed by the symbols that end a line.
+pub(crate) fn line_symbols() -> [Symbol;4] {
+    [Symbol::line_start(),Symbol::line_end(),Symbol::from('\n'),Symbol::eof()]
+}
+
+/// The symbols on which the [`Pattern::Complement`] of a pattern can trigger.
+///
+/// Just like with [`Pattern::none_of`], these are the symbols between [`Symbol::null`] and
+/// [`Symbol::eof`], but the line anchors and [`Symbol::invalid`] are excluded too, as they are
+/// never a part of the input.
+fn complement_universe() -> RangeInclusive<Symbol> {
+    Symbol::from(Symbol::null().index + 1)..=Symbol::from(Symbol::line_start().index - 1)
+}
+
+/// The ranges of symbols covered by the divisions of the alphabets of all the `automata`, so that
+/// each of the automata takes the same transition on all the symbols of a range.
+fn common_ranges<'a>(automata:impl Iterator<Item=&'a Dfa>) -> Vec<RangeInclusive<Symbol>> {
+    let mut divisions = BTreeSet::new();
+    for dfa in automata {
+        divisions.extend(dfa.alphabet.keys().cloned());
+    }
+    let divisions = divisions.into_iter().collect_vec();
+    divisions.iter().enumerate().map(|(ix,start)| {
+        let next = divisions.get(ix+1);
+        let end  = next.map(|next| Symbol::from(next.index - 1)).unwrap_or_else(Symbol::max);
+        *start..=end
+    }).collect()
+}
+
+/// Get the states of the `dfa` from which one of the `accepting` states can be reached.
+fn live_states(dfa:&Dfa, accepting:&[bool]) -> Vec<bool> {
+    let columns   = dfa.alphabet.division_map.len();
+    let mut live  = accepting.to_vec();
+    let mut grown = true;
+    while grown {
+        grown = false;
+        for row in 0..live.len() {
+            let leads_to_live = (0..columns).any(|column| {
+                let target = dfa.links[(row,column)];
+                !target.is_invalid() && live[target.id()]
+            });
+            if !live[row] && leads_to_live {
+                live[row] = true;
+                grown     = true;
+            }
+        }
+    }
+    live
+}
+
+/// Add the transition on the `range` of symbols to the `links`, merging it with the last of them if
+/// their ranges are adjacent and they lead to the same `target`.
+///
+/// The transitions on the line anchors are never merged, as they only trigger on exactly the
+/// anchor.
+fn merge_link
+(links:&mut Vec<(RangeInclusive<Symbol>,State)>, range:&RangeInclusive<Symbol>, target:State) {
+    match links.last_mut() {
+        Some((last,last_target)) if *last_target == target
+            && last.end().index + 1 == range.start().index
+            && !last.end().is_anchor() && !range.start().is_anchor() =>
+            *last = *last.start()..=*range.end(),
+        _ => links.push((range.clone(),target)),
+    }
+}
+
+
 
 // ===========
 // == Tests ==
@@ -392,6 +874,20 @@ pub mod tests {
         }
     }
 
+    /// An automaton with a pattern starting in its start state for each of the `patterns`, together
+    /// with the end states of these patterns.
+    pub(crate) fn nfa_for(patterns:&[Pattern]) -> (Nfa,Vec<State>) {
+        let mut nfa = Nfa::new();
+        let ends    = patterns.iter().map(|pattern| nfa.new_pattern(nfa.start,pattern));
+        let ends    = ends.collect_vec();
+        (nfa,ends)
+    }
+
+    /// Just like [`nfa_for`], but with the patterns parsed from the `regexes`.
+    pub(crate) fn nfa_for_regexes(regexes:&[&str]) -> (Nfa,Vec<State>) {
+        nfa_for(&regexes.iter().map(|regex| Pattern::parse(regex).unwrap()).collect_vec())
+    }
+
 
     // === The Automata ===
 
@@ -415,6 +911,11 @@ pub mod tests {
         NfaTest::make(vec![pattern])
     }
 
+    pub fn pattern_repeat_between() -> NfaTest {
+        let pattern = Pattern::repeat_between(&Pattern::char('a'),1,3);
+        NfaTest::make(vec![pattern])
+    }
+
     pub fn pattern_always() -> NfaTest {
         let pattern = Pattern::always();
         NfaTest::make(vec![pattern])
@@ -536,6 +1037,39 @@ pub mod tests {
         assert!(nfa[nfa.end_state_id].export);
     }
 
+    #[test]
+    fn nfa_pattern_repeat_between() {
+        let nfa = pattern_repeat_between();
+
+        assert_eq!(nfa.states.len(),8);
+        assert!(nfa.has_epsilon(nfa.start_state_id,NfaTest::id(2)));
+        assert!(nfa.has_epsilon(NfaTest::id(2),NfaTest::id(3)));
+        assert!(nfa.has_transition(Symbol::from('a')..=Symbol::from('a'),NfaTest::id(4)));
+        assert!(nfa.has_epsilon(NfaTest::id(4),NfaTest::id(5)));
+        assert!(nfa.has_epsilon(NfaTest::id(4),NfaTest::id(6)));
+        assert!(nfa.has_transition(Symbol::from('a')..=Symbol::from('a'),NfaTest::id(7)));
+        assert!(nfa.has_epsilon(NfaTest::id(7),NfaTest::id(5)));
+        assert!(nfa.has_epsilon(NfaTest::id(5),nfa.end_state_id));
+        assert_eq!(nfa.pattern_state_ids[0],NfaTest::id(5));
+    }
+
+    #[test]
+    fn nfa_pattern_repeat_is_linear() {
+        let digit   = Pattern::range('0'..='9');
+        let pattern = Pattern::repeat_between(&digit,1,65);
+        let nfa     = NfaTest::make(vec![pattern]);
+        assert_eq!(nfa.states.len(),2 + 2 + 64 * 2);
+    }
+
+    #[test]
+    fn nfa_pattern_is_simplified() {
+        let nested = Pattern::all_of("abc") | Pattern::all_of("abd") | Pattern::never();
+        let flat   = Pattern::all_of("ab") >> (Pattern::char('c') | Pattern::char('d'));
+        let nfa    = NfaTest::make(vec![nested]);
+        assert_eq!(nfa.states.len(),NfaTest::make(vec![flat.simplify()]).states.len());
+        assert_eq!(nfa.states.len(),2 + 1 + 3 * 2);
+    }
+
     #[test]
     fn nfa_pattern_always() {
         let nfa = pattern_always();
@@ -640,4 +1174,56 @@ pub mod tests {
             Some(&("self.on_b_word(reader)".to_string()))
         );
     }
+
+    #[test]
+    fn nfa_without_epsilons() {
+        let keywords = Pattern::all_of("if") | Pattern::all_of("in") | Pattern::all_of("int");
+        let word     = Pattern::range('a'..='z').many1() >> Pattern::char('?').opt();
+        let number   = Pattern::range('0'..='9').many1() >> Pattern::char('.').opt();
+        let automata = vec![simple_rules(),complex_rules(),named_rules(),pattern_never()];
+        let repeated = Pattern::repeat_between(&number,1,3);
+        let automata = automata.into_iter().chain(iter::once(NfaTest::make(vec![keywords,word])));
+        let automata = automata.chain(iter::once(NfaTest::make(vec![repeated])));
+        let epsilons = |nfa:&Nfa| nfa.states.iter().map(|s| s.epsilon_links.len()).sum::<usize>();
+        let links    = |nfa:&Nfa| nfa.states.iter().map(|s| s.links.len()).sum::<usize>();
+        for nfa in automata {
+            let (reduced,renumbered) = nfa.without_epsilons();
+            let mut expected         = Dfa::from(&nfa.nfa);
+            for source in expected.sources.iter_mut().flatten() {
+                *source = renumbered[source.id()];
+            }
+            assert_eq!(Dfa::from(&reduced),expected);
+            assert!(reduced.states.len() < nfa.states.len());
+            assert!(links(&reduced) + epsilons(&reduced) < links(&nfa) + epsilons(&nfa));
+            assert!(epsilons(&reduced) < epsilons(&nfa));
+        }
+    }
+
+    #[test]
+    fn nfa_lookahead() {
+        let word          = Pattern::range('a'..='z').many1();
+        let mut nfa       = Nfa::new();
+        let call          = nfa.new_pattern(nfa.start,word.followed_by(&Pattern::char('(')));
+        let keyword       = nfa.new_pattern(nfa.start,Pattern::all_of("if").not_followed_by(&word));
+        let nested        = (Pattern::char('a').followed_by(&Pattern::char('b'))).many1();
+        let nested        = nfa.new_pattern(nfa.start,nested);
+        let (reduced,ids) = nfa.without_epsilons();
+        let dfa           = Dfa::from(&reduced);
+        let call_body     = nfa.lookaheads()[&call];
+        let length        = |input:&str, state:State| {
+            dfa.longest_match(input,&[ids[state.id()]]).map(|found| found.length)
+        };
+        let reduced_body = reduced.lookaheads()[&ids[call.id()]];
+        assert_eq!(reduced_body,ids[call_body.id()]);
+        assert_eq!(nfa.lookaheads().len(),2);
+        assert!(!nfa.lookaheads().contains_key(&nested));
+        assert_eq!(length("foo(x)",call),Some(4));
+        assert_eq!(length("foo",call),None);
+        assert_eq!(length("foo",call_body),Some(3));
+        assert_eq!(length("if x",keyword),Some(3));
+        assert_eq!(length("if",keyword),Some(2));
+        assert_eq!(length("if(",keyword),Some(3));
+        assert_eq!(length("ifx",keyword),None);
+        assert_eq!(length("ababa",nested),Some(4));
+    }
 }
@@ -2,6 +2,7 @@
 
 use crate::prelude::*;
 
+use crate::nfa;
 use crate::symbol::Symbol;
 
 use core::iter;
@@ -9,22 +10,87 @@ use std::ops::BitOr;
 use std::ops::RangeInclusive;
 use std::ops::Shr;
 
+pub mod parser;
+pub mod unicode;
+
+mod printer;
+
+pub use unicode::GeneralCategory;
+
+
+// =============
+// === Error ===
+// =============
+
+/// The reasons for which a pattern cannot be used as the pattern of a rule, as reported by
+/// [`Pattern::check`].
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub enum Error {
+    /// The lookahead is a part of another pattern.
+    NestedLookahead(Pattern),
+    /// The body of the lookahead can trigger on an input extending into its context.
+    AmbiguousLookahead(Pattern),
+}
+
+
+// === Trait Impls ===
+
+impl Display for Error {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NestedLookahead(pattern) => write!(f,
+                "The lookahead `{}` is not the outermost pattern.",pattern
+            ),
+            Error::AmbiguousLookahead(pattern) => write!(f,
+                "The body of the lookahead `{}` can trigger on its trailing context.",pattern
+            ),
+        }
+    }
+}
+
+
 
 // =============
 // == Pattern ==
 // =============
 
 /// A representation of a simple regular pattern.
-#[derive(Clone,Debug,PartialEq)]
+#[derive(Clone,Debug,Eq,PartialEq)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
 pub enum Pattern {
     /// The pattern that triggers on any symbol from the given range.
     Range(RangeInclusive<Symbol>),
+    /// The pattern that triggers on any symbol from the given sorted and disjoint ranges.
+    Class(Vec<RangeInclusive<Symbol>>),
     /// The pattern that triggers on any given pattern from a sequence.
     Or(Vec<Pattern>),
     /// The pattern that triggers when a sequence of patterns is encountered.
     Seq(Vec<Pattern>),
     /// The pattern that triggers on 0..N repetitions of given pattern.
     Many(Box<Pattern>),
+    /// The pattern that triggers on `min..=max` repetitions of the `body`, or on at least `min`
+    /// repetitions if there is no `max`.
+    #[allow(missing_docs)]
+    Repeat {body:Box<Pattern>, min:usize, max:Option<usize>},
+    /// The pattern that triggers when all of the given patterns trigger on the same input.
+    And(Vec<Pattern>),
+    /// The pattern that triggers when the first pattern triggers and the second one does not.
+    Minus(Box<Pattern>,Box<Pattern>),
+    /// The pattern that triggers on any sequence of symbols (excluding [`Symbol::eof`]) that the
+    /// given pattern does not trigger on.
+    Complement(Box<Pattern>),
+    /// The pattern that triggers on the `body` followed by an input on which the `context`
+    /// triggers, or does not trigger if `negated`. See [`Pattern::followed_by`] for details.
+    #[allow(missing_docs)]
+    Lookahead {body:Box<Pattern>, context:Box<Pattern>, negated:bool},
+    /// The pattern that triggers on the `body`, recording the span of the input it triggers on as
+    /// the capture group called `name`. See [`Pattern::capture`] for details.
+    #[allow(missing_docs)]
+    Capture {name:String, body:Box<Pattern>},
+    /// The pattern that triggers at the start of a line without consuming any input.
+    LineStart,
+    /// The pattern that triggers at the end of a line without consuming any input.
+    LineEnd,
     /// The pattern that always triggers without consuming any input.
     Always,
     /// The pattern that never triggers and does not consume any input.
@@ -73,6 +139,108 @@ impl Pattern {
         self | Self::always()
     }
 
+    /// A pattern that triggers when both `self` and `other` trigger on the same input.
+    pub fn and(&self, other:&Pattern) -> Self {
+        use Pattern::*;
+        match (self.clone(),other.clone()) {
+            (And(mut lhs), And(rhs)    ) => {lhs.extend(rhs)   ; And(lhs)},
+            (And(mut lhs), rhs         ) => {lhs.push(rhs)     ; And(lhs)},
+            (lhs         , And(mut rhs)) => {rhs.insert(0,lhs) ; And(rhs)},
+            (lhs         , rhs         ) => And(vec![lhs,rhs]),
+        }
+    }
+
+    /// A pattern that triggers when `self` triggers, but `other` does not trigger on the same
+    /// input.
+    ///
+    /// For example, an identifier that is not a keyword can be described as
+    /// `identifier.minus(&(literal!("if") | literal!("then")))`.
+    pub fn minus(&self, other:&Pattern) -> Self {
+        Pattern::Minus(Box::new(self.clone()),Box::new(other.clone()))
+    }
+
+    /// A pattern that triggers on any sequence of symbols that `self` does not trigger on.
+    ///
+    /// Please note that the complement is taken with respect to sequences of any symbols but
+    /// [`Symbol::eof`], so that it never consumes the end of the input. Use [`Pattern::minus`] to
+    /// restrict the result to the sequences described by another pattern.
+    pub fn complement(&self) -> Self {
+        Pattern::Complement(Box::new(self.clone()))
+    }
+
+    /// A pattern that triggers on `self` only when it is followed by an input on which `context`
+    /// triggers, like the trailing context `r/s` of flex.
+    ///
+    /// The pattern consumes the context as well, but the [`crate::nfa::Nfa`] remembers where the
+    /// body ends, so that the lexers generated by the flexer can rewind the input to that point.
+    /// The patterns on which this cannot be done are rejected by [`Pattern::check`].
+    pub fn followed_by(&self, context:&Pattern) -> Self {
+        let body    = Box::new(self.clone());
+        let context = Box::new(context.clone());
+        Pattern::Lookahead {body,context,negated:false}
+    }
+
+    /// A pattern that triggers on `self` only when it is not followed by an input on which
+    /// `context` triggers, including when it is followed by the end of the input.
+    ///
+    /// The trailing context of the pattern is the shortest input proving that `context` cannot
+    /// trigger, so `keyword.not_followed_by(&identifier_char)` consumes one symbol more than the
+    /// keyword. See [`Pattern::followed_by`] for the details.
+    pub fn not_followed_by(&self, context:&Pattern) -> Self {
+        let body    = Box::new(self.clone());
+        let context = Box::new(context.clone());
+        Pattern::Lookahead {body,context,negated:true}
+    }
+
+    /// A pattern that triggers on `body`, recording the span of the input that `body` triggers on
+    /// as the capture group called `name`.
+    ///
+    /// The capture groups do not change the input on which a pattern triggers. The DFA of the
+    /// pattern records the boundaries of the groups in registers while it is matching, so that the
+    /// spans are known as soon as the match is found. See the [`crate::capture`] module for
+    /// details.
+    pub fn capture(name:impl Into<String>, body:&Pattern) -> Self {
+        let name = name.into();
+        let body = Box::new(body.clone());
+        Pattern::Capture {name,body}
+    }
+
+    /// The names of the capture groups of the pattern, in the order in which they first start.
+    pub fn capture_names(&self) -> Vec<&str> {
+        use Pattern::*;
+        let names:Vec<&str> = match self {
+            Capture {name,body} => iter::once(name.as_str()).chain(body.capture_names()).collect(),
+            Or(patterns) | Seq(patterns) | And(patterns) => {
+                patterns.iter().flat_map(Self::capture_names).collect()
+            },
+            Many(body) | Repeat {body,..} | Complement(body) => body.capture_names(),
+            Minus(lhs,rhs) | Lookahead {body:lhs,context:rhs,..} => {
+                lhs.capture_names().into_iter().chain(rhs.capture_names()).collect()
+            },
+            Range(_) | Class(_) | LineStart | LineEnd | Always | Never => vec![],
+        };
+        names.into_iter().unique().collect()
+    }
+
+    /// A pattern that triggers at the start of a line, that is at the start of the input or after
+    /// a `'\n'` character, without consuming any input.
+    ///
+    /// The automata take the transitions on [`Symbol::line_start`] wherever a line starts, and only
+    /// the transitions on exactly this symbol are taken, so that the patterns like
+    /// [`Pattern::any`] never trigger on it.
+    pub fn line_start() -> Self {
+        Pattern::LineStart
+    }
+
+    /// A pattern that triggers at the end of a line, that is before a `'\n'` character or at the
+    /// end of the input, without consuming any input.
+    ///
+    /// Please note that a line end directly followed by a line start, as in `a$^`, never triggers,
+    /// as the line start is only recognized after the `'\n'` character is consumed.
+    pub fn line_end() -> Self {
+        Pattern::LineEnd
+    }
+
     /// A pattern that triggers on the given character.
     pub fn char(character:char) -> Self {
         Self::symbol(&Symbol::from(character))
@@ -80,7 +248,7 @@ impl Pattern {
 
     /// A pattern that triggers on the given symbol.
     pub fn symbol(symbol:&Symbol) -> Self {
-        Pattern::symbols(symbol.clone()..=symbol.clone())
+        Pattern::symbols(*symbol..=*symbol)
     }
 
     /// A pattern that triggers on any of the provided `symbols`.
@@ -88,6 +256,32 @@ impl Pattern {
         Pattern::Range(symbols)
     }
 
+    /// A pattern that triggers on any symbol contained in any of the provided `ranges`.
+    ///
+    /// The ranges are sorted and the overlapping or adjacent ones are merged, so that the result
+    /// is the smallest equivalent [`Pattern::Range`] or [`Pattern::Class`].
+    pub fn class(ranges:impl IntoIterator<Item=RangeInclusive<Symbol>>) -> Self {
+        let ranges     = ranges.into_iter().filter(|range| range.start() <= range.end());
+        let mut ranges = ranges.collect_vec();
+        ranges.sort_by(|lhs,rhs| lhs.start().cmp(rhs.start()));
+        let mut merged:Vec<RangeInclusive<Symbol>> = vec![];
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start().index <= last.end().index.saturating_add(1) => {
+                    if range.end() > last.end() {
+                        *last = *last.start()..=*range.end();
+                    }
+                },
+                _ => merged.push(range),
+            }
+        }
+        match merged.len() {
+            0 => Pattern::Never,
+            1 => Pattern::Range(merged.remove(0)),
+            _ => Pattern::Class(merged),
+        }
+    }
+
     /// A pattern that triggers at the end of the file.
     pub fn eof() -> Self {
         Self::symbol(&Symbol::eof())
@@ -103,6 +297,14 @@ impl Pattern {
         chars.chars().fold(Self::always(),|pat,char| pat >> Self::char(char))
     }
 
+    /// Pattern that triggers when sequence of characters given by `chars` is encountered, ignoring
+    /// the case of the characters.
+    ///
+    /// See [`Pattern::case_insensitive`] for details.
+    pub fn all_of_ci(chars:&str) -> Self {
+        Self::all_of(chars).case_insensitive()
+    }
+
     /// The pattern that triggers on any characters contained in `chars`.
     pub fn any_of(chars:&str) -> Self {
         chars.chars().fold(Self::never(),|pat,char| pat | Self::char(char))
@@ -114,35 +316,27 @@ impl Pattern {
     /// the excluded characters. If you do not want this behaviour instead use
     /// [`Pattern::none_of_codes`] below.
     pub fn none_of(chars:&str) -> Self {
-        let min       = Symbol::null();
-        let max       = Symbol::eof();
-        let iter      = iter::once(min.index)
+        let min   = Symbol::null();
+        let max   = Symbol::eof();
+        let codes = iter::once(min.index)
             .chain(chars.chars().map(|c| c as u64))
             .chain(iter::once(max.index))
             .collect_vec();
-        let names = iter::once(min.name)
-            .chain(chars.chars().map(|c| c.to_string()))
-            .chain(iter::once(max.name))
-            .collect_vec();
-        Self::none_of_codes(iter.as_slice(),names.as_slice())
+        Self::none_of_codes(codes.as_slice())
     }
 
     /// This pattern doesn't trigger on any code contained in `codes`.
-    pub fn none_of_codes(codes:&[u64],names:&[String]) -> Self {
-        if codes.len() != names.len() {
-            panic!("`codes` and `names`must have the same length.");
-        }
+    pub fn none_of_codes(codes:&[u64]) -> Self {
         let mut codes = Vec::from(codes);
         codes.sort_unstable();
         codes.dedup();
-        let pattern = codes.iter().tuple_windows().zip(names)
-            .fold(Self::never(),|pat,((prev_code,next_code),name)| {
-                let start = prev_code + 1;
-                let end   = next_code - 1;
-                if end < start { pat } else {
-                    pat | Pattern::symbols(Symbol::new_named(start,name)..=Symbol::from(end))
-                }
-            });
+        let pattern = codes.iter().tuple_windows().fold(Self::never(),|pat,(prev_code,next_code)| {
+            let start = prev_code + 1;
+            let end   = next_code - 1;
+            if end < start { pat } else {
+                pat | Pattern::symbols(Symbol::from(start)..=Symbol::from(end))
+            }
+        });
         if codes.contains(&Symbol::null().index) && codes.contains(&Symbol::eof().index) {
             pattern
         } else if codes.contains(&Symbol::null().index) {
@@ -189,7 +383,240 @@ impl Pattern {
 
     /// Pattern that triggers on `min`..`max` repetitions of `pat`.
     pub fn repeat_between(pat:&Pattern, min:usize, max:usize) -> Self {
-        (min..max).fold(Self::never(),|p,n| p | Self::repeat(pat,n))
+        if max <= min { Self::never() } else {
+            let body = Box::new(pat.clone());
+            let max  = Some(max - 1);
+            Pattern::Repeat {body,min,max}
+        }
+    }
+
+    /// Pattern that triggers on `min` or more repetitions of `pat`.
+    pub fn repeat_at_least(pat:&Pattern, min:usize) -> Self {
+        let body = Box::new(pat.clone());
+        let max  = None;
+        Pattern::Repeat {body,min,max}
+    }
+
+    /// Parse a pattern from the provided `regex`.
+    ///
+    /// See the [`parser`] module for the description of the supported syntax.
+    pub fn parse(regex:&str) -> Result<Self,parser::Error> {
+        parser::parse(regex)
+    }
+}
+
+
+// === Validation ===
+
+impl Pattern {
+    /// Check whether the pattern can be used as the pattern of a rule of a lexer.
+    ///
+    /// A [`Pattern::Lookahead`] has to be the outermost pattern, once the pattern is
+    /// [simplified](Pattern::simplify), as the lexers only remember the end of the body of such a
+    /// lookahead. Its body cannot trigger on an input extending into its context either, as in
+    /// `a*` followed by `ab`, as the end of the body would then be ambiguous.
+    pub fn check(&self) -> Result<(),Error> {
+        match self.simplify() {
+            Pattern::Lookahead {body,context,negated} => {
+                let nested = body.nested_lookahead().or_else(|| context.nested_lookahead());
+                if let Some(nested) = nested {
+                    return Err(Error::NestedLookahead(nested.clone()))
+                }
+                if nfa::lookahead_is_ambiguous(&body,&context,negated) {
+                    return Err(Error::AmbiguousLookahead(self.clone()))
+                }
+                Ok(())
+            },
+            pattern => match pattern.nested_lookahead() {
+                Some(nested) => Err(Error::NestedLookahead(nested.clone())),
+                None         => Ok(()),
+            },
+        }
+    }
+
+    /// Get the first [`Pattern::Lookahead`] contained in the pattern, if there is one.
+    fn nested_lookahead(&self) -> Option<&Pattern> {
+        use Pattern::*;
+        match self {
+            Lookahead {..} => Some(self),
+            Or(patterns) | Seq(patterns) | And(patterns) => {
+                patterns.iter().find_map(Self::nested_lookahead)
+            },
+            Many(body) | Repeat {body,..} | Complement(body) | Capture {body,..} => {
+                body.nested_lookahead()
+            },
+            Minus(lhs,rhs) => lhs.nested_lookahead().or_else(|| rhs.nested_lookahead()),
+            Range(_) | Class(_) | LineStart | LineEnd | Always | Never => None,
+        }
+    }
+}
+
+
+// === Simplification ===
+
+impl Pattern {
+    /// Rewrite the pattern to a smaller pattern that triggers on the same input.
+    ///
+    /// Patterns built with the `|` and `>>` operators, or folded from [`Pattern::always`] and
+    /// [`Pattern::never`], tend to be deeply nested, and every level of nesting costs additional
+    /// epsilon-linked states of the NFA. The simplification:
+    /// - flattens nested sequences and alternatives,
+    /// - removes [`Pattern::Always`] from sequences and [`Pattern::Never`] from alternatives,
+    ///   collapsing the sequences that contain [`Pattern::Never`] to [`Pattern::Never`],
+    /// - merges the alternatives of symbol ranges that overlap or are adjacent,
+    /// - factors out the common prefixes of alternatives, so that `ab | ac` becomes `a(b | c)`,
+    /// - removes the repetitions of patterns that do not consume any input.
+    ///
+    /// The simplification is applied automatically by [`crate::nfa::Nfa::new_pattern`].
+    pub fn simplify(&self) -> Self {
+        use Pattern::*;
+        match self {
+            Range(_) | Class(_) | LineStart | LineEnd | Always | Never => self.clone(),
+            Seq(patterns) => Self::simplify_seq(patterns.iter().map(Self::simplify).collect()),
+            Or(patterns)  => Self::simplify_or(patterns.iter().map(Self::simplify).collect()),
+            Many(body)    => match body.simplify() {
+                LineStart | LineEnd | Always | Never => Always,
+                Many(body)     => Many(body),
+                body           => body.many(),
+            },
+            Repeat {body,min,max} => match (body.simplify(),*min,*max) {
+                (_         ,0  ,Some(0)) => Always,
+                (Always    ,_  ,_      ) => Always,
+                (Never     ,0  ,_      ) => Always,
+                (Never     ,_  ,_      ) => Never,
+                (LineStart ,0  ,_      ) => Always,
+                (LineEnd   ,0  ,_      ) => Always,
+                (LineStart ,_  ,_      ) => LineStart,
+                (LineEnd   ,_  ,_      ) => LineEnd,
+                (Many(body),0  ,None   ) => Many(body),
+                (body      ,0  ,None   ) => body.many(),
+                (body      ,1  ,Some(1)) => body,
+                (body      ,min,max    ) => Repeat {body:Box::new(body),min,max},
+            },
+            And(patterns) => {
+                let mut operands = vec![];
+                for pattern in patterns.iter().map(Self::simplify) {
+                    match pattern {
+                        And(patterns) => operands.extend(patterns),
+                        Never         => return Never,
+                        pattern       => operands.push(pattern),
+                    }
+                }
+                if operands.len() == 1 { operands.remove(0) } else { And(operands) }
+            },
+            Minus(lhs,rhs) => match (lhs.simplify(),rhs.simplify()) {
+                (Never,_    ) => Never,
+                (lhs  ,Never) => lhs,
+                (lhs  ,rhs  ) => lhs.minus(&rhs),
+            },
+            Complement(body) => body.simplify().complement(),
+            Capture {name,body} => match body.simplify() {
+                Never => Never,
+                body  => Capture {name:name.clone(),body:Box::new(body)},
+            },
+            Lookahead {body,context,negated} => {
+                match (body.simplify(),context.simplify(),*negated) {
+                    (Never,_      ,_      ) => Never,
+                    (_    ,Never  ,false  ) => Never,
+                    (_    ,Always ,true   ) => Never,
+                    (body ,Always ,false  ) => body,
+                    (body ,context,negated) => {
+                        let body    = Box::new(body);
+                        let context = Box::new(context);
+                        Lookahead {body,context,negated}
+                    },
+                }
+            },
+        }
+    }
+
+    /// Build a sequence of the already simplified `patterns`.
+    fn simplify_seq(patterns:Vec<Pattern>) -> Self {
+        let mut items = vec![];
+        for pattern in patterns {
+            match pattern {
+                Pattern::Seq(patterns) => items.extend(patterns),
+                Pattern::Always        => {},
+                Pattern::Never         => return Pattern::Never,
+                pattern                => items.push(pattern),
+            }
+        }
+        match items.len() {
+            0 => Pattern::Always,
+            1 => items.remove(0),
+            _ => Pattern::Seq(items),
+        }
+    }
+
+    /// Build an alternative of the already simplified `patterns`.
+    fn simplify_or(patterns:Vec<Pattern>) -> Self {
+        let mut alternatives:Vec<Pattern> = vec![];
+        for pattern in patterns {
+            match pattern {
+                Pattern::Or(patterns) => alternatives.extend(patterns),
+                Pattern::Never        => {},
+                pattern               => alternatives.push(pattern),
+            }
+        }
+        let alternatives = Self::merge_ranges(alternatives);
+        let alternatives = Self::factor_prefixes(alternatives);
+        let mut unique   = vec![];
+        for alternative in alternatives {
+            if !unique.contains(&alternative) { unique.push(alternative) }
+        }
+        match unique.len() {
+            0 => Pattern::Never,
+            1 => unique.remove(0),
+            _ => Pattern::Or(unique),
+        }
+    }
+
+    /// Merge the symbol ranges of the `alternatives`, if any of them overlap or are adjacent.
+    ///
+    /// The merged ranges take the place of the first alternative that is a range.
+    fn merge_ranges(alternatives:Vec<Pattern>) -> Vec<Pattern> {
+        let mut ranges = vec![];
+        for alternative in &alternatives {
+            match alternative {
+                Pattern::Range(range)  => ranges.push(range.clone()),
+                Pattern::Class(class)  => ranges.extend(class.iter().cloned()),
+                _                      => {},
+            }
+        }
+        let merged       = Pattern::class(ranges.iter().cloned());
+        let merged_count = match &merged {
+            Pattern::Class(class) => class.len(),
+            _                     => 1,
+        };
+        if ranges.is_empty() || merged_count == ranges.len() { return alternatives }
+        let mut merged = Some(merged);
+        alternatives.into_iter().filter_map(|alternative| match alternative {
+            Pattern::Range(_) | Pattern::Class(_) => merged.take(),
+            alternative                           => Some(alternative),
+        }).collect()
+    }
+
+    /// Factor out the common first items of the `alternatives`.
+    ///
+    /// The alternatives with a common first item are grouped in the place of the first of them.
+    fn factor_prefixes(alternatives:Vec<Pattern>) -> Vec<Pattern> {
+        let mut groups:Vec<(Option<Pattern>,Vec<Pattern>)> = vec![];
+        for alternative in alternatives {
+            let (first,rest) = match alternative {
+                Pattern::Always       => (None,Pattern::Always),
+                Pattern::Seq(mut seq) => (Some(seq.remove(0)),Self::simplify_seq(seq)),
+                alternative           => (Some(alternative),Pattern::Always),
+            };
+            let group = groups.iter_mut().find(|(prefix,_)| prefix.is_some() && *prefix == first);
+            match group {
+                Some((_,suffixes)) => suffixes.push(rest),
+                None               => groups.push((first,vec![rest])),
+            }
+        }
+        groups.into_iter().map(|(first,suffixes)| match first {
+            None        => Pattern::Always,
+            Some(first) => Self::simplify_seq(vec![first,Self::simplify_or(suffixes)]),
+        }).collect()
     }
 }
 
@@ -208,6 +635,15 @@ impl From<char> for Pattern {
     }
 }
 
+impl Display for Pattern {
+    /// Print the pattern in the regex syntax accepted by [`Pattern::parse`].
+    ///
+    /// See the [`printer`] module for details.
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,"{}",printer::print(self))
+    }
+}
+
 impl AsRef<Pattern> for Pattern {
     fn as_ref(&self) -> &Pattern {
         self
@@ -369,7 +805,7 @@ mod tests {
 
     #[test]
     fn pattern_none_of_codes() {
-        let none_of  = Pattern::none_of_codes(&[33,37],&["!".to_string(),"%".to_string()]);
+        let none_of  = Pattern::none_of_codes(&[33,37]);
         let expected = Pattern::Range(Symbol::null()..=Symbol::from(32u64))
                      | Pattern::Never
                      | Pattern::Range(Symbol::from(34u64)..=Symbol::from(36u64))
@@ -403,8 +839,9 @@ mod tests {
     #[test]
     fn pattern_repeat_between() {
         let repeat_between = Pattern::repeat_between(&char!('a'),2,4);
-        let expected       = Pattern::never() | Pattern::all_of("aa") | Pattern::all_of("aaa");
+        let expected       = Pattern::Repeat {body:Box::new(char!('a')),min:2,max:Some(3)};
         assert_eq!(repeat_between,expected);
+        assert_eq!(Pattern::repeat_between(&char!('a'),2,2),Pattern::never());
     }
 
     #[test]
@@ -470,4 +907,96 @@ mod tests {
         let explicit   = Pattern::all_of("abcde");
         assert_eq!(with_macro,explicit);
     }
+
+    #[test]
+    fn pattern_simplify_flattens() {
+        let nested = Pattern::all_of("ab") >> (Pattern::always() >> Pattern::all_of("c"));
+        assert_eq!(nested.simplify(),Pattern::Seq(vec![char!('a'),char!('b'),char!('c')]));
+        let nested = (char!('a') | (char!('c') | Pattern::never())) | char!('e');
+        assert_eq!(nested.simplify(),Pattern::Or(vec![char!('a'),char!('c'),char!('e')]));
+        assert_eq!(Pattern::all_of("").simplify(),Pattern::always());
+        assert_eq!(Pattern::any_of("").simplify(),Pattern::never());
+    }
+
+    #[test]
+    fn pattern_simplify_units() {
+        let seq = literal!("ab") >> Pattern::never() >> literal!("c");
+        assert_eq!(seq.simplify(),Pattern::never());
+        assert_eq!(Pattern::always().many().simplify(),Pattern::always());
+        assert_eq!(char!('a').many().many().simplify(),char!('a').many());
+        assert_eq!(Pattern::repeat_between(&Pattern::never(),1,3).simplify(),Pattern::never());
+        assert_eq!(Pattern::repeat_at_least(&char!('a'),0).simplify(),char!('a').many());
+        assert_eq!(char!('a').minus(&Pattern::never()).simplify(),char!('a'));
+        assert_eq!(char!('a').followed_by(&Pattern::always()).simplify(),char!('a'));
+        assert_eq!(char!('a').followed_by(&Pattern::never()).simplify(),Pattern::never());
+        assert_eq!(char!('a').not_followed_by(&Pattern::always()).simplify(),Pattern::never());
+        let lookahead = literal!("if").not_followed_by(&(Pattern::always() >> char!('x')));
+        let expected  = literal!("if").simplify().not_followed_by(&char!('x'));
+        assert_eq!(lookahead.simplify(),expected);
+        assert_eq!(Pattern::line_start().many().simplify(),Pattern::always());
+        assert_eq!(Pattern::repeat_at_least(&Pattern::line_end(),2).simplify(),Pattern::line_end());
+        assert_eq!(Pattern::capture("x",&Pattern::never()).simplify(),Pattern::never());
+        let capture = Pattern::capture("x",&(Pattern::always() >> char!('a')));
+        assert_eq!(capture.simplify(),Pattern::capture("x",&char!('a')));
+    }
+
+    #[test]
+    fn pattern_capture_names() {
+        let key     = Pattern::capture("key",&Pattern::range('a'..='z'));
+        let value   = Pattern::capture("value",&Pattern::range('0'..='9').many1());
+        let pattern = Pattern::capture("pair",&(key.many1() >> char!('=') >> value));
+        assert_eq!(pattern.capture_names(),vec!["pair","key","value"]);
+        assert!(literal!("ab").capture_names().is_empty());
+    }
+
+    #[test]
+    fn pattern_check_lookaheads() {
+        let word = Pattern::range('a'..='z').many1();
+        assert_eq!(word.followed_by(&char!('(')).check(),Ok(()));
+        assert_eq!(literal!("if").not_followed_by(&word).check(),Ok(()));
+        assert_eq!(char!('a').many1().not_followed_by(&char!('a')).check(),Ok(()));
+        let line = char!('a') >> Pattern::line_end().opt();
+        assert_eq!(line.followed_by(&char!('\n')).check(),Ok(()));
+        let nested = char!('a').followed_by(&char!('b'));
+        let error  = Err(Error::NestedLookahead(nested.clone()));
+        assert_eq!((&nested | char!('c')).check(),error);
+        assert_eq!(nested.many1().check(),error);
+        assert_eq!(word.followed_by(&nested).check(),error);
+        let ambiguous = char!('a').many().followed_by(&literal!("ab"));
+        assert_eq!(ambiguous.check(),Err(Error::AmbiguousLookahead(ambiguous.clone())));
+    }
+
+    #[test]
+    fn pattern_simplify_merges_ranges() {
+        let letters = Pattern::range('a'..='m') | Pattern::range('g'..='z') | char!('_');
+        let expected = Pattern::Class(vec![
+            Symbol::from('_')..=Symbol::from('_'),
+            Symbol::from('a')..=Symbol::from('z'),
+        ]);
+        assert_eq!(letters.simplify(),expected);
+        assert_eq!(Pattern::any_of("abc").simplify(),Pattern::range('a'..='c'));
+        let disjoint = char!('a') | char!('d');
+        assert_eq!(disjoint.simplify(),disjoint);
+    }
+
+    #[test]
+    fn pattern_simplify_factors_prefixes() {
+        let keywords = literal!("if") | literal!("in") | literal!("x") | literal!("int");
+        let expected = Pattern::Or(vec![
+            char!('i') >> (char!('f') | (char!('n') >> (Pattern::always() | char!('t')))),
+            char!('x'),
+        ]);
+        assert_eq!(keywords.simplify(),expected);
+        let shared = literal!("ab") | literal!("ac") | literal!("ab");
+        assert_eq!(shared.simplify(),char!('a') >> Pattern::range('b'..='c'));
+    }
+
+    #[test]
+    #[cfg(feature="serde")]
+    fn pattern_serde_round_trip() {
+        let pattern = (Pattern::range('a'..='z') | Pattern::eof()).many1() >> literal!("end").opt();
+        let pattern = pattern.minus(&literal!("stop")) >> Pattern::repeat_between(&char!('x'),1,3);
+        let json    = serde_json::to_string(&pattern).unwrap();
+        assert_eq!(serde_json::from_str::<Pattern>(&json).unwrap(),pattern);
+    }
 }
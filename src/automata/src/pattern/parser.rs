@@ -0,0 +1,683 @@
+//! A parser for the regex syntax of [`Pattern`]s.
+//!
+//! The supported syntax is the following:
+//!
+//! - Literal characters, like `a`, which trigger on themselves.
+//! - The wildcard `.`, which triggers on any character but a newline.
+//! - Character classes, like `[a-z_]`, and negated character classes, like `[^"\n]`.
+//! - Alternation (`a|b`) and grouping, as in `(ab)+` or in the non-capturing form `(?:ab)+`.
+//! - Named capture groups, like `(?<name>[a-z]+)`, described by [`Pattern::capture`]. The names
+//!   consist of ASCII letters, digits and underscores, and do not start with a digit.
+//! - The repetition operators `*`, `+` and `?`, as well as counted repetitions in the forms `{m}`,
+//!   `{m,}` and `{m,n}`, whose bounds cannot exceed [`MAX_REPETITION`].
+//! - The escapes `\n`, `\r`, `\t`, `\f`, `\v`, `\0`, `\xHH` and `\u{H...}`, the escaped
+//!   punctuation characters like `\*`, and the predefined classes `\d`, `\w`, `\s` together with
+//!   their negations `\D`, `\W` and `\S`.
+//! - The escape `\z`, which triggers on the end of the input, that is on [`Symbol::eof`].
+//! - The anchors `^` and `$`, which trigger at the start and at the end of a line, described by
+//!   [`Pattern::line_start`] and [`Pattern::line_end`].
+//! - The lookaheads `a(?=b)` and `a(?!b)`, described by [`Pattern::followed_by`] and
+//!   [`Pattern::not_followed_by`], which must end the regex, and cannot be a part of a group or of
+//!   an alternative.
+//!
+//! Negated classes, just like [`Pattern::none_of`], never trigger on [`Symbol::null`] and
+//! [`Symbol::eof`].
+
+use crate::prelude::*;
+
+use crate::pattern::Pattern;
+use crate::symbol::Symbol;
+use crate::symbol::SymbolIndex;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The largest bound of a counted repetition.
+///
+/// The automaton of a counted repetition holds a copy of the repeated pattern for each repetition
+/// up to its bound, so larger bounds would exhaust the memory when the automaton is built.
+pub const MAX_REPETITION:usize = 1000;
+
+
+
+// =============
+// === Error ===
+// =============
+
+/// An error that occurred when parsing a regex.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub struct Error {
+    /// The column at which the error occurred, counted in characters starting from 1.
+    pub column : usize,
+    /// The reason of the error.
+    pub kind : ErrorKind,
+}
+
+impl Error {
+    /// Constructor.
+    pub fn new(column:usize, kind:ErrorKind) -> Self {
+        Self {column,kind}
+    }
+}
+
+/// The reasons for which parsing a regex can fail.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum ErrorKind {
+    /// The regex ended unexpectedly.
+    UnexpectedEnd,
+    /// The character is not allowed at this position.
+    UnexpectedChar(char),
+    /// A repetition operator is not preceded by a pattern to repeat.
+    NothingToRepeat(char),
+    /// A group is never closed.
+    UnclosedGroup,
+    /// A character class is never closed.
+    UnclosedClass,
+    /// The start of a range in a character class is greater than its end.
+    InvalidRange(char,char),
+    /// The bounds of a counted repetition are malformed or exceed [`MAX_REPETITION`].
+    InvalidRepetition,
+    /// The escape sequence is not supported.
+    InvalidEscape(char),
+    /// The lookahead is a part of a group or of an alternative.
+    NestedLookahead,
+}
+
+
+// === Trait Impls ===
+
+impl Display for Error {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,"{} (at column {}).",self.kind,self.column)
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::UnexpectedEnd          => write!(f,"Unexpected end of the regex"),
+            ErrorKind::UnexpectedChar(char)   => write!(f,"Unexpected character `{}`",char),
+            ErrorKind::NothingToRepeat(char)  => write!(f,"Nothing to repeat with `{}`",char),
+            ErrorKind::UnclosedGroup          => write!(f,"The group is never closed"),
+            ErrorKind::UnclosedClass          => write!(f,"The character class is never closed"),
+            ErrorKind::InvalidRange(from,to)  => write!(f,"Invalid range `{}-{}`",from,to),
+            ErrorKind::InvalidRepetition      => write!(f,"Invalid counted repetition"),
+            ErrorKind::InvalidEscape(char)    => write!(f,"Invalid escape `\\{}`",char),
+            ErrorKind::NestedLookahead        => write!(f,"The lookahead is nested"),
+        }
+    }
+}
+
+
+
+// ===============
+// === Escaped ===
+// ===============
+
+/// The result of parsing an escape sequence.
+#[derive(Clone,Debug,Eq,PartialEq)]
+enum Escaped {
+    /// A single character.
+    Char(char),
+    /// A predefined class of characters, as a list of disjoint code ranges.
+    Class(Vec<RangeInclusive<SymbolIndex>>),
+}
+
+
+
+// ==============
+// === Parser ===
+// ==============
+
+/// A recursive-descent parser turning a regex into a [`Pattern`].
+///
+/// The `depth` counts the groups and the alternatives that the parser is in, as the lookaheads
+/// cannot be a part of them.
+#[derive(Clone,Debug)]
+struct Parser {
+    chars  : Vec<char>,
+    offset : usize,
+    depth  : usize,
+}
+
+impl Parser {
+    /// Constructor.
+    fn new(input:&str) -> Self {
+        let chars  = input.chars().collect();
+        let offset = 0;
+        let depth  = 0;
+        Self {chars,offset,depth}
+    }
+
+    /// Parse the whole input.
+    fn run(mut self) -> Result<Pattern,Error> {
+        let pattern = self.alternation()?;
+        match self.peek() {
+            None       => Ok(pattern),
+            Some(char) => Err(self.error(self.offset,ErrorKind::UnexpectedChar(char))),
+        }
+    }
+
+    /// Construct an error for the character at the given `offset`.
+    fn error(&self, offset:usize, kind:ErrorKind) -> Error {
+        Error::new(offset+1,kind)
+    }
+
+    /// Get the current character without consuming it.
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.offset).copied()
+    }
+
+    /// Consume the current character.
+    fn next(&mut self) -> Option<char> {
+        let char = self.peek();
+        if char.is_some() { self.offset += 1 }
+        char
+    }
+
+    /// Consume the current character if it is `char`.
+    fn eat(&mut self, char:char) -> bool {
+        let matches = self.peek() == Some(char);
+        if matches { self.offset += 1 }
+        matches
+    }
+
+    /// Parse a list of alternatives, separated by `|`.
+    fn alternation(&mut self) -> Result<Pattern,Error> {
+        let depth       = self.depth;
+        let mut pattern = self.sequence()?;
+        while self.eat('|') {
+            self.depth = depth + 1;
+            pattern    = pattern | self.sequence()?;
+        }
+        self.depth = depth;
+        Ok(pattern)
+    }
+
+    /// Parse a sequence of repeated atoms.
+    fn sequence(&mut self) -> Result<Pattern,Error> {
+        let mut pattern = None;
+        while let Some(char) = self.peek() {
+            if char == '|' || char == ')' { break }
+            let rest = &self.chars[self.offset..];
+            if rest.starts_with(&['(','?']) && matches!(rest.get(2),Some('='|'!')) {
+                return self.lookahead(pattern.unwrap_or_else(Pattern::always))
+            }
+            let item = self.repetition()?;
+            pattern  = Some(match pattern {
+                None          => item,
+                Some(pattern) => pattern >> item,
+            });
+        }
+        Ok(pattern.unwrap_or_else(Pattern::always))
+    }
+
+    /// Parse a lookahead of the `body`, in one of the forms `(?=...)` or `(?!...)`.
+    fn lookahead(&mut self, body:Pattern) -> Result<Pattern,Error> {
+        let start = self.offset;
+        if self.depth > 0 {
+            return Err(self.error(start,ErrorKind::NestedLookahead))
+        }
+        self.offset += 2;
+        let negated = match self.next() {
+            Some('=')  => false,
+            Some('!')  => true,
+            Some(char) => return Err(self.error(start+2,ErrorKind::UnexpectedChar(char))),
+            None       => return Err(self.error(start+2,ErrorKind::UnexpectedEnd)),
+        };
+        self.depth += 1;
+        let context = self.alternation()?;
+        self.depth -= 1;
+        if !self.eat(')') {
+            return Err(self.error(start,ErrorKind::UnclosedGroup))
+        }
+        match self.peek() {
+            None if negated => Ok(body.not_followed_by(&context)),
+            None            => Ok(body.followed_by(&context)),
+            Some('|')       => Err(self.error(start,ErrorKind::NestedLookahead)),
+            Some(char)      => Err(self.error(self.offset,ErrorKind::UnexpectedChar(char))),
+        }
+    }
+
+    /// Parse an atom, followed by any number of repetition operators.
+    fn repetition(&mut self) -> Result<Pattern,Error> {
+        let mut pattern = self.atom()?;
+        loop {
+            pattern = match self.peek() {
+                Some('*') => { self.next(); pattern.many()  },
+                Some('+') => { self.next(); pattern.many1() },
+                Some('?') => { self.next(); pattern.opt()   },
+                Some('{') => {
+                    let (min,max) = self.counted_repetition()?;
+                    let body      = Box::new(pattern);
+                    Pattern::Repeat {body,min,max}
+                },
+                _ => break Ok(pattern),
+            }
+        }
+    }
+
+    /// Parse the bounds of a counted repetition, in one of the forms `{m}`, `{m,}` or `{m,n}`.
+    ///
+    /// The bounds cannot exceed [`MAX_REPETITION`].
+    fn counted_repetition(&mut self) -> Result<(usize,Option<usize>),Error> {
+        let start   = self.offset;
+        let invalid = |this:&Self| this.error(start,ErrorKind::InvalidRepetition);
+        self.next();
+        let min = self.number().ok_or_else(|| invalid(self))?;
+        let max = if self.eat(',') { self.number() } else { Some(min) };
+        let too_large = min > MAX_REPETITION || matches!(max,Some(max) if max > MAX_REPETITION);
+        if !self.eat('}') || matches!(max,Some(max) if max < min) || too_large {
+            return Err(invalid(self))
+        }
+        Ok((min,max))
+    }
+
+    /// Parse a decimal number, if there is one.
+    fn number(&mut self) -> Option<usize> {
+        let start = self.offset;
+        while matches!(self.peek(),Some(char) if char.is_ascii_digit()) {
+            self.next();
+        }
+        self.chars[start..self.offset].iter().collect::<String>().parse().ok()
+    }
+
+    /// Parse a single atom: a character, a class, or a group.
+    fn atom(&mut self) -> Result<Pattern,Error> {
+        let offset = self.offset;
+        match self.next() {
+            None       => Err(self.error(offset,ErrorKind::UnexpectedEnd)),
+            Some('(')  => {
+                let name    = if self.eat('?') { self.group_name()? } else { None };
+                self.depth += 1;
+                let pattern = self.alternation()?;
+                self.depth -= 1;
+                if !self.eat(')') {
+                    return Err(self.error(offset,ErrorKind::UnclosedGroup))
+                }
+                match name {
+                    Some(name) => Ok(Pattern::capture(name,&pattern)),
+                    None       => Ok(pattern),
+                }
+            },
+            Some('[')  => self.class(offset),
+            Some('.')  => Ok(Pattern::none_of("\n")),
+            Some('^')  => Ok(Pattern::line_start()),
+            Some('$')  => Ok(Pattern::line_end()),
+            Some('\\') => match self.escape()? {
+                Escaped::Char(char)    => Ok(Pattern::char(char)),
+                Escaped::Class(ranges) => Ok(pattern_from_ranges(ranges)),
+            },
+            Some(char@('*'|'+'|'?'|'{')) => {
+                Err(self.error(offset,ErrorKind::NothingToRepeat(char)))
+            },
+            Some(char) => Ok(Pattern::char(char)),
+        }
+    }
+
+    /// Parse the name of a group that is not a lookahead, after the `(?` has been consumed.
+    ///
+    /// The group is either a capture group in the form `(?<name>...)`, or a non-capturing group in
+    /// the form `(?:...)`, which has no name.
+    fn group_name(&mut self) -> Result<Option<String>,Error> {
+        let offset = self.offset;
+        match self.peek() {
+            Some('<')  => self.capture_name().map(Some),
+            Some(':')  => { self.next(); Ok(None) },
+            Some(char) => Err(self.error(offset,ErrorKind::UnexpectedChar(char))),
+            None       => Err(self.error(offset,ErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    /// Parse the name of a capture group in the form `<name>`, after the `(?` has been consumed.
+    ///
+    /// It is only called if the `(?` is followed by the `<`.
+    fn capture_name(&mut self) -> Result<String,Error> {
+        self.next();
+        let start = self.offset;
+        loop {
+            let offset = self.offset;
+            match self.next() {
+                Some(char) if char == '_' || char.is_ascii_alphabetic() => {},
+                Some(char) if char.is_ascii_digit() && offset > start   => {},
+                Some('>') if offset > start => {
+                    break Ok(self.chars[start..offset].iter().collect())
+                },
+                Some(char) => break Err(self.error(offset,ErrorKind::UnexpectedChar(char))),
+                None       => break Err(self.error(offset,ErrorKind::UnexpectedEnd)),
+            }
+        }
+    }
+
+    /// Parse an escape sequence, after the backslash has been consumed.
+    fn escape(&mut self) -> Result<Escaped,Error> {
+        let offset = self.offset;
+        let char   = self.next().ok_or_else(|| self.error(offset,ErrorKind::UnexpectedEnd))?;
+        let char   = match char {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'f' => '\x0C',
+            'v' => '\x0B',
+            '0' => '\0',
+            'x' => self.hex_escape(offset,false)?,
+            'u' => self.hex_escape(offset,true)?,
+            'd' => return Ok(Escaped::Class(digit_class())),
+            'w' => return Ok(Escaped::Class(word_class())),
+            's' => return Ok(Escaped::Class(space_class())),
+            'D' => return Ok(Escaped::Class(complement(digit_class()))),
+            'W' => return Ok(Escaped::Class(complement(word_class()))),
+            'S' => return Ok(Escaped::Class(complement(space_class()))),
+            'z' => return Ok(Escaped::Class(vec![Symbol::eof().index..=Symbol::eof().index])),
+            char if !char.is_alphanumeric() => char,
+            char => return Err(self.error(offset,ErrorKind::InvalidEscape(char))),
+        };
+        Ok(Escaped::Char(char))
+    }
+
+    /// Parse the digits of a `\xHH` or a `\u{H...}` escape.
+    fn hex_escape(&mut self, offset:usize, braced:bool) -> Result<char,Error> {
+        let escape  = if braced { 'u' } else { 'x' };
+        let invalid = |this:&Self| this.error(offset,ErrorKind::InvalidEscape(escape));
+        if braced && !self.eat('{') { return Err(invalid(self)) }
+        let start = self.offset;
+        while matches!(self.peek(),Some(char) if char.is_ascii_hexdigit()) {
+            self.next();
+        }
+        let digits = self.chars[start..self.offset].iter().collect::<String>();
+        let valid  = if braced { (1..=6).contains(&digits.len()) } else { digits.len() == 2 };
+        if !valid || (braced && !self.eat('}')) { return Err(invalid(self)) }
+        let code = u32::from_str_radix(&digits,16).map_err(|_| invalid(self))?;
+        std::char::from_u32(code).ok_or_else(|| invalid(self))
+    }
+
+    /// Parse a character class, after the opening bracket at `start` has been consumed.
+    fn class(&mut self, start:usize) -> Result<Pattern,Error> {
+        let negated    = self.eat('^');
+        let mut ranges = vec![];
+        let mut first  = true;
+        loop {
+            let offset = self.offset;
+            let item   = match self.next() {
+                None               => return Err(self.error(start,ErrorKind::UnclosedClass)),
+                Some(']') if !first => break,
+                Some('\\')         => self.escape()?,
+                Some(char)         => Escaped::Char(char),
+            };
+            first = false;
+            match item {
+                Escaped::Class(class) => ranges.extend(class),
+                Escaped::Char(from)   => {
+                    let is_range = self.peek() == Some('-')
+                        && !matches!(self.chars.get(self.offset+1),None|Some(']'));
+                    if is_range {
+                        self.next();
+                        let to = match self.next() {
+                            Some('\\') => self.escape()?,
+                            Some(char) => Escaped::Char(char),
+                            None       => return Err(self.error(start,ErrorKind::UnclosedClass)),
+                        };
+                        match to {
+                            Escaped::Char(to) if from <= to =>
+                                ranges.push(from as SymbolIndex..=to as SymbolIndex),
+                            Escaped::Char(to) =>
+                                return Err(self.error(offset,ErrorKind::InvalidRange(from,to))),
+                            Escaped::Class(_) =>
+                                return Err(self.error(offset,ErrorKind::InvalidRange(from,'\\'))),
+                        }
+                    } else {
+                        ranges.push(from as SymbolIndex..=from as SymbolIndex);
+                    }
+                }
+            }
+        }
+        let ranges = normalize(ranges);
+        let ranges = if negated { complement(ranges) } else { ranges };
+        Ok(pattern_from_ranges(ranges))
+    }
+}
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Parse the provided `regex` into a [`Pattern`].
+pub fn parse(regex:&str) -> Result<Pattern,Error> {
+    Parser::new(regex).run()
+}
+
+/// The codes matched by `\d`.
+fn digit_class() -> Vec<RangeInclusive<SymbolIndex>> {
+    vec![code('0')..=code('9')]
+}
+
+/// The codes matched by `\w`.
+fn word_class() -> Vec<RangeInclusive<SymbolIndex>> {
+    vec![code('0')..=code('9'),code('A')..=code('Z'),code('_')..=code('_'),code('a')..=code('z')]
+}
+
+/// The codes matched by `\s`.
+fn space_class() -> Vec<RangeInclusive<SymbolIndex>> {
+    vec![code('\t')..=code('\r'),code(' ')..=code(' ')]
+}
+
+/// The code of the provided `char`.
+fn code(char:char) -> SymbolIndex {
+    char as SymbolIndex
+}
+
+/// Sort the `ranges` and merge the overlapping or adjacent ones.
+pub(super) fn normalize
+(mut ranges:Vec<RangeInclusive<SymbolIndex>>) -> Vec<RangeInclusive<SymbolIndex>> {
+    ranges.sort_by_key(|range| *range.start());
+    let mut result:Vec<RangeInclusive<SymbolIndex>> = vec![];
+    for range in ranges {
+        match result.last_mut() {
+            Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                if range.end() > last.end() {
+                    *last = *last.start()..=*range.end();
+                }
+            },
+            _ => result.push(range),
+        }
+    }
+    result
+}
+
+/// Compute the complement of the disjoint and sorted `ranges`, excluding [`Symbol::null`] and
+/// [`Symbol::eof`].
+pub(super) fn complement
+(ranges:Vec<RangeInclusive<SymbolIndex>>) -> Vec<RangeInclusive<SymbolIndex>> {
+    let min        = Symbol::null().index + 1;
+    let max        = Symbol::eof().index - 1;
+    let mut result = vec![];
+    let mut start  = min;
+    for range in normalize(ranges) {
+        if *range.start() > start {
+            result.push(start..=range.start() - 1);
+        }
+        start = start.max(range.end().saturating_add(1));
+    }
+    if start <= max {
+        result.push(start..=max);
+    }
+    result
+}
+
+/// Create a pattern triggering on any of the provided `ranges` of codes.
+fn pattern_from_ranges(ranges:Vec<RangeInclusive<SymbolIndex>>) -> Pattern {
+    let symbol = |index:SymbolIndex| {
+        let char = u32::try_from(index).ok().and_then(std::char::from_u32);
+        char.map(Symbol::from).unwrap_or_else(|| Symbol::new(index))
+    };
+    let ranges = ranges.into_iter().map(|range| {
+        Pattern::symbols(symbol(*range.start())..=symbol(*range.end()))
+    });
+    ranges.reduce(|lhs,rhs| lhs | rhs).unwrap_or_else(Pattern::never)
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(column:usize, kind:ErrorKind) -> Result<Pattern,Error> {
+        Err(Error::new(column,kind))
+    }
+
+    #[test]
+    fn parse_literal() {
+        assert_eq!(parse("abc"),Ok(Pattern::char('a') >> Pattern::char('b') >> Pattern::char('c')));
+        assert_eq!(parse("a"),Ok(Pattern::char('a')));
+        assert_eq!(parse(""),Ok(Pattern::always()));
+    }
+
+    #[test]
+    fn parse_alternation() {
+        let expected = Pattern::char('a') | Pattern::char('b') >> Pattern::char('c');
+        assert_eq!(parse("a|bc"),Ok(expected));
+        assert_eq!(parse("a|"),Ok(Pattern::char('a') | Pattern::always()));
+    }
+
+    #[test]
+    fn parse_group() {
+        let expected = (Pattern::char('a') | Pattern::char('b')) >> Pattern::char('c');
+        assert_eq!(parse("(a|b)c"),Ok(expected.clone()));
+        assert_eq!(parse("(?:a|b)c"),Ok(expected));
+        assert_eq!(parse("()"),Ok(Pattern::always()));
+        assert_eq!(parse("(?:)"),Ok(Pattern::always()));
+        let expected = (Pattern::char('a') >> Pattern::char('b')).many1();
+        assert_eq!(parse("(?:ab)+"),Ok(expected));
+    }
+
+    #[test]
+    fn parse_repetitions() {
+        let a = Pattern::char('a');
+        assert_eq!(parse("a*"),Ok(a.many()));
+        assert_eq!(parse("a+"),Ok(a.many1()));
+        assert_eq!(parse("a?"),Ok(a.opt()));
+        let repeat = |min,max| Pattern::Repeat {body:Box::new(a.clone()),min,max};
+        assert_eq!(parse("a{3}"),Ok(repeat(3,Some(3))));
+        assert_eq!(parse("a{2,4}"),Ok(repeat(2,Some(4))));
+        assert_eq!(parse("a{0,1000}"),Ok(repeat(0,Some(1000))));
+        assert_eq!(parse("a{2,}"),Ok(Pattern::repeat_at_least(&a,2)));
+        assert_eq!(parse("(ab)+"),Ok((Pattern::char('a') >> Pattern::char('b')).many1()));
+    }
+
+    #[test]
+    fn parse_class() {
+        assert_eq!(parse("[a-z]"),Ok(Pattern::range('a'..='z')));
+        assert_eq!(parse("[a]"),Ok(Pattern::char('a')));
+        let expected = Pattern::range('A'..='Z') | Pattern::char('_') | Pattern::range('a'..='z');
+        assert_eq!(parse("[a-zA-Z_]"),Ok(expected));
+        assert_eq!(parse("[a-cb-f]"),Ok(Pattern::range('a'..='f')));
+        assert_eq!(parse("[]a]"),Ok(Pattern::char(']') | Pattern::char('a')));
+        assert_eq!(parse("[a-]"),Ok(Pattern::char('-') | Pattern::char('a')));
+        assert_eq!(parse("[\\d]"),Ok(Pattern::range('0'..='9')));
+    }
+
+    #[test]
+    fn parse_negated_class() {
+        let expected = Pattern::symbols(Symbol::from(1u64)..=Symbol::from('a'))
+            | Pattern::symbols(Symbol::from('c')..=Symbol::from(Symbol::eof().index - 1));
+        assert_eq!(parse("[^b]"),Ok(expected));
+    }
+
+    #[test]
+    fn parse_escapes() {
+        assert_eq!(parse("\\n"),Ok(Pattern::char('\n')));
+        assert_eq!(parse("\\*"),Ok(Pattern::char('*')));
+        assert_eq!(parse("\\x41"),Ok(Pattern::char('A')));
+        assert_eq!(parse("\\u{1F600}"),Ok(Pattern::char('😀')));
+        assert_eq!(parse("\\d"),Ok(Pattern::range('0'..='9')));
+        assert_eq!(parse("\\s"),Ok(Pattern::range('\t'..='\r') | Pattern::char(' ')));
+        assert_eq!(parse("\\z"),Ok(Pattern::eof()));
+    }
+
+    #[test]
+    fn parse_identifier() {
+        let start    = Pattern::range('A'..='Z') | Pattern::char('_') | Pattern::range('a'..='z');
+        let rest     = Pattern::range('0'..='9') | start.clone();
+        let expected = start >> rest.many();
+        assert_eq!(Pattern::parse("[a-zA-Z_][a-zA-Z0-9_]*"),Ok(expected));
+    }
+
+    #[test]
+    fn parse_lookahead() {
+        let ab = Pattern::char('a') >> Pattern::char('b');
+        let cd = Pattern::char('c') | Pattern::char('d');
+        assert_eq!(parse("ab(?=c|d)"),Ok(ab.followed_by(&cd)));
+        assert_eq!(parse("ab(?!c)"),Ok(ab.not_followed_by(&Pattern::char('c'))));
+        assert_eq!(parse("(?=a)"),Ok(Pattern::always().followed_by(&Pattern::char('a'))));
+    }
+
+    #[test]
+    fn parse_nested_lookahead() {
+        assert_eq!(parse("a(?=b)|c"),error(2,ErrorKind::NestedLookahead));
+        assert_eq!(parse("c|a(?=b)"),error(4,ErrorKind::NestedLookahead));
+        assert_eq!(parse("(a(?=b))"),error(3,ErrorKind::NestedLookahead));
+        assert_eq!(parse("(?<x>a(?=b))c"),error(7,ErrorKind::NestedLookahead));
+        assert_eq!(parse("a(?=b(?=c))"),error(6,ErrorKind::NestedLookahead));
+        let context = (Pattern::char('b') | Pattern::char('c')) >> Pattern::char('d');
+        assert_eq!(parse("a(?=(b|c)d)"),Ok(Pattern::char('a').followed_by(&context)));
+    }
+
+    #[test]
+    fn parse_captures() {
+        let key      = Pattern::capture("key",&Pattern::char('a').many1());
+        let value    = Pattern::capture("_value1",&Pattern::char('b'));
+        let expected = key >> Pattern::char('=') >> value.opt();
+        assert_eq!(parse("(?<key>a+)=(?<_value1>b)?"),Ok(expected));
+        let nested = Pattern::capture("x",&Pattern::capture("y",&Pattern::always()));
+        assert_eq!(parse("(?<x>(?<y>))"),Ok(nested));
+    }
+
+    #[test]
+    fn parse_line_anchors() {
+        let expected = Pattern::line_start() >> Pattern::char('a') >> Pattern::line_end();
+        assert_eq!(parse("^a$"),Ok(expected));
+        assert_eq!(parse("\\^\\$"),Ok(Pattern::char('^') >> Pattern::char('$')));
+        assert_eq!(parse("[$^]"),parse("[\\$\\^]"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse("ab)"),error(3,ErrorKind::UnexpectedChar(')')));
+        assert_eq!(parse("a(b"),error(2,ErrorKind::UnclosedGroup));
+        assert_eq!(parse("a[bc"),error(2,ErrorKind::UnclosedClass));
+        assert_eq!(parse("a|*"),error(3,ErrorKind::NothingToRepeat('*')));
+        assert_eq!(parse("[z-a]"),error(2,ErrorKind::InvalidRange('z','a')));
+        assert_eq!(parse("a{3,2}"),error(2,ErrorKind::InvalidRepetition));
+        assert_eq!(parse("a{x}"),error(2,ErrorKind::InvalidRepetition));
+        assert_eq!(parse("a{4000000000}"),error(2,ErrorKind::InvalidRepetition));
+        assert_eq!(parse("a{0,18446744073709551615}"),error(2,ErrorKind::InvalidRepetition));
+        assert_eq!(parse("a{1001,}"),error(2,ErrorKind::InvalidRepetition));
+        assert_eq!(parse("\\q"),error(2,ErrorKind::InvalidEscape('q')));
+        assert_eq!(parse("ab\\"),error(4,ErrorKind::UnexpectedEnd));
+        assert_eq!(parse("a(?>b)"),error(4,ErrorKind::UnexpectedChar('>')));
+        assert_eq!(parse("a(?"),error(4,ErrorKind::UnexpectedEnd));
+        assert_eq!(parse("a(?:b"),error(2,ErrorKind::UnclosedGroup));
+        assert_eq!(parse("a(?<b)"),error(6,ErrorKind::UnexpectedChar(')')));
+        assert_eq!(parse("a(?<1b>c)"),error(5,ErrorKind::UnexpectedChar('1')));
+        assert_eq!(parse("a(?<>c)"),error(5,ErrorKind::UnexpectedChar('>')));
+        assert_eq!(parse("a(?<b>c"),error(2,ErrorKind::UnclosedGroup));
+        assert_eq!(parse("a(?=b"),error(2,ErrorKind::UnclosedGroup));
+        assert_eq!(parse("a(?=b)c"),error(7,ErrorKind::UnexpectedChar('c')));
+    }
+
+    #[test]
+    fn error_display() {
+        let error = parse("a(b").unwrap_err();
+        assert_eq!(error.to_string(),"The group is never closed (at column 2).");
+    }
+}
@@ -0,0 +1,374 @@
+//! A printer of [`Pattern`]s in the regex syntax understood by the [`parser`](super::parser).
+//!
+//! The printed regexes use the minimal number of groups, and the shortest forms of the operators,
+//! so that `literal!("ab").many1()` is printed as `(ab)+`, and `char!('a') | char!('c')` as
+//! `[ac]`. Parsing a printed regex results in a pattern that triggers on the same input, with
+//! the following exceptions:
+//!
+//! - The boolean operators have no counterpart in the regex syntax. They are printed as `x&y` for
+//!   [`Pattern::And`], `x&~y` for [`Pattern::Minus`] and `~x` for [`Pattern::Complement`], which
+//!   the parser does not accept.
+//! - The lookaheads are printed as `x(?=y)` and `x(?!y)`, which the parser accepts only at the
+//!   end of a sequence, so that they are grouped whenever they are followed by other patterns.
+//! - The symbols that are neither characters, nor [`Symbol::eof`], cannot be written in the regex
+//!   syntax, and are omitted, unless they are a part of a negated class. They never occur in the
+//!   decoded input.
+
+use crate::prelude::*;
+
+use crate::pattern::Pattern;
+use crate::pattern::parser;
+use crate::symbol::Symbol;
+use crate::symbol::SymbolIndex;
+
+use std::ops::RangeInclusive;
+use std::slice;
+
+
+
+// ==================
+// === Precedence ===
+// ==================
+
+/// The precedence of the regex constructs, from the most loosely to the most tightly binding.
+#[derive(Clone,Copy,Debug,Eq,Ord,PartialEq,PartialOrd)]
+enum Precedence {
+    /// The alternatives, like `a|b`, and the boolean operators.
+    Alternation,
+    /// The lookaheads, like `a(?=b)`, which can only end a sequence.
+    Lookahead,
+    /// The sequences, like `ab`.
+    Sequence,
+    /// The repetitions, like `a*`, and the complement.
+    Repetition,
+    /// The characters, classes and groups.
+    Atom,
+}
+
+
+
+// ===============
+// === Printed ===
+// ===============
+
+/// A printed regex, together with the precedence of its outermost construct.
+#[derive(Clone,Debug)]
+struct Printed {
+    regex      : String,
+    precedence : Precedence,
+}
+
+impl Printed {
+    /// Constructor.
+    fn new(regex:impl Into<String>, precedence:Precedence) -> Self {
+        let regex = regex.into();
+        Self {regex,precedence}
+    }
+
+    /// Get the regex, wrapped in a group if it binds more loosely than the `context` requires.
+    fn in_context(self, context:Precedence) -> String {
+        if self.precedence < context { format!("({})",self.regex) } else { self.regex }
+    }
+}
+
+
+
+// ===============
+// === Printer ===
+// ===============
+
+/// Print the `pattern` in the regex syntax.
+///
+/// The pattern is [simplified](Pattern::simplify) first, so that it is printed in its minimal
+/// form.
+pub fn print(pattern:&Pattern) -> String {
+    print_pattern(&pattern.simplify()).regex
+}
+
+/// Print the `pattern`, without simplifying it.
+fn print_pattern(pattern:&Pattern) -> Printed {
+    use Precedence::*;
+    let operand = |pattern:&Pattern, context| print_pattern(pattern).in_context(context);
+    match pattern {
+        Pattern::Range(range)  => print_ranges(iter::once(range)),
+        Pattern::Class(ranges) => print_ranges(ranges),
+        Pattern::Seq(patterns) => print_seq(patterns),
+        Pattern::Or(patterns)  => print_or(patterns),
+        Pattern::Many(body)    => Printed::new(format!("{}*",operand(body,Atom)),Repetition),
+        Pattern::Repeat {body,min,max} => {
+            let bounds = match max {
+                Some(max) if max == min => format!("{{{}}}",min),
+                Some(max)               => format!("{{{},{}}}",min,max),
+                None                    => format!("{{{},}}",min),
+            };
+            Printed::new(format!("{}{}",operand(body,Atom),bounds),Repetition)
+        },
+        Pattern::And(patterns) => {
+            let operands = patterns.iter().map(|pattern| operand(pattern,Sequence)).collect_vec();
+            Printed::new(operands.join("&"),Alternation)
+        },
+        Pattern::Minus(lhs,rhs) => {
+            let regex = format!("{}&~{}",operand(lhs,Sequence),operand(rhs,Atom));
+            Printed::new(regex,Alternation)
+        },
+        Pattern::Complement(body) => Printed::new(format!("~{}",operand(body,Atom)),Repetition),
+        Pattern::Lookahead {body,context,negated} => {
+            let operator = if *negated { "?!" } else { "?=" };
+            let context  = print_pattern(context).regex;
+            let regex    = format!("{}({}{})",operand(body,Sequence),operator,context);
+            Printed::new(regex,Lookahead)
+        },
+        Pattern::Capture {name,body} => {
+            Printed::new(format!("(?<{}>{})",name,print_pattern(body).regex),Atom)
+        },
+        Pattern::LineStart        => Printed::new("^",Atom),
+        Pattern::LineEnd          => Printed::new("$",Atom),
+        Pattern::Always           => Printed::new("()",Atom),
+        Pattern::Never            => Printed::new(NEVER,Atom),
+    }
+}
+
+/// The regex that never triggers, which is the negation of a class containing all characters.
+const NEVER : &str = "[^\\s\\S]";
+
+/// Print a sequence of `patterns`, printing the repetitions `x >> x.many()` as `x+`.
+fn print_seq(patterns:&[Pattern]) -> Printed {
+    let mut items = vec![];
+    let mut ix    = 0;
+    while ix < patterns.len() {
+        let many1 = (1..patterns.len() - ix).find_map(|count| match &patterns[ix + count] {
+            Pattern::Many(body) => {
+                let repeated = &patterns[ix..ix + count];
+                let matches  = match body.as_ref() {
+                    Pattern::Seq(items) => items[..] == *repeated,
+                    body                => slice::from_ref(body) == repeated,
+                };
+                matches.as_some((count,body.as_ref()))
+            },
+            _ => None,
+        });
+        match many1 {
+            Some((count,body)) => {
+                let body = print_pattern(body).in_context(Precedence::Atom);
+                items.push(Printed::new(format!("{}+",body),Precedence::Repetition));
+                ix += count + 1;
+            },
+            None => {
+                items.push(print_pattern(&patterns[ix]));
+                ix += 1;
+            },
+        }
+    }
+    match items.len() {
+        0 => Printed::new("()",Precedence::Atom),
+        1 => items.remove(0),
+        _ => {
+            let items = items.into_iter().map(|item| item.in_context(Precedence::Repetition));
+            Printed::new(items.collect::<String>(),Precedence::Sequence)
+        }
+    }
+}
+
+/// Print the alternative of `patterns`, printing all the alternatives that are symbol ranges as
+/// a single class, and the alternatives with [`Pattern::Always`] as optional.
+fn print_or(patterns:&[Pattern]) -> Printed {
+    let is_ranges = |pattern:&&Pattern| matches!(pattern,Pattern::Range(_)|Pattern::Class(_));
+    let ranges    = patterns.iter().filter(is_ranges).flat_map(|pattern| match pattern {
+        Pattern::Range(range)  => vec![range],
+        Pattern::Class(ranges) => ranges.iter().collect(),
+        _                      => vec![],
+    }).collect_vec();
+    let mut ranges = Some(ranges);
+    let mut items  = vec![];
+    for pattern in patterns {
+        match pattern {
+            Pattern::Always                       => {},
+            Pattern::Range(_) | Pattern::Class(_) => if let Some(ranges) = ranges.take() {
+                items.push(print_ranges(ranges));
+            },
+            pattern => items.push(print_pattern(pattern)),
+        }
+    }
+    let printed = match items.len() {
+        0 => Printed::new(NEVER,Precedence::Atom),
+        1 => items.remove(0),
+        _ => {
+            let items = items.into_iter().map(|item| item.in_context(Precedence::Lookahead));
+            Printed::new(items.collect_vec().join("|"),Precedence::Alternation)
+        }
+    };
+    let optional = patterns.contains(&Pattern::Always);
+    match (optional,printed.regex.as_str()) {
+        (false,_)     => printed,
+        (true,NEVER)  => Printed::new("()",Precedence::Atom),
+        (true,_)      => {
+            let regex = format!("{}?",printed.in_context(Precedence::Atom));
+            Printed::new(regex,Precedence::Repetition)
+        }
+    }
+}
+
+/// Print the union of the symbol `ranges` as a character, a class or a negated class.
+fn print_ranges<'a>(ranges:impl IntoIterator<Item=&'a RangeInclusive<Symbol>>) -> Printed {
+    let eof        = Symbol::eof().index;
+    let ranges     = ranges.into_iter().map(|range| range.start().index..=range.end().index);
+    let mut ranges = parser::normalize(ranges.collect());
+    let has_eof    = ranges.last().map(|range| *range.end() == eof).unwrap_or(false);
+    if has_eof {
+        let last = ranges.pop().unwrap();
+        if last.start() < last.end() { ranges.push(*last.start()..=eof - 1) }
+    }
+    let first    = ranges.first().map(|range| *range.start());
+    let last     = ranges.last().map(|range| *range.end());
+    let negation = (first > Some(Symbol::null().index) && last == Some(eof - 1) && !has_eof)
+        .as_some_from(|| parser::complement(ranges.clone()));
+    let newline  = code('\n');
+    let class    = match negation {
+        Some(negation) if negation.is_empty() => "[\\s\\S]".into(),
+        Some(negation) if negation == vec![newline..=newline] => ".".into(),
+        Some(negation) => format!("[^{}]",print_class_items(&negation)),
+        None => {
+            let mut items = print_class_items(&ranges);
+            let single    = match ranges.as_slice() {
+                [range] if range.start() == range.end() => char_for(*range.start()),
+                _                                       => None,
+            };
+            match (single,has_eof) {
+                (Some(char),false) => escape(char,false),
+                _ if items.is_empty() && has_eof => "\\z".into(),
+                _ if items.is_empty() => NEVER.into(),
+                _ => {
+                    if has_eof { items.push_str("\\z") }
+                    format!("[{}]",items)
+                },
+            }
+        },
+    };
+    Printed::new(class,Precedence::Atom)
+}
+
+/// Print the `ranges` of codes as the items of a class, omitting the codes that are not
+/// characters.
+fn print_class_items(ranges:&[RangeInclusive<SymbolIndex>]) -> String {
+    let max_char   = SymbolIndex::from(u32::from(std::char::MAX));
+    let surrogates = code('\u{D7FF}') + 1..code('\u{E000}');
+    ranges.iter().filter_map(|range| {
+        let (start,end) = (*range.start(),*range.end());
+        let start       = if surrogates.contains(&start) { surrogates.end } else { start };
+        let end         = if surrogates.contains(&end) { surrogates.start - 1 } else { end };
+        let end         = end.min(max_char);
+        let start = char_for(start)?;
+        let end   = char_for(end).filter(|end| start <= *end)?;
+        let item  = match u32::from(end) - u32::from(start) {
+            0 => escape(start,true),
+            1 => format!("{}{}",escape(start,true),escape(end,true)),
+            _ => format!("{}-{}",escape(start,true),escape(end,true)),
+        };
+        Some(item)
+    }).collect()
+}
+
+/// Escape the `char`, either outside or inside of a class.
+fn escape(char:char, in_class:bool) -> String {
+    let special = if in_class { "\\[]^-" } else { "\\.|()[]{}*+?^$" };
+    match char {
+        '\n'   => "\\n".into(),
+        '\r'   => "\\r".into(),
+        '\t'   => "\\t".into(),
+        '\x0C' => "\\f".into(),
+        '\x0B' => "\\v".into(),
+        '\0'   => "\\0".into(),
+        char if special.contains(char) => format!("\\{}",char),
+        char if char.is_control() || (char.is_whitespace() && char != ' ') => {
+            format!("\\u{{{:X}}}",u32::from(char))
+        },
+        char => char.to_string(),
+    }
+}
+
+/// The code of the provided `char`.
+fn code(char:char) -> SymbolIndex {
+    SymbolIndex::from(u32::from(char))
+}
+
+/// The character with the provided `code`, if there is one.
+fn char_for(code:SymbolIndex) -> Option<char> {
+    u32::try_from(code).ok().and_then(std::char::from_u32)
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::char;
+    use crate::dfa::tests::dfa_for;
+    use crate::literal;
+
+    fn print(pattern:&Pattern) -> String {
+        pattern.to_string()
+    }
+
+    /// Check that the `regex` is printed back unchanged, and that it triggers on the same input
+    /// as the printed pattern.
+    fn assert_round_trip(regex:&str, expected:&str) {
+        let pattern = Pattern::parse(regex).unwrap();
+        let printed = print(&pattern);
+        assert_eq!(printed,expected);
+        let reparsed = Pattern::parse(&printed).unwrap();
+        let (lhs,lhs_end) = dfa_for(&[pattern]);
+        let (rhs,rhs_end) = dfa_for(&[reparsed]);
+        assert_eq!(lhs.is_equivalent(&lhs_end,&rhs,&rhs_end),Ok(()),"{}",regex);
+    }
+
+    #[test]
+    fn print_constructors() {
+        assert_eq!(print(&Pattern::range('a'..='z').many1()),"[a-z]+");
+        assert_eq!(print(&(literal!("foo") | literal!("bar")).opt()),"(foo|bar)?");
+        assert_eq!(print(&(literal!("foo") | literal!("far"))),"f(oo|ar)");
+        assert_eq!(print(&literal!("ab").many1()),"(ab)+");
+        assert_eq!(print(&(char!('a') | char!('c'))),"[ac]");
+        assert_eq!(print(&Pattern::none_of("\n")),".");
+        assert_eq!(print(&Pattern::none_of("\"\\")),"[^\"\\\\]");
+        assert_eq!(print(&literal!("a.b")),"a\\.b");
+        assert_eq!(print(&Pattern::repeat_between(&char!('a'),2,4)),"a{2,3}");
+        assert_eq!(print(&Pattern::repeat_at_least(&literal!("ab"),2)),"(ab){2,}");
+        assert_eq!(print(&Pattern::eof()),"\\z");
+        assert_eq!(print(&(Pattern::char(' ') | Pattern::eof())),"[ \\z]");
+        assert_eq!(print(&Pattern::always()),"()");
+        assert_eq!(print(&Pattern::never()),"[^\\s\\S]");
+        assert_eq!(print(&Pattern::capture("x",&char!('a')).many1()),"(?<x>a)+");
+    }
+
+    #[test]
+    fn print_boolean_operators() {
+        let word = Pattern::range('a'..='z').many1();
+        assert_eq!(print(&word.minus(&literal!("if"))),"[a-z]+&~(if)");
+        assert_eq!(print(&word.and(&literal!("ab").many())),"[a-z]+&(ab)*");
+        assert_eq!(print(&char!('a').complement()),"~a");
+    }
+
+    #[test]
+    fn print_round_trip() {
+        assert_round_trip("[a-zA-Z_][a-zA-Z0-9_]*","[A-Z_a-z][0-9A-Z_a-z]*");
+        assert_round_trip("(a|b)c","[ab]c");
+        assert_round_trip("a(bc)*d?","a(bc)*d?");
+        assert_round_trip("\"[^\"\\n]*\"","\"[^\\n\"]*\"");
+        assert_round_trip("[0-9]+(\\.[0-9]+)?","[0-9]+(\\.[0-9]+)?");
+        assert_round_trip("if|in|int","i(f|nt?)");
+        assert_round_trip("(ab|cd){2,3}x{2}","(ab|cd){2,3}x{2}");
+        assert_round_trip("[\\]\\-^\\\\]","[\\-\\\\-\\^]");
+        assert_round_trip("\\t\\u{3000}\\u{1F600}\\*.","\\t\\u{3000}😀\\*.");
+        assert_round_trip("\\s+|\\z","[\\t-\\r ]+|\\z");
+        assert_round_trip("\\S","[^\\t-\\r ]");
+        assert_round_trip("[a-z]+(?=\\()","[a-z]+(?=\\()");
+        assert_round_trip("if(?![a-z])|(a(?!b))*","if(?![a-z])|(a(?!b))*");
+        assert_round_trip("^#[a-z]+$|\\^","^#[a-z]+$|\\^");
+        assert_round_trip("(?<key>[a-z]+)=(?<value>\\d+)","(?<key>[a-z]+)=(?<value>[0-9]+)");
+    }
+}
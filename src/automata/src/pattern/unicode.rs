@@ -0,0 +1,321 @@
+//! Patterns for the Unicode general categories and character properties.
+//!
+//! The patterns are built from the compact range tables in the [`tables`] module as a single
+//! [`Pattern::Class`], so that every range of code points becomes a single transition from one
+//! state of the automaton, instead of a separate branch of an alternative.
+
+use crate::prelude::*;
+
+use crate::pattern::Pattern;
+use crate::symbol::Symbol;
+
+pub mod tables;
+
+
+
+// =======================
+// === GeneralCategory ===
+// =======================
+
+/// The Unicode general categories, as described in
+/// [UAX #44](https://www.unicode.org/reports/tr44/#General_Category_Values).
+#[derive(Clone,Copy,Debug,Eq,Hash,PartialEq)]
+#[allow(missing_docs)]
+pub enum GeneralCategory {
+    Lu, Ll, Lt, Lm, Lo,
+    Mn, Mc, Me,
+    Nd, Nl, No,
+    Pc, Pd, Ps, Pe, Pi, Pf, Po,
+    Sm, Sc, Sk, So,
+    Zs, Zl, Zp,
+    Cc, Cf, Cs, Co, Cn,
+}
+
+impl GeneralCategory {
+    /// All the general categories.
+    pub const ALL : [GeneralCategory;30] = {
+        use GeneralCategory::*;
+        [ Lu, Ll, Lt, Lm, Lo, Mn, Mc, Me, Nd, Nl, No, Pc, Pd, Ps, Pe, Pi, Pf, Po, Sm, Sc, Sk, So
+        , Zs, Zl, Zp, Cc, Cf, Cs, Co, Cn ]
+    };
+
+    /// The table of code point ranges belonging to the category.
+    pub fn table(self) -> &'static [(u32,u32)] {
+        use GeneralCategory::*;
+        match self {
+            Lu => tables::LU, Ll => tables::LL, Lt => tables::LT, Lm => tables::LM,
+            Lo => tables::LO, Mn => tables::MN, Mc => tables::MC, Me => tables::ME,
+            Nd => tables::ND, Nl => tables::NL, No => tables::NO, Pc => tables::PC,
+            Pd => tables::PD, Ps => tables::PS, Pe => tables::PE, Pi => tables::PI,
+            Pf => tables::PF, Po => tables::PO, Sm => tables::SM, Sc => tables::SC,
+            Sk => tables::SK, So => tables::SO, Zs => tables::ZS, Zl => tables::ZL,
+            Zp => tables::ZP, Cc => tables::CC, Cf => tables::CF, Cs => tables::CS,
+            Co => tables::CO, Cn => tables::CN,
+        }
+    }
+
+    /// Check whether `char` belongs to the category.
+    pub fn contains(self, char:char) -> bool {
+        table_contains(self.table(),char)
+    }
+}
+
+
+
+// ================
+// === Patterns ===
+// ================
+
+impl Pattern {
+    /// The pattern that triggers on any character in the general `category`.
+    pub fn unicode_category(category:GeneralCategory) -> Self {
+        Self::from_table(category.table())
+    }
+
+    /// The pattern that triggers on any character with the `XID_Start` property, as defined in
+    /// [UAX #31](https://www.unicode.org/reports/tr31/).
+    pub fn xid_start() -> Self {
+        Self::from_table(tables::XID_START)
+    }
+
+    /// The pattern that triggers on any character with the `XID_Continue` property, as defined in
+    /// [UAX #31](https://www.unicode.org/reports/tr31/).
+    pub fn xid_continue() -> Self {
+        Self::from_table(tables::XID_CONTINUE)
+    }
+
+    /// The pattern that triggers on any character with the Unicode `White_Space` property.
+    pub fn whitespace() -> Self {
+        Self::from_table(tables::WHITE_SPACE)
+    }
+
+    /// The pattern that triggers on any code point in the ranges of the provided `table`.
+    fn from_table(table:&[(u32,u32)]) -> Self {
+        let ranges = table.iter().map(|&(start,end)| Symbol::from(start)..=Symbol::from(end));
+        Pattern::Class(ranges.collect())
+    }
+}
+
+
+
+// ====================
+// === Case Folding ===
+// ====================
+
+impl Pattern {
+    /// Make the pattern ignore the case of characters.
+    ///
+    /// Every range of symbols in the pattern is extended with all the characters that are
+    /// equivalent to any of its characters under the Unicode simple case folding. For example,
+    /// `[a-z]` becomes `[A-Za-zſK]`, as `ſ` (U+017F) folds to `s` and `K` (U+212A) folds to `k`.
+    pub fn case_insensitive(&self) -> Self {
+        let map = |patterns:&[Pattern]| patterns.iter().map(Self::case_insensitive).collect_vec();
+        match self {
+            Pattern::Range(range)     => Self::class(case_fold_closure(&[range.clone()])),
+            Pattern::Class(ranges)    => Self::class(case_fold_closure(ranges)),
+            Pattern::Or(patterns)     => Pattern::Or(map(patterns)),
+            Pattern::Seq(patterns)    => Pattern::Seq(map(patterns)),
+            Pattern::And(patterns)    => Pattern::And(map(patterns)),
+            Pattern::Many(body)       => body.case_insensitive().many(),
+            Pattern::Repeat {body,min,max} => {
+                let body = Box::new(body.case_insensitive());
+                Pattern::Repeat {body,min:*min,max:*max}
+            },
+            Pattern::Minus(lhs,rhs)   => lhs.case_insensitive().minus(&rhs.case_insensitive()),
+            Pattern::Complement(body) => body.case_insensitive().complement(),
+            Pattern::Lookahead {body,context,negated} => {
+                let body    = Box::new(body.case_insensitive());
+                let context = Box::new(context.case_insensitive());
+                Pattern::Lookahead {body,context,negated:*negated}
+            },
+            Pattern::Capture {name,body} => Pattern::capture(name,&body.case_insensitive()),
+            Pattern::LineStart        => Pattern::LineStart,
+            Pattern::LineEnd          => Pattern::LineEnd,
+            Pattern::Always           => Pattern::Always,
+            Pattern::Never            => Pattern::Never,
+        }
+    }
+}
+
+/// Extend the `ranges` with all the code points that have the same simple case folding as any of
+/// the code points in the `ranges`.
+fn case_fold_closure(ranges:&[RangeInclusive<Symbol>]) -> Vec<RangeInclusive<Symbol>> {
+    let contains = |code:u32| ranges.iter().any(|range| {
+        range.start().index <= code as u64 && code as u64 <= range.end().index
+    });
+    let folded:HashSet<u32> = tables::SIMPLE_CASE_FOLDING.iter().filter_map(|&(code,folded)| {
+        (contains(code) || contains(folded)).as_some(folded)
+    }).collect();
+    let equivalent = tables::SIMPLE_CASE_FOLDING.iter().filter_map(|&(code,target)| {
+        folded.contains(&target).as_some(code)
+    });
+    let extra = equivalent.chain(folded.iter().copied()).filter_map(std::char::from_u32);
+    let extra = extra.map(|char| Symbol::from(char)..=Symbol::from(char));
+    ranges.iter().cloned().chain(extra).collect()
+}
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Check whether `char` is contained in any of the ranges of the `table`.
+fn table_contains(table:&[(u32,u32)], char:char) -> bool {
+    let code = char as u32;
+    table.binary_search_by(|&(start,end)| {
+        if      end   < code { std::cmp::Ordering::Less    }
+        else if start > code { std::cmp::Ordering::Greater }
+        else                 { std::cmp::Ordering::Equal   }
+    }).is_ok()
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dfa::Dfa;
+    use crate::nfa::Nfa;
+
+    fn all_tables() -> Vec<&'static [(u32,u32)]> {
+        let categories = GeneralCategory::ALL.iter().map(|category| category.table());
+        let properties = vec![tables::XID_START,tables::XID_CONTINUE,tables::WHITE_SPACE];
+        categories.chain(properties).collect()
+    }
+
+    fn assert_matches(pattern:&Pattern, matching:&[&str], not_matching:&[&str]) {
+        let mut nfa = Nfa::new();
+        let end     = nfa.new_pattern(nfa.start,pattern);
+        let dfa     = Dfa::from(&nfa);
+        for input in matching {
+            assert!(dfa.matches(input,&[end]),"`{}` should match.",input);
+        }
+        for input in not_matching {
+            assert!(!dfa.matches(input,&[end]),"`{}` should not match.",input);
+        }
+    }
+
+    #[test]
+    fn tables_are_normalized() {
+        for table in all_tables() {
+            for (start,end) in table {
+                assert!(start <= end);
+            }
+            for ((_,end),(next_start,_)) in table.iter().tuple_windows() {
+                assert!(end + 1 < *next_start);
+            }
+        }
+    }
+
+    #[test]
+    fn categories_are_disjoint_and_complete() {
+        let mut codes = GeneralCategory::ALL.iter().flat_map(|category| category.table())
+            .copied().collect_vec();
+        codes.sort_unstable();
+        let mut next = 0;
+        for (start,end) in codes {
+            assert_eq!(start,next);
+            next = end + 1;
+        }
+        assert_eq!(next,0x110000);
+    }
+
+    #[test]
+    fn category_contains() {
+        assert!(GeneralCategory::Lu.contains('A'));
+        assert!(GeneralCategory::Lu.contains('Ω'));
+        assert!(!GeneralCategory::Lu.contains('a'));
+        assert!(GeneralCategory::Nd.contains('٣'));
+        assert!(GeneralCategory::Sc.contains('€'));
+        assert!(GeneralCategory::Co.contains('\u{E000}'));
+    }
+
+    #[test]
+    fn category_boundaries() {
+        use GeneralCategory::*;
+        let expected = [
+            ('\u{40}',Po), ('\u{41}',Lu), ('\u{5A}',Lu), ('\u{5B}',Ps), ('\u{D6}',Lu),
+            ('\u{D7}',Sm), ('\u{D8}',Lu), ('\u{377}',Ll), ('\u{378}',Cn), ('\u{37A}',Lm),
+            ('\u{86A}',Lo), ('\u{86B}',Cn), ('\u{870}',Lo), ('\u{D7FB}',Lo), ('\u{D7FC}',Cn),
+            ('\u{D7FF}',Cn), ('\u{E000}',Co), ('\u{F8FF}',Co), ('\u{F900}',Lo),
+            ('\u{1FAF6}',So), ('\u{1FAF7}',Cn), ('\u{3134A}',Lo), ('\u{3134B}',Cn),
+            ('\u{10FFFD}',Co), ('\u{10FFFE}',Cn), ('\u{10FFFF}',Cn),
+        ];
+        for &(char,category) in &expected {
+            let found = GeneralCategory::ALL.iter().filter(|other| other.contains(char));
+            assert_eq!(found.collect_vec(),vec![&category],"The category of U+{:04X}.",char as u32);
+        }
+        assert_eq!(tables::CS,&[(0xD800,0xDFFF)]);
+    }
+
+    #[test]
+    fn xid_edge_cases() {
+        let expected = [
+            // Letters and the ID_Start characters of `Other_ID_Start`.
+            ('a',true,true), ('\u{2118}',true,true), ('\u{212E}',true,true),
+            ('\u{1885}',true,true),
+            // Continuing characters, including those of `Other_ID_Continue`.
+            ('_',false,true), ('1',false,true), ('\u{B7}',false,true), ('\u{FF10}',false,true),
+            ('\u{1369}',false,true), ('\u{19DA}',false,true), ('\u{FF9E}',false,true),
+            // Characters excluded from XID_Start or XID_Continue, as their NFKC forms are not.
+            ('\u{E33}',false,true), ('\u{37A}',false,false), ('\u{309B}',false,false),
+            ('\u{FC5E}',false,false),
+            // Characters that are neither, including the joiners, which are only XID_Continue since
+            // Unicode 15.1.
+            ('\u{2E2F}',false,false), ('\u{200C}',false,false), ('\u{200D}',false,false),
+            ('-',false,false), (' ',false,false),
+        ];
+        for &(char,is_start,is_continue) in &expected {
+            let code      = char as u32;
+            let starts    = table_contains(tables::XID_START,char);
+            let continues = table_contains(tables::XID_CONTINUE,char);
+            assert_eq!(starts,is_start,"XID_Start of U+{:04X}.",code);
+            assert_eq!(continues,is_continue,"XID_Continue of U+{:04X}.",code);
+        }
+    }
+
+    #[test]
+    fn pattern_unicode_category() {
+        let pattern = Pattern::unicode_category(GeneralCategory::Ll).many1();
+        assert_matches(&pattern,&["abc","żółw","ß"],&["","Abc","a1"]);
+    }
+
+    #[test]
+    fn pattern_identifier() {
+        let pattern = Pattern::xid_start() >> Pattern::xid_continue().many();
+        assert_matches(&pattern,&["a","ident_1","Δx","変数","e\u{301}"],&["","1a","_a","a-b"]);
+    }
+
+    #[test]
+    fn pattern_all_of_ci() {
+        let pattern = Pattern::all_of_ci("select");
+        let matching     = ["select","SELECT","SeLeCt","ſelect"];
+        let not_matching = ["selec","selects","sel_ct"];
+        assert_matches(&pattern,&matching,&not_matching);
+    }
+
+    #[test]
+    fn pattern_case_insensitive() {
+        let upper    = Symbol::from('A')..=Symbol::from('C');
+        let lower    = Symbol::from('a')..=Symbol::from('c');
+        let expected = Pattern::Class(vec![upper,lower]) | Pattern::char('7');
+        let pattern  = Pattern::range('a'..='c') | Pattern::char('7');
+        assert_eq!(pattern.case_insensitive(),expected);
+        let pattern = (Pattern::range('a'..='z').many1() >> Pattern::char('σ')).case_insensitive();
+        assert_matches(&pattern,&["abcσ","ABCΣ","Kς","ſς"],&["abc","1σ","abcΩ"]);
+        let pattern = Pattern::char('ß').case_insensitive();
+        assert_matches(&pattern,&["ß","ẞ"],&["ss","SS"]);
+    }
+
+    #[test]
+    fn pattern_whitespace() {
+        let pattern = Pattern::whitespace().many1();
+        assert_matches(&pattern,&[" ","\t\n","\u{A0}\u{3000}"],&["","a"," a","\u{200B}"]);
+    }
+}
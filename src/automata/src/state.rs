@@ -1,6 +1,8 @@
 //! This module exports State implementation for Nondeterministic Finite Automata.
 
 use crate::alphabet;
+use crate::capture::Tag;
+use crate::symbol::Names;
 use crate::symbol::Symbol;
 
 use crate::prelude::*;
@@ -22,8 +24,11 @@ use crate::nfa::Nfa; // FIXME
 #[derivative(Ord(bound=""))]
 #[derivative(PartialEq(bound=""))]
 #[derivative(PartialOrd(bound=""))]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
+#[cfg_attr(feature="serde",serde(transparent,bound=""))]
 #[allow(missing_docs)]
 pub struct State<T> {
+    #[cfg_attr(feature="serde",serde(skip))]
     tp : PhantomData<T>,
     id : usize
 }
@@ -79,6 +84,7 @@ impl<T> Debug for State<T> {
 
 /// A named state for a [`super::nfa::Nfa`].
 #[derive(Clone,Debug,Default,PartialEq,Eq)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
 pub struct Data {
     /// A set of transitions that can trigger without consuming a symbol (ε-transitions).
     pub epsilon_links: Vec<State<Nfa>>,
@@ -90,6 +96,9 @@ pub struct Data {
     /// representation. Non exported states are considered "transitive" states and are used as
     /// helpers to design the NFA network. All user defined states are marked to be exported.
     pub export : bool,
+    /// The boundaries of the capture groups that are recorded whenever the automaton enters this
+    /// state.
+    pub tags : Vec<Tag>,
 }
 
 impl Data {
@@ -109,7 +118,7 @@ impl Data {
         let mut targets = vec![];
         let mut index   = 0;
         let mut links   = self.links.clone();
-        links.sort_by_key(|link| link.symbols.start().clone());
+        links.sort_by_key(|link| *link.symbols.start());
         for symbol in &alphabet.divisions {
             while links.len() > index && links[index].symbols.end() < symbol {
                 index += 1;
@@ -132,6 +141,7 @@ impl Data {
 
 /// A transition between states in a finite automaton that must consume a symbol to trigger.
 #[derive(Clone,Debug,PartialEq,Eq)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
 pub struct Transition {
     /// The range of symbols on which this transition will trigger.
     pub symbols: RangeInclusive<Symbol>,
@@ -147,10 +157,33 @@ impl Transition {
 
     /// Display the symbols range of this tansition.
     pub fn display_symbols(&self) -> String {
-        if self.symbols.start() == self.symbols.end() {
-            format!("{}",self.symbols.start())
+        Self::display_range(&self.symbols)
+    }
+
+    /// Display the range of `symbols`. Printable characters are displayed as themselves, while all
+    /// the other symbols are displayed as their code points, or as `EOF` for [`Symbol::eof`].
+    pub fn display_range(symbols:&RangeInclusive<Symbol>) -> String {
+        Self::display_named_range(symbols,&default())
+    }
+
+    /// Display the range of `symbols` as in [`Transition::display_range`], with the symbols that
+    /// have a name in the `names` displayed as that name instead.
+    pub fn display_named_range(symbols:&RangeInclusive<Symbol>, names:&Names) -> String {
+        let printable = |char:char| !char.is_control() && !char.is_whitespace();
+        let display   = |symbol:&Symbol| {
+            let char = symbol.char();
+            match char {
+                _ if names.get(*symbol).is_some() => names.name(*symbol),
+                _ if *symbol == Symbol::eof()     => "EOF".into(),
+                Some(char) if printable(char)     => char.to_string(),
+                Some(_)                           => format!("U+{:04X}",symbol.index),
+                None                              => symbol.index.to_string(),
+            }
+        };
+        if symbols.start() == symbols.end() {
+            display(symbols.start())
         } else {
-            format!("{} .. {}",self.symbols.start(),self.symbols.end())
+            format!("{} .. {}",display(symbols.start()),display(symbols.end()))
         }
     }
 }
@@ -172,7 +205,8 @@ mod tests {
         fn from(links:Vec<Transition>) -> Self {
             let epsilon_links = vec![];
             let export        = false;
-            Data{epsilon_links,links,export}
+            let tags          = vec![];
+            Data{epsilon_links,links,export,tags}
         }
     }
 
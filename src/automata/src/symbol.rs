@@ -2,8 +2,6 @@
 
 use crate::prelude::*;
 
-use std::cmp::Ordering;
-
 
 
 // =============
@@ -20,15 +18,17 @@ pub type SymbolIndex = u64;
 // ==============
 
 /// An input symbol to a finite automaton.
-#[derive(Clone,Debug)]
+///
+/// A symbol is just an index, so that it can be freely copied. Names can be given to the symbols
+/// for the purposes of debugging and display with the [`Names`] table.
+#[derive(Clone,Copy,Debug,Default,Eq,Hash,Ord,PartialEq,PartialOrd)]
+#[cfg_attr(feature="serde",derive(serde::Serialize,serde::Deserialize))]
+#[cfg_attr(feature="serde",serde(transparent))]
 #[allow(missing_docs)]
 pub struct Symbol {
     pub index : SymbolIndex,
-    pub name  : String
 }
 
-
-
 impl Symbol {
     /// End of line symbol.
     pub fn eof() -> Self {
@@ -40,6 +40,22 @@ impl Symbol {
         Self::new(SymbolIndex::max_value() - 1)
     }
 
+    /// Line start symbol.
+    ///
+    /// It is never a part of the input. Instead, the automata take a transition on it wherever a
+    /// line starts, that is at the start of the input and after each `'\n'` character.
+    pub fn line_start() -> Self {
+        Self::new(SymbolIndex::max_value() - 3)
+    }
+
+    /// Line end symbol.
+    ///
+    /// It is never a part of the input. Instead, the automata take a transition on it wherever a
+    /// line ends, that is before each `'\n'` character and at the end of the input.
+    pub fn line_end() -> Self {
+        Self::new(SymbolIndex::max_value() - 2)
+    }
+
     /// Null symbol.
     pub fn null() -> Self {
         Self::new(0)
@@ -56,59 +72,41 @@ impl Symbol {
     }
 
     /// Constructor.
-    pub fn new(index:SymbolIndex) -> Self {
-        let name = "unnamed".into();
-        Self{index,name}
-    }
-
-    /// Named constructor.
-    pub fn new_named(index:SymbolIndex, name:impl Into<String>) -> Self {
-        let name = name.into();
-        Self{index,name}
+    pub const fn new(index:SymbolIndex) -> Self {
+        Self{index}
     }
 
     /// Next symbol, if any.
     pub fn next(&self) -> Option<Self> {
         self.index.checked_add(1).map(Self::new)
     }
-}
-
 
-// === Impls ===
-
-impl PartialEq for Symbol {
-    fn eq(&self, other: &Self) -> bool {
-        self.index.eq(&other.index)
+    /// Check whether this is one of the line anchors, [`Symbol::line_start`] or
+    /// [`Symbol::line_end`].
+    pub fn is_anchor(&self) -> bool {
+        *self == Self::line_start() || *self == Self::line_end()
     }
-}
-impl Eq for Symbol {}
 
-impl PartialOrd for Symbol {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-impl Ord for Symbol {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.index.cmp(&other.index)
+    /// The character represented by this symbol, if any.
+    pub fn char(&self) -> Option<char> {
+        u32::try_from(self.index).ok().and_then(std::char::from_u32)
     }
 }
 
-impl Hash for Symbol {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.index.hash(state);
-    }
-}
+
+// === Impls ===
 
 impl Display for Symbol {
+    /// Displays the symbols that represent characters as these characters, [`Symbol::eof`] as
+    /// `EOF`, the line anchors as `BOL` and `EOL`, and all the other symbols as their indices.
     fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f,"{}",self.name)
-    }
-}
-
-impl Default for Symbol {
-    fn default() -> Self {
-        Symbol::null()
+        match self.char() {
+            _ if *self == Symbol::eof()        => write!(f,"EOF"),
+            _ if *self == Symbol::line_start() => write!(f,"BOL"),
+            _ if *self == Symbol::line_end()   => write!(f,"EOL"),
+            Some(char)                         => write!(f,"{}",char),
+            None                               => write!(f,"{}",self.index),
+        }
     }
 }
 
@@ -126,13 +124,51 @@ impl From<u32> for Symbol {
 
 impl From<char> for Symbol {
     fn from(ch:char) -> Symbol {
-        Symbol::new_named(ch as u64,format!("{}",ch))
+        Symbol::new(ch as u64)
     }
 }
 
 impl From<&Symbol> for Symbol {
     fn from(symbol:&Symbol) -> Self {
-        symbol.clone()
+        *symbol
+    }
+}
+
+
+
+// =============
+// === Names ===
+// =============
+
+/// A side table of the names of symbols, used only for the purposes of debugging and display.
+///
+/// The names are used by [`Diagram::names`](crate::visualization::Diagram::names) and
+/// [`Counterexample::display_with`](crate::dfa::Counterexample::display_with). The symbols without
+/// a name are displayed as defined by the [`Display`] implementation of [`Symbol`].
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct Names {
+    names : HashMap<Symbol,String>,
+}
+
+impl Names {
+    /// Constructor.
+    pub fn new() -> Self {
+        default()
+    }
+
+    /// Give the `name` to the `symbol`, replacing its previous name, if any.
+    pub fn insert(&mut self, symbol:Symbol, name:impl Into<String>) {
+        self.names.insert(symbol,name.into());
+    }
+
+    /// Get the name given to the `symbol`, if any.
+    pub fn get(&self, symbol:Symbol) -> Option<&str> {
+        self.names.get(&symbol).map(|name| name.as_str())
+    }
+
+    /// Get the name of the `symbol`, falling back to its default display if it has no name.
+    pub fn name(&self, symbol:Symbol) -> String {
+        self.get(symbol).map(|name| name.to_string()).unwrap_or_else(|| symbol.to_string())
     }
 }
 
@@ -163,4 +199,23 @@ mod tests {
         let sym = Symbol::from('a');
         assert_eq!(sym.index,97);
     }
+
+    #[test]
+    fn display() {
+        assert_eq!(Symbol::from('a').to_string(),"a");
+        assert_eq!(Symbol::from(0xD800u32).to_string(),"55296");
+        assert_eq!(Symbol::eof().to_string(),"EOF");
+        assert_eq!(Symbol::line_start().to_string(),"BOL");
+        assert_eq!(Symbol::line_end().to_string(),"EOL");
+    }
+
+    #[test]
+    fn names() {
+        let mut names = Names::new();
+        names.insert(Symbol::null(),"NULL");
+        assert_eq!(names.get(Symbol::null()),Some("NULL"));
+        assert_eq!(names.get(Symbol::from('a')),None);
+        assert_eq!(names.name(Symbol::null()),"NULL");
+        assert_eq!(names.name(Symbol::from('a')),"a");
+    }
 }
@@ -0,0 +1,355 @@
+//! Compilation of automata over code points to equivalent automata over the bytes of the UTF-8
+//! encoding of the input.
+//!
+//! The lexers operate on [`Symbol`]s that are Unicode code points, so that the input has to be
+//! decoded before it can be dispatched on. An automaton compiled to UTF-8 bytes can instead run
+//! directly over the encoded input, with every code point range of the original automaton
+//! replaced with the sequences of byte ranges that encode it, in the style of the
+//! [utf8-ranges](https://docs.rs/utf8-ranges) crate. For example, the range `U+0080..=U+07FF`
+//! becomes the sequence `[C2-DF][80-BF]`.
+//!
+//! The symbols of the byte automata are the byte values `0..=255`, and the [`Symbol::eof`], which
+//! is kept as is. Surrogate code points and symbols above `U+10FFFF` cannot be encoded, so that
+//! the transitions on them are removed, except for the transitions on exactly one of the line
+//! anchors, which are kept as well. As the `'\n'` character is encoded as a single byte of the same
+//! value, the line anchors work over the bytes just as over the code points.
+
+use crate::prelude::*;
+
+use crate::dfa;
+use crate::dfa::Dfa;
+use crate::dfa::Match;
+use crate::nfa;
+use crate::nfa::Nfa;
+use crate::pattern::Pattern;
+use crate::symbol::Symbol;
+
+use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The largest Unicode code point.
+pub const MAX_CODE_POINT : u32 = 0x10FFFF;
+
+/// The range of surrogate code points, which have no UTF-8 encoding.
+pub const SURROGATES : RangeInclusive<u32> = 0xD800..=0xDFFF;
+
+
+
+// =================
+// === Sequences ===
+// =================
+
+/// A sequence of byte ranges, matching the UTF-8 encodings of a range of code points.
+pub type Sequence = Vec<RangeInclusive<u8>>;
+
+/// Split the `range` of code points into sequences of byte ranges that together match exactly
+/// the UTF-8 encodings of the code points in the `range`.
+///
+/// The sequences are disjoint and sorted by the code points they encode. The surrogates and the
+/// values above [`MAX_CODE_POINT`] are skipped.
+pub fn sequences(range:RangeInclusive<u32>) -> Vec<Sequence> {
+    let mut sequences = vec![];
+    let end           = (*range.end()).min(MAX_CODE_POINT);
+    split(*range.start(),end,&mut sequences);
+    sequences
+}
+
+/// Split the range `start..=end` until each of its parts is encoded by bytes that vary
+/// independently, which makes them expressible as a [`Sequence`].
+fn split(start:u32, end:u32, sequences:&mut Vec<Sequence>) {
+    if start > end { return }
+    if start <= *SURROGATES.end() && end >= *SURROGATES.start() {
+        split(start,SURROGATES.start().saturating_sub(1).min(end),sequences);
+        split(SURROGATES.end() + 1,end,sequences);
+        return
+    }
+    // Split at the boundaries of the encoding lengths, so that both ends are equally long.
+    for &max in &[0x7F,0x7FF,0xFFFF] {
+        if start <= max && max < end {
+            split(start,max,sequences);
+            split(max + 1,end,sequences);
+            return
+        }
+    }
+    // Split so that every continuation byte either spans its whole range, or is fixed.
+    for bits in &[6,12,18] {
+        let mask = (1_u32 << bits) - 1;
+        if start & !mask != end & !mask {
+            if start & mask != 0 {
+                split(start,start | mask,sequences);
+                split((start | mask) + 1,end,sequences);
+                return
+            }
+            if end & mask != mask {
+                split(start,(end & !mask) - 1,sequences);
+                split(end & !mask,end,sequences);
+                return
+            }
+        }
+    }
+    let start = encode(start);
+    let end   = encode(end);
+    sequences.push(start.into_iter().zip(end).map(|(start,end)| start..=end).collect());
+}
+
+/// Encode the `code_point` in UTF-8.
+fn encode(code_point:u32) -> Vec<u8> {
+    let char = std::char::from_u32(code_point).expect("Surrogates are never encoded.");
+    char.encode_utf8(&mut [0;4]).as_bytes().to_vec()
+}
+
+/// Convert the `range` of code point symbols to byte ranges, as described in the module docs.
+fn symbol_range_sequences(range:&RangeInclusive<Symbol>) -> Vec<Vec<RangeInclusive<Symbol>>> {
+    let byte      = |byte:u8| Symbol::from(u64::from(byte));
+    let start     = range.start().index;
+    let end       = range.end().index.min(u64::from(MAX_CODE_POINT));
+    let mut found = vec![];
+    if start <= end {
+        for sequence in sequences(start as u32..=end as u32) {
+            let sequence = sequence.iter().map(|range| byte(*range.start())..=byte(*range.end()));
+            found.push(sequence.collect());
+        }
+    }
+    if range.contains(&Symbol::eof()) {
+        found.push(vec![Symbol::eof()..=Symbol::eof()]);
+    }
+    if range.start() == range.end() && range.start().is_anchor() {
+        found.push(vec![range.clone()]);
+    }
+    found
+}
+
+
+
+// ================
+// === Patterns ===
+// ================
+
+impl Pattern {
+    /// Compile the pattern over code points to an equivalent pattern over the bytes of the UTF-8
+    /// encoding of the input.
+    ///
+    /// See the [module docs](crate::utf8) for details.
+    pub fn to_utf8(&self) -> Pattern {
+        use Pattern::*;
+        match self {
+            Range(range)          => Self::utf8_ranges(iter::once(range)),
+            Class(ranges)         => Self::utf8_ranges(ranges),
+            Or(patterns)          => Or(patterns.iter().map(Self::to_utf8).collect()),
+            Seq(patterns)         => Seq(patterns.iter().map(Self::to_utf8).collect()),
+            Many(body)            => body.to_utf8().many(),
+            Repeat {body,min,max} => Repeat {body:Box::new(body.to_utf8()),min:*min,max:*max},
+            And(patterns)         => And(patterns.iter().map(Self::to_utf8).collect()),
+            Minus(lhs,rhs)        => lhs.to_utf8().minus(&rhs.to_utf8()),
+            // The complement over bytes would contain invalid UTF-8, so that it is restricted to
+            // the encodings of the code points.
+            Complement(body) => {
+                let chars = Symbol::min()..=Symbol::from(MAX_CODE_POINT);
+                Self::utf8_ranges(iter::once(&chars)).many().minus(&body.to_utf8())
+            },
+            Lookahead {body,context,negated} => {
+                let body    = Box::new(body.to_utf8());
+                let context = Box::new(context.to_utf8());
+                Lookahead {body,context,negated:*negated}
+            },
+            Capture {name,body} => Self::capture(name,&body.to_utf8()),
+            LineStart => LineStart,
+            LineEnd   => LineEnd,
+            Always    => Always,
+            Never     => Never,
+        }
+    }
+
+    /// A pattern matching the UTF-8 encodings of the code points in the `ranges`.
+    fn utf8_ranges<'a>(ranges:impl IntoIterator<Item=&'a RangeInclusive<Symbol>>) -> Pattern {
+        let sequences = ranges.into_iter().flat_map(symbol_range_sequences);
+        let sequences = sequences.map(|sequence| {
+            let mut bytes = sequence.into_iter().map(Pattern::symbols).collect_vec();
+            if bytes.len() == 1 { bytes.remove(0) } else { Pattern::Seq(bytes) }
+        });
+        sequences.fold(Pattern::never(),|pattern,sequence| pattern | sequence)
+    }
+}
+
+
+
+// ===========
+// === Dfa ===
+// ===========
+
+impl Dfa {
+    /// Compile the automaton over code points to an equivalent automaton over the bytes of the
+    /// UTF-8 encoding of the input.
+    ///
+    /// The states of the resulting automaton are constructed from the same NFA states as the
+    /// states of this automaton, so that the same set of accepting NFA states can be used to run
+    /// both of them. The resulting automaton has to be run with [`Dfa::longest_match_bytes`]. See
+    /// the [module docs](crate::utf8) for details.
+    ///
+    /// The [`Dfa::tags`] are not carried over, so that the resulting automaton does not record the
+    /// spans of the capture groups.
+    pub fn to_utf8(&self) -> Dfa {
+        let mut nfa  = Nfa::new();
+        let rows     = self.links.rows;
+        let states   = iter::once(nfa.start);
+        let states   = states.chain((1..rows).map(|_| nfa.new_state_exported())).take(rows);
+        let states   = states.collect_vec();
+        let ranges   = self.division_ranges();
+        for (row,&source) in states.iter().enumerate() {
+            for (column,range) in ranges.iter().enumerate() {
+                let target = self.links[(row,column)];
+                if target.is_invalid() { continue }
+                let target = states[target.id()];
+                // Every NFA state can have a single target for a symbol, so that the sequences
+                // sharing their first bytes need separate entry states.
+                for sequence in symbol_range_sequences(range) {
+                    let (last,init) = sequence.split_last().unwrap();
+                    let entry       = nfa.new_state();
+                    nfa.connect(source,entry);
+                    let state = init.iter().fold(entry,|state,range| {
+                        let next = nfa.new_state();
+                        nfa.connect_via(state,next,range);
+                        next
+                    });
+                    nfa.connect_via(state,target,last);
+                }
+            }
+        }
+        // The states of the NFA that proxy the states of this automaton come first.
+        let bytes   = Dfa::from(&nfa);
+        let sources = bytes.sources.iter().map(|sources| {
+            let proxies = sources.iter().filter(|state| state.id() < rows);
+            let sources = proxies.flat_map(|state| self.sources[state.id()].iter().copied());
+            sources.collect::<BTreeSet<_>>().into_iter().collect_vec()
+        }).collect();
+        Dfa {sources,..bytes}
+    }
+
+    /// Find the longest prefix of the `input` bytes that is accepted by an automaton compiled
+    /// with [`Dfa::to_utf8`].
+    ///
+    /// See [`Dfa::longest_match`] for the meaning of `accepting`.
+    pub fn longest_match_bytes(&self, input:&[u8], accepting:&[nfa::State]) -> Option<Match> {
+        let symbols = input.iter().map(|byte| (Symbol::from(u64::from(*byte)),1));
+        dfa::longest_match_symbols(&mut &*self,symbols,accepting)
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dfa::tests::dfa_for;
+    use crate::dfa::tests::dfa_for_regexes;
+
+    const INPUTS : &[&str] =
+        &[ "", "a", "if", "iffy", "zß", "日本語", "Ω≈ç", "😀x", "\u{7F}\u{80}", "\u{FFFF}\u{10000}"
+         , "\u{D7FF}\u{E000}", "\u{10FFFF}", "x y", "1.5e3" ];
+
+    #[test]
+    fn sequences_of_all_code_points() {
+        let sequences = sequences(0..=u32::max_value());
+        let expected:Vec<Sequence> = vec!
+            [ vec![0x00..=0x7F]
+            , vec![0xC2..=0xDF,0x80..=0xBF]
+            , vec![0xE0..=0xE0,0xA0..=0xBF,0x80..=0xBF]
+            , vec![0xE1..=0xEC,0x80..=0xBF,0x80..=0xBF]
+            , vec![0xED..=0xED,0x80..=0x9F,0x80..=0xBF]
+            , vec![0xEE..=0xEF,0x80..=0xBF,0x80..=0xBF]
+            , vec![0xF0..=0xF0,0x90..=0xBF,0x80..=0xBF,0x80..=0xBF]
+            , vec![0xF1..=0xF3,0x80..=0xBF,0x80..=0xBF,0x80..=0xBF]
+            , vec![0xF4..=0xF4,0x80..=0x8F,0x80..=0xBF,0x80..=0xBF]
+            ];
+        assert_eq!(sequences,expected);
+    }
+
+    #[test]
+    fn sequences_match_exactly_the_range() {
+        let ranges = [0x41..=0x5A,0x370..=0x3FF,0x7F0..=0x820,0xD000..=0xE100,0xFFF0..=0x10010];
+        for range in ranges.iter() {
+            let sequences = sequences(range.clone());
+            let matches   = |bytes:&[u8]| sequences.iter().any(|sequence| {
+                sequence.len() == bytes.len()
+                    && sequence.iter().zip(bytes).all(|(range,byte)| range.contains(byte))
+            });
+            let start = range.start().saturating_sub(0x100);
+            for code_point in start..=range.end() + 0x100 {
+                if let Some(char) = std::char::from_u32(code_point) {
+                    let bytes = char.encode_utf8(&mut [0;4]).as_bytes().to_vec();
+                    assert_eq!(matches(&bytes),range.contains(&code_point),"{:X}",code_point);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pattern_to_utf8() {
+        let patterns = ["[a-zß]+","[^ ]+","if","\\w+","[😀-🙏]x?","[\\x7F-\\u{80}]+"];
+        for regex in patterns.iter() {
+            let pattern            = Pattern::parse(regex).unwrap();
+            let (bytes,ends_bytes) = dfa_for(&[pattern.to_utf8()]);
+            let (chars,ends)       = dfa_for(&[pattern]);
+            for input in INPUTS {
+                let expected = chars.longest_match(input,&ends).map(|found| found.length);
+                let result   = bytes.longest_match_bytes(input.as_bytes(),&ends_bytes);
+                assert_eq!(result.map(|found| found.length),expected,"{} on {:?}",regex,input);
+            }
+        }
+    }
+
+    #[test]
+    fn pattern_complement_to_utf8() {
+        let pattern            = Pattern::all_of("if").complement();
+        let (bytes,ends_bytes) = dfa_for(&[pattern.to_utf8()]);
+        let matches = |input:&str| {
+            let result = bytes.longest_match_bytes(input.as_bytes(),&ends_bytes);
+            result.map(|found| found.length == input.len()).unwrap_or(false)
+        };
+        assert!(matches("iffy"));
+        assert!(matches("日本"));
+        assert!(!matches("if"));
+        let invalid = bytes.longest_match_bytes(&[b'a',0xFF],&ends_bytes);
+        assert_eq!(invalid.map(|found| found.length),Some(1));
+    }
+
+    #[test]
+    fn dfa_to_utf8() {
+        let patterns   = ["[a-z_][a-z_0-9]*","if","[0-9]+","\\s+","[日本語]+","[^a-z0-9\\s]"];
+        let (dfa,ends) = dfa_for_regexes(&patterns);
+        let bytes      = dfa.to_utf8();
+        let symbols    = bytes.alphabet.division_map.keys();
+        assert!(symbols.clone().all(|symbol| symbol.index <= 0xFF || *symbol == Symbol::eof()));
+        for input in INPUTS {
+            let result   = bytes.longest_match_bytes(input.as_bytes(),&ends);
+            let expected = dfa.longest_match(input,&ends);
+            let summary  = |found:Match| (found.length,found.sources);
+            assert_eq!(result.map(summary),expected.map(summary),"{:?}",input);
+        }
+    }
+
+    #[test]
+    fn dfa_with_line_anchors_to_utf8() {
+        let patterns   = ["^[日本]+","[^a]+$","^$","([a-z]|\\n)*^ß"];
+        let (dfa,ends) = dfa_for_regexes(&patterns);
+        let bytes      = dfa.to_utf8();
+        let inputs     = INPUTS.iter().copied().chain(["日本\n本","ab\nß","a\n","\n"].iter().copied());
+        for input in inputs {
+            let result   = bytes.longest_match_bytes(input.as_bytes(),&ends);
+            let expected = dfa.longest_match(input,&ends);
+            let summary  = |found:Match| (found.length,found.sources);
+            assert_eq!(result.map(summary),expected.map(summary),"{:?}",input);
+        }
+    }
+}
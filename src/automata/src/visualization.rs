@@ -0,0 +1,390 @@
+//! Rendering of the finite automata as diagrams for the debugging purposes.
+//!
+//! Both the [`Nfa`] and the [`Dfa`] can be rendered as [GraphViz](https://graphviz.org) or
+//! [Mermaid](https://mermaid-js.github.io) diagrams. All the transitions between a pair of states
+//! are collapsed into a single edge labelled with their symbol ranges, and the accepting states
+//! are highlighted together with the names of the rules they accept.
+
+use crate::prelude::*;
+
+use crate::dfa::Dfa;
+use crate::nfa;
+use crate::nfa::Nfa;
+use crate::state::Transition;
+use crate::symbol::Names;
+use crate::symbol::Symbol;
+
+use std::collections::BTreeSet;
+use std::iter;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The maximum number of symbol ranges displayed on a single edge. The remaining ranges are
+/// elided, so that edges for large classes of characters stay readable.
+pub const MAX_EDGE_RANGES : usize = 16;
+
+/// The style of the nodes in the GraphViz diagrams.
+const GRAPHVIZ_NODE_STYLE : &str = "node [shape=circle style=filled fillcolor=\"#4385f5\" \
+    fontcolor=\"#FFFFFF\" color=white penwidth=5.0 margin=0.1 width=0.5 height=0.5 fixedsize=true]";
+
+
+
+// ==============
+// === Format ===
+// ==============
+
+/// The format of a rendered diagram.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum Format {
+    /// The [GraphViz](https://graphviz.org) DOT language.
+    GraphViz,
+    /// The [Mermaid](https://mermaid-js.github.io) flowchart syntax.
+    Mermaid,
+}
+
+
+// === Trait Impls ===
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::GraphViz
+    }
+}
+
+
+
+// ===============
+// === Diagram ===
+// ===============
+
+/// The configuration for rendering automata as diagrams.
+///
+/// ```
+/// use enso_automata::nfa::Nfa;
+/// use enso_automata::pattern::Pattern;
+/// use enso_automata::visualization::Diagram;
+///
+/// let mut nfa = Nfa::new();
+/// let end     = nfa.new_pattern(nfa.start,Pattern::range('a'..='z').many1());
+/// let diagram = Diagram::mermaid().rule(end,"identifier");
+/// println!("{}",diagram.render_nfa(&nfa));
+/// ```
+#[derive(Clone,Debug,Default)]
+pub struct Diagram {
+    /// The format of the rendered diagrams.
+    pub format : Format,
+    /// The accepting NFA states, together with the names of the rules that they accept.
+    pub accepting : BTreeMap<nfa::State,Option<String>>,
+    /// The names that the symbols on the edges are displayed with.
+    pub names : Names,
+}
+
+impl Diagram {
+    /// Constructor.
+    pub fn new(format:Format) -> Self {
+        let accepting = default();
+        let names     = default();
+        Self {format,accepting,names}
+    }
+
+    /// Constructor of a diagram in the GraphViz format.
+    pub fn graphviz() -> Self {
+        Self::new(Format::GraphViz)
+    }
+
+    /// Constructor of a diagram in the Mermaid format.
+    pub fn mermaid() -> Self {
+        Self::new(Format::Mermaid)
+    }
+
+    /// Mark the NFA `state` as accepting.
+    ///
+    /// In a DFA, the states constructed from the `state` are marked as accepting.
+    pub fn accepting(mut self, state:nfa::State) -> Self {
+        self.accepting.entry(state).or_insert(None);
+        self
+    }
+
+    /// Mark the NFA `state` as accepting the rule with the given `name`.
+    pub fn rule(mut self, state:nfa::State, name:impl Into<String>) -> Self {
+        self.accepting.insert(state,Some(name.into()));
+        self
+    }
+
+    /// Display the symbols on the edges with the given `names`, where they have one.
+    pub fn names(mut self, names:&Names) -> Self {
+        self.names = names.clone();
+        self
+    }
+
+    /// Render the `nfa`.
+    ///
+    /// The epsilon links are drawn as dashed edges, and the states that are not exported are
+    /// dimmed.
+    pub fn render_nfa(&self, nfa:&Nfa) -> String {
+        let mut graph = Graph::default();
+        for (ix,state) in nfa.states().iter().enumerate() {
+            let source = nfa::State::new(ix);
+            let rule   = self.accepting.get(&source);
+            graph.nodes.push(Node::new(ix,rule,!state.export));
+            let mut links:BTreeMap<usize,Vec<RangeInclusive<Symbol>>> = default();
+            for link in &state.links {
+                links.entry(link.target.id()).or_default().push(link.symbols.clone());
+            }
+            for (target,mut ranges) in links {
+                ranges.sort_by_key(|range| *range.start());
+                graph.edges.push(Edge::new(ix,target,&ranges,&self.names));
+            }
+            let targets = state.epsilon_links.iter().map(|target| target.id());
+            for target in targets.collect::<BTreeSet<_>>() {
+                graph.edges.push(Edge::epsilon(ix,target));
+            }
+        }
+        graph.render(self.format)
+    }
+
+    /// Render the `dfa`.
+    ///
+    /// A DFA state is accepting if any of the states in its [`Dfa::sources`] is accepting. It is
+    /// labelled with the name of the first of the accepted rules.
+    pub fn render_dfa(&self, dfa:&Dfa) -> String {
+        let divisions = dfa.alphabet.keys().copied().collect_vec();
+        let ends      = divisions.iter().skip(1).map(|next| Symbol::new(next.index - 1));
+        let ends      = ends.chain(iter::once(Symbol::max())).collect_vec();
+        let mut graph = Graph::default();
+        for row in 0..dfa.links.rows {
+            let sources   = dfa.sources.get(row).map(|sources| sources.as_slice()).unwrap_or(&[]);
+            let accepting = sources.iter().filter_map(|source| self.accepting.get(source));
+            let accepting = accepting.collect_vec();
+            let named     = accepting.iter().find(|name| name.is_some());
+            let rule      = named.or_else(|| accepting.first()).copied();
+            graph.nodes.push(Node::new(row,rule,false));
+            let mut links:BTreeMap<usize,Vec<RangeInclusive<Symbol>>> = default();
+            for column in 0..dfa.links.columns {
+                let target = dfa.links[(row,column)];
+                if target.is_invalid() { continue }
+                let start  = divisions[column];
+                let end    = ends[column];
+                let ranges = links.entry(target.id()).or_default();
+                match ranges.last_mut() {
+                    Some(last) if last.end().index + 1 == start.index => {
+                        *last = *last.start()..=end
+                    },
+                    _ => ranges.push(start..=end),
+                }
+            }
+            for (target,ranges) in links {
+                graph.edges.push(Edge::new(row,target,&ranges,&self.names));
+            }
+        }
+        graph.render(self.format)
+    }
+}
+
+
+
+// =============
+// === Graph ===
+// =============
+
+/// A format-independent representation of a rendered automaton.
+#[derive(Clone,Debug,Default)]
+struct Graph {
+    nodes : Vec<Node>,
+    edges : Vec<Edge>,
+}
+
+/// A state of a rendered automaton.
+#[derive(Clone,Debug)]
+struct Node {
+    id        : usize,
+    /// Set for accepting states, possibly with the name of the accepted rule.
+    rule      : Option<Option<String>>,
+    transient : bool,
+}
+
+/// A set of transitions between two states of a rendered automaton.
+#[derive(Clone,Debug)]
+struct Edge {
+    source : usize,
+    target : usize,
+    /// The displayed symbols, or `None` for an epsilon link.
+    label  : Option<String>,
+}
+
+impl Node {
+    fn new(id:usize, rule:Option<&Option<String>>, transient:bool) -> Self {
+        let rule = rule.cloned();
+        Self {id,rule,transient}
+    }
+}
+
+impl Edge {
+    fn new(source:usize, target:usize, ranges:&[RangeInclusive<Symbol>], names:&Names) -> Self {
+        let labels     = ranges.iter().take(MAX_EDGE_RANGES);
+        let mut labels = labels.map(|range| Transition::display_named_range(range,names));
+        let mut label  = labels.join(", ");
+        if ranges.len() > MAX_EDGE_RANGES {
+            label += &format!(", … ({} more)",ranges.len() - MAX_EDGE_RANGES);
+        }
+        let label = Some(label);
+        Self {source,target,label}
+    }
+
+    fn epsilon(source:usize, target:usize) -> Self {
+        let label = None;
+        Self {source,target,label}
+    }
+}
+
+impl Graph {
+    fn render(&self, format:Format) -> String {
+        match format {
+            Format::GraphViz => self.render_graphviz(),
+            Format::Mermaid  => self.render_mermaid(),
+        }
+    }
+
+    fn render_graphviz(&self) -> String {
+        let escape  = |text:&str| text.replace('\\',"\\\\").replace('"',"\\\"");
+        let mut out = String::new();
+        if !self.nodes.is_empty() {
+            out += "start[shape=point width=0.1 height=0.1 fillcolor=\"#000000\"]\n";
+            out += "start -> node_0\n";
+        }
+        for node in &self.nodes {
+            let mut opts = format!("label=\"{}\"",node.id);
+            if let Some(rule) = &node.rule {
+                opts += " shape=doublecircle";
+                if let Some(name) = rule { opts += &format!(" xlabel=\"{}\"",escape(name)) }
+            } else if node.transient {
+                opts += " fillcolor=\"#EEEEEE\" fontcolor=\"#888888\"";
+            }
+            out += &format!("node_{}[{}]\n",node.id,opts);
+        }
+        for edge in &self.edges {
+            let opts = match &edge.label {
+                Some(label) => format!("label=\"{}\"",escape(label)),
+                None        => "label=\"ε\" style=dashed".into(),
+            };
+            out += &format!("node_{} -> node_{}[{}]\n",edge.source,edge.target,opts);
+        }
+        format!("digraph G {{\n{}\n{}}}\n",GRAPHVIZ_NODE_STYLE,out)
+    }
+
+    fn render_mermaid(&self) -> String {
+        let escape  = |text:&str| {
+            text.replace('#',"#35;").replace('"',"#quot;").replace('<',"#lt;").replace('>',"#gt;")
+        };
+        let mut out = String::from("flowchart LR\n");
+        out += "    classDef accepting fill:#4385f5,color:#FFFFFF\n";
+        out += "    classDef transient fill:#EEEEEE,color:#888888\n";
+        for node in &self.nodes {
+            match &node.rule {
+                Some(Some(name)) => {
+                    let label = format!("{}<br>{}",node.id,escape(name));
+                    out += &format!("    s{}(((\"{}\"))):::accepting\n",node.id,label)
+                },
+                Some(None) => out += &format!("    s{}(((\"{}\"))):::accepting\n",node.id,node.id),
+                None if node.transient => {
+                    out += &format!("    s{}((\"{}\")):::transient\n",node.id,node.id)
+                },
+                None => out += &format!("    s{}((\"{}\"))\n",node.id,node.id),
+            }
+        }
+        for edge in &self.edges {
+            let (source,target) = (edge.source,edge.target);
+            out += &match &edge.label {
+                Some(label) => format!("    s{} -->|\"{}\"| s{}\n",source,escape(label),target),
+                None        => format!("    s{} -.->|\"ε\"| s{}\n",source,target),
+            };
+        }
+        out
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::nfa::tests::nfa_for;
+    use crate::pattern::Pattern;
+
+    fn minimized(nfa:&Nfa, end:nfa::State) -> Dfa {
+        let dfa = Dfa::from(nfa);
+        dfa.minimize_by(|state| dfa.sources[state.id()].contains(&end))
+    }
+
+    #[test]
+    fn dfa_graphviz_collapses_edges() {
+        let pattern    = Pattern::range('a'..='c') | Pattern::range('x'..='z') | Pattern::char('"');
+        let (nfa,ends) = nfa_for(&[pattern]);
+        let end        = ends[0];
+        let dfa        = minimized(&nfa,end);
+        let code       = Diagram::graphviz().rule(end,"letter").render_dfa(&dfa);
+        assert!(code.contains("node_0 -> node_1[label=\"\\\", a .. c, x .. z\"]"));
+        assert!(code.contains("node_1[label=\"1\" shape=doublecircle xlabel=\"letter\"]"));
+        assert!(code.contains("start -> node_0"));
+        assert_eq!(code.matches(" -> node_").count(),2);
+    }
+
+    #[test]
+    fn nfa_graphviz_marks_epsilon_links() {
+        let (nfa,ends) = nfa_for(&[Pattern::char('a').many()]);
+        let end        = ends[0];
+        let code       = Diagram::graphviz().accepting(end).render_nfa(&nfa);
+        assert!(code.contains("node_0 -> node_1[label=\"ε\" style=dashed]"));
+        let accepting  = format!("node_{}[label=\"{}\" shape=doublecircle]",end.id(),end.id());
+        assert!(code.contains(&accepting));
+        assert!(code.contains("[label=\"a\"]"));
+    }
+
+    #[test]
+    fn dfa_mermaid() {
+        let (nfa,ends) = nfa_for(&[Pattern::char('<') >> Pattern::eof()]);
+        let end        = ends[0];
+        let dfa        = Dfa::from(&nfa);
+        let code       = Diagram::mermaid().rule(end,"tag").render_dfa(&dfa);
+        let expected   = "flowchart LR\n    \
+            classDef accepting fill:#4385f5,color:#FFFFFF\n    \
+            classDef transient fill:#EEEEEE,color:#888888\n    \
+            s0((\"0\"))\n    \
+            s1((\"1\"))\n    \
+            s2(((\"2<br>tag\"))):::accepting\n    \
+            s0 -->|\"#lt;\"| s1\n    \
+            s1 -->|\"EOF\"| s2\n";
+        assert_eq!(code,expected);
+    }
+
+    #[test]
+    fn dfa_graphviz_with_names() {
+        let (nfa,_)   = nfa_for(&[Pattern::char('a') >> Pattern::eof()]);
+        let mut names = Names::new();
+        names.insert(Symbol::from('a'),"letter");
+        names.insert(Symbol::eof(),"end of input");
+        let code = Diagram::graphviz().names(&names).render_dfa(&Dfa::from(&nfa));
+        assert!(code.contains("node_0 -> node_1[label=\"letter\"]"),"{}",code);
+        assert!(code.contains("node_1 -> node_2[label=\"end of input\"]"),"{}",code);
+        let code = Diagram::graphviz().render_dfa(&Dfa::from(&nfa));
+        assert!(code.contains("node_1 -> node_2[label=\"EOF\"]"),"{}",code);
+    }
+
+    #[test]
+    fn edge_labels_are_elided() {
+        let pattern    = Pattern::any_of("acegikmoqsuwy02468");
+        let (nfa,ends) = nfa_for(&[pattern]);
+        let end        = ends[0];
+        let code       = Diagram::graphviz().render_dfa(&minimized(&nfa,end));
+        assert!(code.contains(", … (2 more)\"]"),"{}",code);
+    }
+}
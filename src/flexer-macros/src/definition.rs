@@ -0,0 +1,219 @@
+//! The definition of a lexer accepted by the [`lexer!`](crate::lexer) macro, and its expansion.
+
+use enso_flexer::automata::pattern::Pattern;
+use enso_flexer::generate;
+use enso_flexer::group::Identifier;
+use enso_flexer::group::Registry;
+use proc_macro2::Span;
+use proc_macro2::TokenStream;
+use quote::format_ident;
+use quote::quote;
+use quote::ToTokens;
+use std::collections::HashMap;
+use std::fmt::Display;
+use syn::Attribute;
+use syn::Expr;
+use syn::Ident;
+use syn::LitStr;
+use syn::Token;
+use syn::Type;
+use syn::braced;
+use syn::parse::Parse;
+use syn::parse::ParseStream;
+use syn::punctuated::Punctuated;
+
+
+
+// ================
+// === Keywords ===
+// ================
+
+mod keyword {
+    syn::custom_keyword!(lexer);
+    syn::custom_keyword!(group);
+}
+
+
+
+// ==================
+// === Definition ===
+// ==================
+
+/// The definition of a lexer.
+#[derive(Clone,Debug)]
+pub struct Definition {
+    /// The type that the specialized code is generated for.
+    name : Ident,
+    /// The type of the output of the lexer.
+    output : Type,
+    /// The groups of the lexer, in the order of their definitions.
+    groups : Vec<Group>,
+}
+
+impl Definition {
+    /// Generate the specialized code of the lexer, together with the function defining its groups.
+    pub fn expand(&self) -> syn::Result<TokenStream> {
+        let registry    = self.registry()?;
+        let output      = self.output.to_token_stream().to_string();
+        let specialized = generate::specialize_groups(&registry,self.name.to_string(),output);
+        let specialized = specialized.map_err(|err| syn::Error::new(Span::call_site(),err))?;
+        let definitions = self.define_groups();
+        Ok(quote! {
+            #specialized
+            #definitions
+        })
+    }
+
+    /// Build the registry of the groups and their rules.
+    fn registry(&self) -> syn::Result<Registry> {
+        let mut registry = Registry::default();
+        let mut groups   = HashMap::<String,Identifier>::new();
+        for group in &self.groups {
+            let name = group.name.to_string();
+            if groups.contains_key(&name) {
+                let message = format!("The group `{}` is already defined.",name);
+                return Err(syn::Error::new(group.name.span(),message))
+            }
+            let parent = match &group.parent {
+                Some(parent) => match groups.get(&parent.to_string()) {
+                    Some(id) => Some(*id),
+                    None     => {
+                        let message = format!("The group `{}` is not defined before.",parent);
+                        return Err(syn::Error::new(parent.span(),message))
+                    }
+                },
+                None => None,
+            };
+            let id = registry.define_group(name.clone(),parent);
+            registry.group_mut(id).longest_match = group.longest_match;
+            for rule in &group.rules {
+                let invalid = |err:&dyn Display| {
+                    syn::Error::new(rule.pattern.span(),format!("Invalid pattern: {}",err))
+                };
+                let pattern = Pattern::parse(&rule.pattern.value()).map_err(|err| invalid(&err))?;
+                pattern.check().map_err(|err| invalid(&err))?;
+                registry.create_rule(id,&pattern,rule.callback.to_token_stream().to_string());
+            }
+            groups.insert(name,id);
+        }
+        Ok(registry)
+    }
+
+    /// Generate the `define_groups` function of the lexer, which defines the groups in the same
+    /// order as in the registry used to generate the specialized code.
+    fn define_groups(&self) -> TokenStream {
+        let name        = &self.name;
+        let count       = self.groups.len();
+        let ids         = (0..count).map(|ix| format_ident!("group_{}",ix)).collect::<Vec<_>>();
+        let position    = |name:&Ident| self.groups.iter().position(|group| group.name == *name);
+        let definitions = self.groups.iter().zip(&ids).map(|(group,id)| {
+            let group_name    = group.name.to_string();
+            let longest_match = group.longest_match;
+            let parent        = group.parent.as_ref().and_then(position).map(|ix| &ids[ix]);
+            let parent        = match parent {
+                Some(parent) => quote! {Some(#parent)},
+                None         => quote! {None},
+            };
+            quote! {
+                let #id = registry.define_group(#group_name,#parent);
+                registry.group_mut(#id).longest_match = #longest_match;
+            }
+        });
+        quote! {
+            impl #name {
+                /// Define the groups of the lexer in the empty `registry`, returning their
+                /// identifiers in the order of their definitions.
+                pub fn define_groups
+                (registry:&mut enso_flexer::group::Registry)
+                -> [enso_flexer::group::Identifier;#count] {
+                    #(#definitions)*
+                    [#(#ids),*]
+                }
+            }
+        }
+    }
+}
+
+impl Parse for Definition {
+    fn parse(input:ParseStream) -> syn::Result<Self> {
+        input.parse::<keyword::lexer>()?;
+        let name = input.parse()?;
+        input.parse::<Token![->]>()?;
+        let output = input.parse()?;
+        input.parse::<Token![;]>()?;
+        let mut groups = vec![];
+        while !input.is_empty() {
+            groups.push(input.parse()?);
+        }
+        Ok(Self {name,output,groups})
+    }
+}
+
+
+
+// =============
+// === Group ===
+// =============
+
+/// The definition of a group of rules.
+#[derive(Clone,Debug)]
+struct Group {
+    /// The name of the group.
+    name : Ident,
+    /// The group that the rules are inherited from.
+    parent : Option<Ident>,
+    /// Whether the group has longest-match semantics.
+    longest_match : bool,
+    /// The rules of the group, in the order of their definitions.
+    rules : Vec<Rule>,
+}
+
+impl Parse for Group {
+    fn parse(input:ParseStream) -> syn::Result<Self> {
+        let mut longest_match = false;
+        for attribute in input.call(Attribute::parse_outer)? {
+            if !attribute.path.is_ident("longest_match") || !attribute.tokens.is_empty() {
+                let message = "Expected the `#[longest_match]` attribute.";
+                return Err(syn::Error::new_spanned(attribute,message))
+            }
+            longest_match = true;
+        }
+        input.parse::<keyword::group>()?;
+        let name   = input.parse()?;
+        let parent = if input.peek(Token![:]) {
+            input.parse::<Token![:]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let body;
+        braced!(body in input);
+        let rules = Punctuated::<Rule,Token![,]>::parse_terminated(&body)?;
+        let rules = rules.into_iter().collect();
+        Ok(Self {name,parent,longest_match,rules})
+    }
+}
+
+
+
+// ============
+// === Rule ===
+// ============
+
+/// The definition of a rule.
+#[derive(Clone,Debug)]
+struct Rule {
+    /// The regex of the pattern of the rule.
+    pattern : LitStr,
+    /// The callback of the rule.
+    callback : Expr,
+}
+
+impl Parse for Rule {
+    fn parse(input:ParseStream) -> syn::Result<Self> {
+        let pattern = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let callback = input.parse()?;
+        Ok(Self {pattern,callback})
+    }
+}
@@ -0,0 +1,67 @@
+//! This crate defines the [`lexer!`] macro, which generates the specialized code of a lexer defined
+//! with the flexer at compile time.
+//!
+//! It is an alternative to generating the code in a build script with
+//! [`enso_flexer::generate::specialize`], which requires the definition of the lexer to live in a
+//! separate crate from the generated code. The macro lives in a separate crate from the flexer, as
+//! it runs the code generation of the latter.
+
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unsafe_code)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+
+extern crate proc_macro;
+
+mod definition;
+
+use crate::definition::Definition;
+
+
+
+// =============
+// === Lexer ===
+// =============
+
+/// Generate the specialized code of a lexer from the definitions of its groups and rules.
+///
+/// The input starts with the name of the lexer type and the type of its output, followed by the
+/// groups of the lexer. Each group has a name and an optional parent, given after a colon, and
+/// consists of rules mapping the patterns to the callbacks, just like the ones created with
+/// [`enso_flexer::group::Group::create_rule`]. The patterns are written as regexes, in the syntax
+/// described in [`enso_flexer::automata::pattern::parser`]. A group can be given the
+/// `#[longest_match]` attribute to set [`enso_flexer::group::Group::longest_match`].
+///
+/// ```ignore
+/// lexer! {
+///     lexer WordLexer -> TokenStream;
+///
+///     group ROOT {
+///         "[a-z]+" => self.on_word(reader),
+///         "\\z"    => self.on_end(reader),
+///     }
+///
+///     #[longest_match]
+///     group NUMBER : ROOT {
+///         "[0-9]+" => self.on_number(reader),
+///     }
+/// }
+/// ```
+///
+/// The macro expands to the same `impl` block as the one generated by
+/// [`enso_flexer::generate::specialize`], so that it expects the same items in scope as the code
+/// generated by the latter, that is the contents of `enso_flexer` and `enso_flexer::prelude`.
+/// Additionally, it defines the associated function `define_groups` on the lexer type, which
+/// defines the groups in an empty [`enso_flexer::group::Registry`] and returns their identifiers
+/// in the order of their definitions. It is meant to be used by the implementation of
+/// [`enso_flexer::State::new`], as the generated code identifies the groups by their positions in
+/// the registry.
+#[proc_macro]
+pub fn lexer(input:proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let definition = syn::parse_macro_input!(input as Definition);
+    definition.expand().unwrap_or_else(|error| error.to_compile_error()).into()
+}
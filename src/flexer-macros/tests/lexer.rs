@@ -0,0 +1,285 @@
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains the tests of a lexer generated with the `lexer!` macro, for a language of
+//! words, assignments of numbers to words, and string literals.
+
+use enso_flexer::*;
+use enso_flexer::prelude::*;
+
+use enso_flexer::group::Registry;
+use enso_flexer::prelude::logger::Disabled;
+use enso_flexer::prelude::reader::BookmarkManager;
+use enso_flexer::prelude::reader::decoder::DecoderUTF8;
+use enso_flexer_macros::lexer;
+
+
+
+// ====================
+// === Type Aliases ===
+// ====================
+
+type Logger = Disabled;
+
+
+
+// ===========
+// === AST ===
+// ===========
+
+/// A token of the language.
+#[derive(Clone,Debug,PartialEq)]
+pub enum Token {
+    /// A word, consisting of lowercase letters.
+    Word(String),
+    /// An assignment of a number to a word.
+    Assignment(String,String),
+    /// The contents of a string literal.
+    Text(String),
+    /// A token that the lexer is unable to recognise.
+    Unrecognized(String),
+}
+
+/// A representation of a stream of tokens.
+#[allow(missing_docs)]
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct TokenStream {
+    tokens:Vec<Token>
+}
+
+impl TokenStream {
+    /// Append the provided token to the token stream.
+    pub fn push(&mut self,token:Token) {
+        self.tokens.push(token);
+    }
+}
+
+
+
+// ==================
+// === Word Lexer ===
+// ==================
+
+/// The lexer of the language.
+#[derive(Debug)]
+pub struct WordLexer {
+    lexer:Flexer<WordState,TokenStream,Logger>
+}
+
+impl Deref for WordLexer {
+    type Target = Flexer<WordState,TokenStream,Logger>;
+    fn deref(&self) -> &Self::Target {
+        &self.lexer
+    }
+}
+
+impl DerefMut for WordLexer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lexer
+    }
+}
+
+impl WordLexer {
+    /// Creates a new instance of this lexer.
+    pub fn new() -> Self {
+        let logger = Logger::new("WordLexer");
+        let lexer  = Flexer::new(logger);
+        WordLexer{lexer}
+    }
+}
+
+lexer! {
+    lexer WordLexer -> TokenStream;
+
+    group ROOT {
+        "[a-z]+"                            => self.on_word(reader),
+        "(?<name>[a-z]+)=(?<value>[0-9]+)"  => self.on_assignment(reader),
+        " "                                 => self.on_space(reader),
+        "\""                                => self.on_string_start(reader),
+        "\\z"                               => self.on_end(reader),
+        "."                                 => self.on_unrecognized(reader),
+    }
+
+    #[longest_match]
+    group STRING {
+        "[^\"]+" => self.on_text(reader),
+        "\""     => self.on_string_end(reader),
+        "\\z"    => self.on_end(reader),
+    }
+}
+
+/// The callbacks of the rules.
+#[allow(dead_code,missing_docs)]
+impl WordLexer {
+    fn on_word<R:ReaderOps>(&mut self, _reader:&mut R) {
+        let token = Token::Word(self.current_match.clone());
+        self.output.push(token);
+    }
+
+    fn on_assignment<R:ReaderOps>(&mut self, _reader:&mut R) {
+        let name  = self.capture("name").unwrap_or_default().to_string();
+        let value = self.capture("value").unwrap_or_default().to_string();
+        self.output.push(Token::Assignment(name,value));
+    }
+
+    fn on_space<R:ReaderOps>(&mut self, _reader:&mut R) {}
+
+    fn on_string_start<R:ReaderOps>(&mut self, _reader:&mut R) {
+        let string_state = self.string_state;
+        self.push_state(string_state);
+    }
+
+    fn on_text<R:ReaderOps>(&mut self, _reader:&mut R) {
+        let token = Token::Text(self.current_match.clone());
+        self.output.push(token);
+    }
+
+    fn on_string_end<R:ReaderOps>(&mut self, _reader:&mut R) {
+        self.pop_state();
+    }
+
+    fn on_end<R:ReaderOps>(&mut self, _reader:&mut R) {}
+
+    fn on_unrecognized<R:ReaderOps>(&mut self, _reader:&mut R) {
+        let token = Token::Unrecognized(self.current_match.clone());
+        self.output.push(token);
+    }
+}
+
+
+// === Trait Impls ===
+
+impl enso_flexer::Definition for WordLexer {
+    fn define() -> Self {
+        WordLexer::new()
+    }
+
+    fn groups(&self) -> &Registry {
+        self.lexer.groups()
+    }
+
+    fn set_up(&mut self) {}
+
+    fn tear_down(&mut self) {}
+}
+
+impl Default for WordLexer {
+    fn default() -> Self {
+        WordLexer::new()
+    }
+}
+
+
+
+// ===================
+// === Lexer State ===
+// ===================
+
+/// The stateful components of the lexer.
+#[derive(Debug)]
+pub struct WordState {
+    /// The registry for groups in the lexer.
+    lexer_states:group::Registry,
+    /// The initial state of the lexer.
+    initial_state:group::Identifier,
+    /// The state entered in a string literal.
+    string_state:group::Identifier,
+    /// The bookmarks for this lexer.
+    bookmarks:BookmarkManager
+}
+
+
+// === Trait Impls ===
+
+impl enso_flexer::State for WordState {
+    fn new(_logger:&impl AnyLogger) -> Self {
+        let mut lexer_states             = group::Registry::default();
+        let [initial_state,string_state] = WordLexer::define_groups(&mut lexer_states);
+        let bookmarks                    = BookmarkManager::new();
+        Self{lexer_states,initial_state,string_state,bookmarks}
+    }
+
+    fn initial_state(&self) -> group::Identifier {
+        self.initial_state
+    }
+
+    fn groups(&self) -> &group::Registry {
+        &self.lexer_states
+    }
+
+    fn groups_mut(&mut self) -> &mut group::Registry {
+        &mut self.lexer_states
+    }
+
+    fn bookmarks(&self) -> &BookmarkManager {
+        &self.bookmarks
+    }
+
+    fn bookmarks_mut(&mut self) -> &mut BookmarkManager {
+        &mut self.bookmarks
+    }
+
+    fn specialize(&self) -> Result<String,GenError> {
+        unreachable_panic!("The lexer is specialized by the `lexer!` macro.")
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+/// Executes the lexer on the provided input string slice.
+fn run_test_on(str:impl AsRef<str>) -> Vec<Token> {
+    let reader     = Reader::new(str.as_ref().as_bytes(),DecoderUTF8());
+    let mut lexer  = WordLexer::new();
+    let run_result = lexer.run(reader);
+    match run_result.kind {
+        enso_flexer::ResultKind::Success => run_result.tokens.tokens,
+        _                                => default()
+    }
+}
+
+#[test]
+fn test_words() {
+    let expected = vec![Token::Word("ab".into()),Token::Word("c".into())];
+    assert_eq!(run_test_on("ab c"),expected);
+}
+
+#[test]
+fn test_assignments() {
+    let expected = vec![
+        Token::Assignment("ab".into(),"12".into()),
+        Token::Word("c".into()),
+        Token::Unrecognized("=".into()),
+    ];
+    assert_eq!(run_test_on("ab=12 c="),expected);
+}
+
+#[test]
+fn test_strings() {
+    let expected = vec![
+        Token::Word("a".into()),
+        Token::Text("b c".into()),
+        Token::Word("d".into()),
+        Token::Text("e".into()),
+    ];
+    assert_eq!(run_test_on("a\"b c\" d\"e"),expected);
+}
+
+#[test]
+fn test_groups() {
+    let mut registry  = Registry::default();
+    let [root,string] = WordLexer::define_groups(&mut registry);
+    assert_eq!(registry.group(root).name,"ROOT");
+    assert_eq!(registry.group(string).name,"STRING");
+    assert!(!registry.group(root).longest_match);
+    assert!(registry.group(string).longest_match);
+}
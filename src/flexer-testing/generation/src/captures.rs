@@ -0,0 +1,71 @@
+//! This module contains a lexer for a language testing the capture groups of the flexer,
+//! specialized at compile time by the `lexer!` macro. For the actual tests of this code, please see
+//! `tests/flexer_captures.rs`.
+//!
+//! The language here is being defined as follows:
+//!
+//! word   = 'a'..'z'+;
+//! number = '0'..'9'+;
+//! entry  = word, ('=', number)?;
+//! list   = '[', word, (',', word)*, ']';
+//!
+//! The callbacks read the word and the number of an entry, as well as the first and the last word
+//! of a list, from the capture groups of their rules.
+
+use enso_flexer::*;
+use enso_flexer::prelude::*;
+
+use enso_flexer_macros::lexer;
+
+
+
+// ==================
+// === Test Lexer ===
+// ==================
+
+test_lexer! {
+    lexer CapturesLexer;
+
+    state CapturesState {
+        /// The initial state of the lexer.
+        initial_state,
+    }
+
+    tokens {
+        /// An entry, with its word and its number, if any.
+        Entry(String,Option<String>),
+        /// A list, with its first word and its last word, if it has more than one.
+        List(String,Option<String>),
+        /// A token that the lexer is unable to recognise.
+        Unrecognized(String),
+    }
+}
+
+lexer! {
+    lexer CapturesLexer -> TokenStream;
+
+    group ROOT {
+        "(?<key>[a-z]+)(=(?<value>[0-9]+))?"        => self.on_entry(reader),
+        "\\[(?<first>[a-z]+)(,(?<last>[a-z]+))*\\]" => self.on_list(reader),
+        " "                                         => self.on_space(reader),
+        "\\z"                                       => self.on_end(reader),
+        "."                                         => self.on_word(reader,Token::Unrecognized),
+    }
+}
+
+/// Rules for the root state.
+impl CapturesLexer {
+    fn on_entry<R:ReaderOps>(&mut self, _reader:&mut R) {
+        let key   = self.capture("key").unwrap_or_default().to_string();
+        let value = self.capture("value").map(String::from);
+        self.output.push(Token::Entry(key,value));
+    }
+
+    fn on_list<R:ReaderOps>(&mut self, _reader:&mut R) {
+        let first = self.capture("first").unwrap_or_default().to_string();
+        let last  = self.capture("last").map(String::from);
+        self.output.push(Token::List(first,last));
+    }
+
+    fn on_space<R:ReaderOps>(&mut self, _reader:&mut R) {}
+}
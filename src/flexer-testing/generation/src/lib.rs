@@ -5,6 +5,9 @@
 //!
 //! This separation enables generation of the enso lexer source code with `build.rs` during
 //! compilation. Its output is then stored in a new file `engine.rs`and exported by `lexer.rs`.
+//!
+//! The other lexers of the crate are specialized by the `lexer!` macro instead, which keeps their
+//! definitions and the generated code together in the modules defining them.
 
 #![feature(test)]
 #![deny(unconditional_recursion)]
@@ -16,4 +19,199 @@
 #![warn(unsafe_code)]
 #![warn(unused_import_braces)]
 
+
+
+// ===================
+// === Test Lexers ===
+// ===================
+
+/// Define the scaffolding of a test lexer specialized by the `lexer!` macro.
+///
+/// The macro defines the `Token` type of the language, with the provided variants, the
+/// `TokenStream` output by the lexer, the lexer type wrapping a [`Flexer`](enso_flexer::Flexer)
+/// and the state type of the lexer. The state keeps the identifiers of the groups in the provided
+/// fields, in the order in which the groups are defined by the `lexer!` macro, and the first group
+/// is the initial state of the lexer. The lexer has the `on_word` and `on_end` callbacks used by
+/// all the test languages.
+///
+/// The module invoking the macro thus only defines the rules of the lexer with the `lexer!` macro,
+/// together with any other callbacks they use. It imports `enso_flexer::*` and
+/// `enso_flexer::prelude::*`, just as the code generated by the `lexer!` macro requires.
+///
+/// ```ignore
+/// test_lexer! {
+///     lexer WordLexer;
+///
+///     state WordState {
+///         /// The initial state of the lexer.
+///         initial_state,
+///     }
+///
+///     tokens {
+///         /// A word.
+///         Word(String),
+///     }
+/// }
+/// ```
+macro_rules! test_lexer {
+    ( lexer $lexer:ident;
+
+      state $state:ident {
+          $(#[$initial_meta:meta])* $initial:ident
+          $(, $(#[$group_meta:meta])* $group:ident)* $(,)?
+      }
+
+      tokens {
+          $($(#[$token_meta:meta])* $token:ident($($field:ty),*)),* $(,)?
+      }
+    ) => {
+        type Logger = enso_flexer::prelude::logger::Disabled;
+
+
+        // === AST ===
+
+        /// A token of the language, recording the rule that produced it.
+        #[derive(Clone,Debug,PartialEq)]
+        pub enum Token {
+            $($(#[$token_meta])* $token($($field),*),)*
+        }
+
+        /// A representation of a stream of tokens.
+        #[allow(missing_docs)]
+        #[derive(Clone,Debug,Default,PartialEq)]
+        pub struct TokenStream {
+            tokens:Vec<Token>
+        }
+
+        impl TokenStream {
+            /// Append the provided token to the token stream.
+            pub fn push(&mut self,token:Token) {
+                self.tokens.push(token);
+            }
+        }
+
+        impl From<Vec<Token>> for TokenStream {
+            fn from(tokens: Vec<Token>) -> Self {
+                TokenStream {tokens}
+            }
+        }
+
+
+        // === Test Lexer ===
+
+        /// The definition of a test lexer for the language of the module.
+        #[derive(Debug)]
+        pub struct $lexer {
+            lexer:Flexer<$state,TokenStream,Logger>
+        }
+
+        impl Deref for $lexer {
+            type Target = Flexer<$state,TokenStream,Logger>;
+            fn deref(&self) -> &Self::Target {
+                &self.lexer
+            }
+        }
+
+        impl DerefMut for $lexer {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.lexer
+            }
+        }
+
+        impl $lexer {
+            /// Creates a new instance of this lexer.
+            pub fn new() -> Self {
+                let logger = Logger::new(stringify!($lexer));
+                let lexer  = Flexer::new(logger);
+                $lexer{lexer}
+            }
+        }
+
+        /// Rules shared by the test languages.
+        #[allow(dead_code,missing_docs)]
+        impl $lexer {
+            fn on_word<R:ReaderOps>(&mut self, _reader:&mut R, token:fn(String) -> Token) {
+                let ast = token(self.current_match.clone());
+                self.output.push(ast);
+            }
+
+            fn on_end<R:ReaderOps>(&mut self, _reader:&mut R) {}
+        }
+
+        impl enso_flexer::Definition for $lexer {
+            fn define() -> Self {
+                $lexer::new()
+            }
+
+            fn groups(&self) -> &group::Registry {
+                self.lexer.groups()
+            }
+
+            fn set_up(&mut self) {}
+
+            fn tear_down(&mut self) {}
+        }
+
+        impl Default for $lexer {
+            fn default() -> Self {
+                $lexer::new()
+            }
+        }
+
+
+        // === Lexer State ===
+
+        /// The stateful components of the test lexer.
+        #[derive(Debug)]
+        pub struct $state {
+            /// The registry for groups in the lexer.
+            lexer_states:group::Registry,
+            $(#[$initial_meta])*
+            $initial:group::Identifier,
+            $($(#[$group_meta])* $group:group::Identifier,)*
+            /// The bookmarks for this lexer.
+            bookmarks:enso_flexer::prelude::reader::BookmarkManager,
+        }
+
+        impl enso_flexer::State for $state {
+            fn new(_logger:&impl AnyLogger) -> Self {
+                let mut lexer_states        = group::Registry::default();
+                let [$initial $(,$group)*] = $lexer::define_groups(&mut lexer_states);
+                let bookmarks               = enso_flexer::prelude::reader::BookmarkManager::new();
+                Self{lexer_states,$initial,$($group,)*bookmarks}
+            }
+
+            fn initial_state(&self) -> group::Identifier {
+                self.$initial
+            }
+
+            fn groups(&self) -> &group::Registry {
+                &self.lexer_states
+            }
+
+            fn groups_mut(&mut self) -> &mut group::Registry {
+                &mut self.lexer_states
+            }
+
+            fn bookmarks(&self) -> &enso_flexer::prelude::reader::BookmarkManager {
+                &self.bookmarks
+            }
+
+            fn bookmarks_mut(&mut self) -> &mut enso_flexer::prelude::reader::BookmarkManager {
+                &mut self.bookmarks
+            }
+
+            fn specialize(&self) -> Result<String,GenError> {
+                unreachable_panic!("The lexer is specialized by the `lexer!` macro.")
+            }
+        }
+    };
+}
+
+
+
+pub mod captures;
 pub mod generated;
+pub mod line_anchors;
+pub mod longest_match;
+pub mod lookahead;
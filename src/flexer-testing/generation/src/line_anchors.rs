@@ -0,0 +1,60 @@
+//! This module contains a lexer for a language testing the line anchors of the flexer, specialized
+//! at compile time by the `lexer!` macro. For the actual tests of this code, please see
+//! `tests/flexer_line_anchors.rs`.
+//!
+//! The language here is being defined as follows:
+//!
+//! word  = 'a'..'z'+;
+//! space = ' ' | '\n';
+//!
+//! A word at the start of a line is lexed as a first word, and a word at the end of a line as a
+//! last word. The former takes precedence for a word spanning the whole line.
+
+use enso_flexer::*;
+use enso_flexer::prelude::*;
+
+use enso_flexer_macros::lexer;
+
+
+
+// ==================
+// === Test Lexer ===
+// ==================
+
+test_lexer! {
+    lexer LineAnchorsLexer;
+
+    state LineAnchorsState {
+        /// The initial state of the lexer.
+        initial_state,
+    }
+
+    tokens {
+        /// A word at the start of a line.
+        First(String),
+        /// A word at the end of a line.
+        Last(String),
+        /// A word inside a line.
+        Word(String),
+        /// A token that the lexer is unable to recognise.
+        Unrecognized(String),
+    }
+}
+
+lexer! {
+    lexer LineAnchorsLexer -> TokenStream;
+
+    group ROOT {
+        "^[a-z]+" => self.on_word(reader,Token::First),
+        "[a-z]+$" => self.on_word(reader,Token::Last),
+        "[a-z]+"  => self.on_word(reader,Token::Word),
+        "[ \n]"   => self.on_space(reader),
+        "\\z"     => self.on_end(reader),
+        "."       => self.on_word(reader,Token::Unrecognized),
+    }
+}
+
+/// Rules for the root state.
+impl LineAnchorsLexer {
+    fn on_space<R:ReaderOps>(&mut self, _reader:&mut R) {}
+}
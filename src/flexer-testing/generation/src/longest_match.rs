@@ -0,0 +1,77 @@
+//! This module contains a lexer for a language in which the rules of a group with
+//! [`longest_match`](group::Group::longest_match) set trigger differently than the same rules of a
+//! group without it, specialized at compile time by the `lexer!` macro. For the actual tests of
+//! this code, please see `tests/flexer_longest_match.rs`.
+//!
+//! The language here is being defined as follows:
+//!
+//! a-word = 'a';
+//! b-word = 'b';
+//! c-word = ('a', 'c' | 'b', 'd'), 'c', 'c';
+//! switch = '!';
+//!
+//! The words are matched in the order given above. Once a `switch` is read, the rest of the input
+//! is lexed with longest-match semantics, by a child group inheriting the same rules.
+//!
+//! Both an a-word and a b-word can be followed by a prefix of a c-word, with the paths following
+//! the two matches merging in the automaton once the first `c` after `ac` or `bd` is read. Without
+//! longest-match semantics, the lexer falls back to the rule decided for the merged path when the
+//! lexer is generated, which is the rule of the a-word, so that it triggers on the `b` of `bdc`.
+//! With longest-match semantics, the rule of the b-word triggers there instead.
+
+use enso_flexer::*;
+use enso_flexer::prelude::*;
+
+use enso_flexer_macros::lexer;
+
+
+
+// ==================
+// === Test Lexer ===
+// ==================
+
+test_lexer! {
+    lexer LongestMatchLexer;
+
+    state LongestMatchState {
+        /// The initial state of the lexer.
+        initial_state,
+        /// The state with longest-match semantics, entered after a `switch`.
+        longest_match_state,
+    }
+
+    tokens {
+        /// An a-word.
+        A(String),
+        /// A b-word.
+        B(String),
+        /// A c-word.
+        C(String),
+        /// A token that the lexer is unable to recognise.
+        Unrecognized(String),
+    }
+}
+
+lexer! {
+    lexer LongestMatchLexer -> TokenStream;
+
+    group FIRST_MATCH {
+        "a"         => self.on_word(reader,Token::A),
+        "b"         => self.on_word(reader,Token::B),
+        "(ac|bd)cc" => self.on_word(reader,Token::C),
+        "!"         => self.on_switch(reader),
+        "\\z"       => self.on_end(reader),
+        "."         => self.on_word(reader,Token::Unrecognized),
+    }
+
+    #[longest_match]
+    group LONGEST_MATCH : FIRST_MATCH {}
+}
+
+/// Rules for both of the states.
+impl LongestMatchLexer {
+    fn on_switch<R:ReaderOps>(&mut self, _reader:&mut R) {
+        let id = self.longest_match_state;
+        self.push_state(id);
+    }
+}
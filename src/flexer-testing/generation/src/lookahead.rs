@@ -0,0 +1,61 @@
+//! This module contains a lexer for a language testing the lookaheads of the flexer, specialized
+//! at compile time by the `lexer!` macro. For the actual tests of this code, please see
+//! `tests/flexer_lookahead.rs`.
+//!
+//! The language here is being defined as follows:
+//!
+//! a-word = 'a'+;
+//! b-word = 'b'+;
+//! space  = ' ';
+//!
+//! An a-word followed by a space and a b-word is lexed as a word of its own, without consuming the
+//! space and the b-word that follow it.
+
+use enso_flexer::*;
+use enso_flexer::prelude::*;
+
+use enso_flexer_macros::lexer;
+
+
+
+// ==================
+// === Test Lexer ===
+// ==================
+
+test_lexer! {
+    lexer LookaheadLexer;
+
+    state LookaheadState {
+        /// The initial state of the lexer.
+        initial_state,
+    }
+
+    tokens {
+        /// An a-word.
+        A(String),
+        /// An a-word followed by a space and a b-word.
+        ABeforeB(String),
+        /// A b-word.
+        B(String),
+        /// A token that the lexer is unable to recognise.
+        Unrecognized(String),
+    }
+}
+
+lexer! {
+    lexer LookaheadLexer -> TokenStream;
+
+    group ROOT {
+        "a+(?= b+)" => self.on_word(reader,Token::ABeforeB),
+        "a+"        => self.on_word(reader,Token::A),
+        "b+"        => self.on_word(reader,Token::B),
+        " "         => self.on_space(reader),
+        "\\z"       => self.on_end(reader),
+        "."         => self.on_word(reader,Token::Unrecognized),
+    }
+}
+
+/// Rules for the root state.
+impl LookaheadLexer {
+    fn on_space<R:ReaderOps>(&mut self, _reader:&mut R) {}
+}
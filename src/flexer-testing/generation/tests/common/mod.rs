@@ -0,0 +1,20 @@
+//! The helpers shared by the tests of the lexers specialized by the `lexer!` macro.
+
+/// Define the `run_test_on` function, executing a new instance of the `$lexer` on the provided
+/// input string slice and returning the `$output` of the lexer, which is empty if lexing fails.
+macro_rules! run_test_on {
+    ($lexer:ty => $output:ty) => {
+        /// Executes the test on the provided input string slice.
+        fn run_test_on(str:impl AsRef<str>) -> $output {
+            let decoder    = enso_flexer::prelude::reader::decoder::DecoderUTF8();
+            let reader     = enso_flexer::prelude::Reader::new(str.as_ref().as_bytes(),decoder);
+            let mut lexer  = <$lexer>::new();
+            let run_result = lexer.run(reader);
+
+            match run_result.kind {
+                enso_flexer::ResultKind::Success => run_result.tokens,
+                _                                => Default::default()
+            }
+        }
+    };
+}
@@ -0,0 +1,58 @@
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for the capture groups of the generated lexer.
+
+#[macro_use]
+mod common;
+
+use flexer_test_generation::captures::CapturesLexer;
+use flexer_test_generation::captures::Token;
+use flexer_test_generation::captures::TokenStream;
+
+
+
+// =============
+// === Tests ===
+// =============
+
+run_test_on!(CapturesLexer => TokenStream);
+
+#[test]
+fn test_entries() {
+    let expected_output = TokenStream::from(vec![
+        Token::Entry("a".into(),Some("1".into())),
+        Token::Entry("bc".into(),None),
+        Token::Entry("de".into(),Some("23".into())),
+    ]);
+    assert_eq!(run_test_on("a=1 bc de=23"),expected_output);
+}
+
+#[test]
+fn test_entry_without_number() {
+    let expected_output = TokenStream::from(vec![
+        Token::Entry("a".into(),None),
+        Token::Unrecognized("=".into()),
+        Token::Entry("b".into(),None),
+    ]);
+    assert_eq!(run_test_on("a= b"),expected_output);
+}
+
+#[test]
+fn test_lists() {
+    let expected_output = TokenStream::from(vec![
+        Token::List("a".into(),None),
+        Token::List("a".into(),Some("d".into())),
+        Token::Unrecognized("[".into()),
+        Token::Entry("b".into(),None),
+        Token::Unrecognized(",".into()),
+    ]);
+    assert_eq!(run_test_on("[a] [a,bc,d] [b,"),expected_output);
+}
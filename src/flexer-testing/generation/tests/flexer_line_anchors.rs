@@ -0,0 +1,50 @@
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for the line anchors of the generated lexer.
+
+#[macro_use]
+mod common;
+
+use flexer_test_generation::line_anchors::LineAnchorsLexer;
+use flexer_test_generation::line_anchors::Token;
+use flexer_test_generation::line_anchors::TokenStream;
+
+
+
+// =============
+// === Tests ===
+// =============
+
+run_test_on!(LineAnchorsLexer => TokenStream);
+
+#[test]
+fn test_words_at_line_boundaries() {
+    let expected_output = TokenStream::from(vec![
+        Token::First("ab".into()),
+        Token::Word("cd".into()),
+        Token::Last("ef".into()),
+        Token::First("gh".into()),
+        Token::Last("ij".into()),
+    ]);
+    assert_eq!(run_test_on("ab cd ef\ngh\n ij"),expected_output);
+}
+
+#[test]
+fn test_words_inside_line() {
+    let expected_output = TokenStream::from(vec![
+        Token::First("ab".into()),
+        Token::Unrecognized("#".into()),
+        Token::Word("cd".into()),
+        Token::Unrecognized("#".into()),
+        Token::Last("ef".into()),
+    ]);
+    assert_eq!(run_test_on("ab#cd#ef"),expected_output);
+}
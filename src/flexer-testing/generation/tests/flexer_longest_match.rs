@@ -0,0 +1,66 @@
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for the longest-match semantics of the generated lexer.
+
+#[macro_use]
+mod common;
+
+use flexer_test_generation::longest_match::LongestMatchLexer;
+use flexer_test_generation::longest_match::Token;
+use flexer_test_generation::longest_match::TokenStream;
+
+
+
+// =============
+// === Tests ===
+// =============
+
+run_test_on!(LongestMatchLexer => TokenStream);
+
+#[test]
+fn test_full_match() {
+    let expected_output = TokenStream::from(vec![Token::C("accc".into()),Token::C("bdcc".into())]);
+    assert_eq!(run_test_on("acccbdcc"),expected_output);
+    assert_eq!(run_test_on("!acccbdcc"),expected_output);
+}
+
+#[test]
+fn test_partial_match() {
+    let expected_output = TokenStream::from(vec![
+        Token::A("a".into()),
+        Token::Unrecognized("c".into()),
+        Token::Unrecognized("c".into()),
+        Token::B("b".into()),
+        Token::Unrecognized("d".into()),
+    ]);
+    assert_eq!(run_test_on("accbd"),expected_output);
+    assert_eq!(run_test_on("!accbd"),expected_output);
+}
+
+#[test]
+fn test_first_match_on_merged_paths() {
+    let expected_output = TokenStream::from(vec![
+        Token::A("b".into()),
+        Token::Unrecognized("d".into()),
+        Token::Unrecognized("c".into()),
+    ]);
+    assert_eq!(run_test_on("bdc"),expected_output);
+}
+
+#[test]
+fn test_longest_match_on_merged_paths() {
+    let expected_output = TokenStream::from(vec![
+        Token::B("b".into()),
+        Token::Unrecognized("d".into()),
+        Token::Unrecognized("c".into()),
+    ]);
+    assert_eq!(run_test_on("!bdc"),expected_output);
+}
@@ -0,0 +1,49 @@
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for the lookaheads of the generated lexer.
+
+#[macro_use]
+mod common;
+
+use flexer_test_generation::lookahead::LookaheadLexer;
+use flexer_test_generation::lookahead::Token;
+use flexer_test_generation::lookahead::TokenStream;
+
+
+
+// =============
+// === Tests ===
+// =============
+
+run_test_on!(LookaheadLexer => TokenStream);
+
+#[test]
+fn test_word_before_context() {
+    let expected_output = TokenStream::from(vec![
+        Token::ABeforeB("aa".into()),
+        Token::B("b".into()),
+        Token::ABeforeB("a".into()),
+        Token::B("bb".into()),
+        Token::A("a".into()),
+    ]);
+    assert_eq!(run_test_on("aa b a bb a"),expected_output);
+}
+
+#[test]
+fn test_word_without_context() {
+    let expected_output = TokenStream::from(vec![
+        Token::A("aa".into()),
+        Token::B("b".into()),
+        Token::A("a".into()),
+        Token::Unrecognized("c".into()),
+    ]);
+    assert_eq!(run_test_on("aa  b a c"),expected_output);
+}
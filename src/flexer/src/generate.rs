@@ -5,17 +5,21 @@ use crate::prelude::*;
 use quote::*;
 use syn::*;
 
+use crate::automata::capture::Operation;
+use crate::automata::capture::Tag;
 use crate::automata::dfa::Dfa;
 use crate::automata::nfa;
 use crate::automata::dfa;
+use crate::automata::pattern;
 use crate::automata::state::State;
+use crate::automata::symbol::Symbol;
 use crate::group::Group;
 use crate::group::AutomatonData;
 use crate::group;
 
 use enso_macro_utils::repr;
 use proc_macro2::Literal;
-use std::hash::BuildHasher;
+use proc_macro2::TokenStream;
 use std::result::Result;
 use std::fmt;
 
@@ -37,7 +41,26 @@ pub fn specialize
 , state_type_name  : impl Str
 , output_type_name : impl Str
 ) -> Result<String,GenError> {
-    let group_registry = definition.groups();
+    let result = specialize_groups(definition.groups(),state_type_name,output_type_name)?;
+    let code   = show_code(&result);
+    Ok(code)
+}
+
+/// Generate the specialized `impl` block of the lexer for the provided `group_registry`.
+///
+/// See [`specialize`] for details. The rules of the groups cannot have closures as their callbacks,
+/// as these can only be executed by the [`flexer::runtime::Engine`].
+pub fn specialize_groups
+( group_registry   : &group::Registry
+, state_type_name  : impl Str
+, output_type_name : impl Str
+) -> Result<ItemImpl,GenError> {
+    for rule in group_registry.all().iter().flat_map(|group| group.rules.iter()) {
+        if rule.closure.is_some() {
+            return Err(GenError::ClosureCallback(rule.to_string()))
+        }
+        rule.pattern.check().map_err(|err| GenError::BadPattern(rule.to_string(),err))?;
+    }
     let mut body_items = vec![
         run_function(output_type_name)?,
         run_current_state_function(),
@@ -46,9 +69,7 @@ pub fn specialize
     for group in group_registry.all().iter() {
         body_items.extend(automaton_for_group(group,group_registry)?)
     }
-    let result = wrap_in_impl_for(state_type_name,body_items)?;
-    let code   = show_code(&result);
-    Ok(code)
+    wrap_in_impl_for(state_type_name,body_items)
 }
 
 
@@ -76,6 +97,7 @@ pub fn run_function(output_type_name:impl Str) -> Result<ImplItem,GenError> {
     let tree:ImplItem    = parse_quote! {
         pub fn run<R:ReaderOps>(&mut self, mut reader:R) -> LexingResult<#output_type_name> {
             self.set_up();
+            self.line_start = true;
             reader.advance_char(&mut self.bookmarks);
             while self.run_current_state(&mut reader) == StageStatus::ExitSuccess {}
             let result = match self.status {
@@ -179,49 +201,135 @@ pub fn automaton_for_group
 ( group    : &Group
 , registry : &group::Registry
 ) -> Result<Vec<ImplItem>,GenError> {
-    let mut nfa       = registry.to_nfa_from(group.id);
+    let nfa       = registry.to_nfa_from(group.id);
     let mut rules = Vec::with_capacity(nfa.states().len());
     for state in nfa.public_states().iter() {
         if nfa.name(*state).is_some() {
             rules.push(rule_for_state(*state,&nfa)?);
         }
     }
-    let mut dfa             = Dfa::from(nfa.automaton());
+    let dfa                 = minimize_dfa(&Dfa::from(nfa.automaton()),&nfa);
     let dispatch_for_dfa    = dispatch_in_state(&dfa,group.id.into())?;
-    let mut dfa_transitions = transitions_for_dfa(&mut dfa,&mut nfa,group.id.into())?;
+    let mut dfa_transitions = transitions_for_dfa(&dfa,&nfa,group.id.into())?;
     dfa_transitions.push(dispatch_for_dfa);
+    if nfa.longest_match() {
+        dfa_transitions.push(accept_rule_function(&nfa,group.id.into())?);
+    }
     dfa_transitions.extend(rules);
     Ok(dfa_transitions)
 }
 
+/// Minimize the `dfa` generated for the rules described by `data`.
+///
+/// States are merged only if they accept the same rule. As the states that do not accept a rule
+/// fall back to the rule they inherit (see [`inherited_rules`]), states inheriting different rules
+/// are kept apart as well. The states in which the bodies of lookaheads end are kept apart too, as
+/// they bookmark the input (see [`transition_for_dfa`]). If the automaton has transitions on the
+/// line anchors, its start state is kept apart from all the other states, as it is the only one
+/// that checks whether a line starts (see [`transition_for_dfa`]).
+pub fn minimize_dfa(dfa:&Dfa, data:&AutomatonData) -> Dfa {
+    let rules     = dfa.sources.iter().map(|s| data.name_for_dfa_state(s)).collect_vec();
+    let inherited = inherited_rules(dfa,data);
+    let body_ends = dfa.sources.iter().map(|s| data.lookahead_bodies_in_dfa_state(s));
+    let body_ends = body_ends.collect_vec();
+    let anchored  = dfa.alphabet.own_division(&Symbol::line_start()).is_some();
+    dfa.minimize_by(|state| {
+        let is_start = anchored && state == Dfa::START_STATE;
+        (rules[state.id()],inherited[state.id()],body_ends[state.id()].clone(),is_start)
+    })
+}
+
+/// Get the rule that each state of the `dfa` that accepts no rule falls back to, for the rules
+/// described by `data`.
+///
+/// The states are visited in order, and each state accepting or inheriting a rule passes it on to
+/// the states it leads to that neither accept nor inherit a rule yet. The rule is thus decided for
+/// each state when the lexer is generated, regardless of the path taken to reach the state.
+///
+/// No state inherits a rule if the rules have longest-match semantics, as the rule to fall back to
+/// is remembered while lexing instead (see [`branch_body`]).
+pub fn inherited_rules(dfa:&Dfa, data:&AutomatonData) -> Vec<Option<nfa::State>> {
+    let rules         = dfa.sources.iter().map(|s| data.rule_for_dfa_state(s)).collect_vec();
+    let mut inherited = vec![None;rules.len()];
+    if data.longest_match() { return inherited }
+    for state_ix in dfa.links.row_indices() {
+        let rule = rules[state_ix].or(inherited[state_ix]);
+        if rule.is_some() {
+            for column in 0..dfa.links.columns {
+                let target = dfa.links[(state_ix,column)];
+                if !target.is_invalid() && rules[target.id()].or(inherited[target.id()]).is_none() {
+                    inherited[target.id()] = rule;
+                }
+            }
+        }
+    }
+    inherited
+}
+
 /// Generate a set of transition functions for the provided `dfa`, with identifier `id`.
 pub fn transitions_for_dfa
-( dfa  : &mut Dfa
-, data : &mut AutomatonData
+( dfa  : &Dfa
+, data : &AutomatonData
 , id   : usize
 ) -> Result<Vec<ImplItem>,GenError> {
-    let mut state_has_overlapping_rules:HashMap<usize,bool> = HashMap::new();
-    state_has_overlapping_rules.insert(0,false);
     let state_names:Vec<_> = dfa.links.row_indices().map(|ix| (ix,name_for_step(id,ix))).collect();
+    let body_ends          = dfa.sources.iter().map(|s| data.lookahead_bodies_in_dfa_state(s));
+    let body_ends          = body_ends.collect_vec();
+    let inherited          = inherited_rules(dfa,data);
     let mut transitions    = Vec::with_capacity(state_names.len());
     for (ix,name) in state_names.into_iter() {
-        transitions.push(transition_for_dfa(dfa,name,data,ix,&mut state_has_overlapping_rules)?)
+        transitions.push(transition_for_dfa(dfa,name,data,id,ix,&body_ends[ix],&inherited)?)
     }
     Ok(transitions)
 }
 
 /// Generate a specific transition function for
-#[allow(clippy::implicit_hasher)]
-pub fn transition_for_dfa<S:BuildHasher>
-( dfa             : &mut Dfa
+///
+/// The function first bookmarks the input for each of the lookaheads whose bodies end in the state,
+/// as listed in `body_ends`, so that the lexer can rewind to that point once the rule with the
+/// lookahead is accepted.
+///
+/// The function of the start state of an automaton with transitions on the line anchors continues
+/// in the state reached through the transition on [`Symbol::line_start`] if the input starts a
+/// line, as tracked by the `line_start` field of the [`flexer::Flexer`]. The operations of that
+/// transition on the registers are applied beforehand.
+///
+/// The `inherited` rules of the states are given by [`inherited_rules`].
+pub fn transition_for_dfa
+( dfa             : &Dfa
 , transition_name : Ident
-, data            : &mut AutomatonData
+, data            : &AutomatonData
+, id              : usize
 , state_ix        : usize
-, has_overlaps    : &mut HashMap<usize,bool,S>
+, body_ends       : &[usize]
+, inherited       : &[Option<nfa::State>]
 ) -> Result<ImplItem,GenError> {
-    let match_expr:Expr   = match_for_transition(dfa,state_ix,data,has_overlaps)?;
+    let match_expr:Expr   = match_for_transition(dfa,id,state_ix,data,inherited)?;
+    let line_start        = dfa.alphabet.own_division(&Symbol::line_start());
+    let line_start        = line_start.filter(|_| state_ix == Dfa::START_STATE.id());
+    let line_start        = line_start.map(|column| (column,dfa.links[(state_ix,column)]));
+    let line_start        = line_start.filter(|(_,target)| target.id() != state_ix);
+    let line_start        = line_start.map(|(column,target)| {
+        let function   = name_for_step(id,target.id());
+        let operations = apply_operations(dfa.tags.transition(State::new(state_ix),column));
+        quote! {
+            if self.line_start {
+                #operations
+                return self.#function(reader)
+            }
+        }
+    });
+    let bookmarks         = body_ends.iter().map(|index| {
+        let index = Literal::usize_unsuffixed(*index);
+        quote! {
+            let context_bookmark = self.bookmarks.context_bookmark(#index);
+            self.bookmarks.bookmark(context_bookmark,reader);
+        }
+    });
     let function:ImplItem = parse_quote! {
         fn #transition_name<R:ReaderOps>(&mut self, reader:&mut R) -> StageStatus {
+            #line_start
+            #(#bookmarks)*
             #match_expr
         }
     };
@@ -229,31 +337,51 @@ pub fn transition_for_dfa<S:BuildHasher>
 }
 
 /// Generate the pattern match for a given transition function.
-pub fn match_for_transition<S:BuildHasher>
-( dfa          : &mut Dfa
-, state_ix     : usize
-, data         : &mut AutomatonData
-, has_overlaps : &mut HashMap<usize,bool,S>
+///
+/// If the state of an automaton with transitions on the line anchors differs from the state
+/// reached from it through the transition on [`Symbol::line_end`], the symbols ending a line are
+/// dispatched on in the function of the latter state instead (see [`line_end_body`]).
+pub fn match_for_transition
+( dfa       : &Dfa
+, id        : usize
+, state_ix  : usize
+, data      : &AutomatonData
+, inherited : &[Option<nfa::State>]
 ) -> Result<Expr,GenError> {
-    let overlaps          = *has_overlaps.get(&state_ix).unwrap_or(&false);
-    let mut trigger_state = dfa.links[(state_ix,0)];
+    let line_end          = dfa.alphabet.own_division(&Symbol::line_end());
+    let line_end          = line_end.map(|column| dfa.links[(state_ix,column)]);
+    let line_end          = line_end.filter(|target| target.id() != state_ix);
+    let ends_line         = |sym:&Symbol| *sym == Symbol::from('\n') || *sym == Symbol::eof();
+    let target_for        = |sym:&Symbol, ix:usize| match line_end {
+        Some(line_end) if ends_line(sym) => Target::LineEnd(line_end),
+        _                                => {
+            let operations = dfa.tags.transition(State::new(state_ix),ix);
+            Target::Link(dfa.links[(state_ix,ix)],operations)
+        },
+    };
+    let mut trigger_state = target_for(&Symbol::min(),0);
     let mut range_start   = enso_automata::symbol::SymbolIndex::min_value();
     let divisions         = dfa.alphabet.division_map.clone();
     let mut branches      = Vec::with_capacity(divisions.len());
+    let body_for          = |target| match target {
+        Target::Link(target,operations) => {
+            branch_body(dfa,id,target,operations,state_ix,data,inherited)
+        },
+        Target::LineEnd(target) => Ok(line_end_body(dfa,id,target,state_ix,data,inherited)),
+    };
     for (sym, ix) in divisions.into_iter() {
-        let new_trigger_state = dfa.links[(state_ix,ix)];
+        let new_trigger_state = target_for(&sym,ix);
         if new_trigger_state != trigger_state {
             let range_end             = if sym.index != 0 { sym.index - 1 } else { sym.index };
             let current_trigger_state = trigger_state;
             let current_range_start   = range_start;
             trigger_state             = new_trigger_state;
             range_start               = sym.index;
-            let body =
-                branch_body(dfa,current_trigger_state,state_ix,data,has_overlaps,overlaps)?;
+            let body                  = body_for(current_trigger_state)?;
             branches.push(Branch::new(Some(current_range_start..=range_end),body));
         } else {}
     }
-    let catch_all_branch_body = branch_body(dfa,trigger_state,state_ix,data,has_overlaps,overlaps)?;
+    let catch_all_branch_body = body_for(trigger_state)?;
     let catch_all_branch      = Branch::new(None,catch_all_branch_body);
     branches.push(catch_all_branch);
     let arms:Vec<Arm> = branches.into_iter().map(Into::into).collect();
@@ -267,80 +395,256 @@ pub fn match_for_transition<S:BuildHasher>
 }
 
 /// Generate the branch body for a transition in the DFA.
-pub fn branch_body<S:BuildHasher>
-( dfa           : &mut Dfa
-, target_state  : State<Dfa>
-, state_ix      : usize
-, data          : &mut AutomatonData
-, has_overlaps  : &mut HashMap<usize,bool,S>
-, rules_overlap : bool
+///
+/// If the transition is invalid, the rule accepted in the state is triggered (see [`rule_body`]).
+/// The invalid transitions from the states that do not accept a rule trigger the rule they fall
+/// back to instead, with the input rewound to the end of its match, or fail if there is none.
+///
+/// By default, the rule to fall back to is the one the state inherits (see [`inherited_rules`]),
+/// and the valid transitions from a state accepting a rule to a state inheriting it bookmark the
+/// input. The rule is thus decided for each state regardless of the path taken to reach it, and the
+/// lexer triggers a wrong rule if the paths following different matches merge.
+///
+/// If the rules have longest-match semantics, the valid transitions from a state accepting a rule
+/// to a state that does not accept one bookmark the input and remember the rule in the
+/// `accepted_rule` field of the [`flexer::Flexer`]. The invalid transitions from the states that do
+/// not accept a rule then trigger the rule remembered along the path taken through the automaton,
+/// through the function generated by [`accept_rule_function`] for the automaton `id`. The longest
+/// match thus wins, even if the paths following different matches merge, and the order of the
+/// rules only breaks the ties between the matches of the same length.
+///
+/// The valid transitions apply their `operations` on the registers recording the capture groups,
+/// and the rules store the spans of their groups in the output registers once they are accepted or
+/// remembered, so that the spans are known when the callbacks of the rules are executed.
+pub fn branch_body
+( dfa          : &Dfa
+, id           : usize
+, target_state : State<Dfa>
+, operations   : &[Operation]
+, state_ix     : usize
+, data         : &AutomatonData
+, inherited    : &[Option<nfa::State>]
 ) -> Result<Block,GenError> {
-    let sources             = dfa.sources.get(state_ix).expect("Internal error.");
-    let rule_name_for_state = data.name_for_dfa_state(sources);
+    let sources        = dfa.sources.get(state_ix).expect("Internal error.");
+    let rule_for_state = data.rule_for_dfa_state(sources);
     if target_state == State::<Dfa>::INVALID {
-        match rule_name_for_state {
-            None => {
+        match rule_for_state {
+            Some(rule) => {
+                let body   = rule_body(rule,data,false)?;
+                let finals = final_operations(dfa,state_ix,rule,data);
+                if finals.is_empty() { return Ok(body) }
+                let finals = apply_operations(&finals);
                 Ok(parse_quote! {{
-                    StageStatus::ExitFail
+                    #finals
+                    #body
                 }})
             },
-            Some(rule) => {
-                let rule:Expr = match parse_str(rule) {
-                    Ok(rule) => rule,
-                    Err(_) => return Err(GenError::BadExpression(rule.to_string()))
-                };
-                if rules_overlap {
-                    Ok(parse_quote! {{
-                        let rule_bookmark    = self.bookmarks.rule_bookmark;
-                        let matched_bookmark = self.bookmarks.matched_bookmark;
-                        self.bookmarks.rewind(rule_bookmark,reader);
-                        self.current_match = reader.pop_result();
-                        self.#rule(reader);
-                        self.bookmarks.bookmark(matched_bookmark,reader);
-                        StageStatus::ExitSuccess
-                    }})
-                } else {
-                    Ok(parse_quote! {{
-                        let matched_bookmark = self.bookmarks.matched_bookmark;
-                        self.current_match   = reader.pop_result();
-                        self.#rule(reader);
-                        self.bookmarks.bookmark(matched_bookmark,reader);
-                        StageStatus::ExitSuccess
-                    }})
-                }
-            }
+            None if data.longest_match() => {
+                let accept = name_for_accept(id);
+                Ok(parse_quote! {{
+                    match self.accepted_rule.take() {
+                        Some(rule) => self.#accept(rule,reader),
+                        None       => StageStatus::ExitFail,
+                    }
+                }})
+            },
+            None => match inherited[state_ix] {
+                Some(rule) => rule_body(rule,data,true),
+                None       => Ok(parse_quote! {{
+                    StageStatus::ExitFail
+                }}),
+            },
         }
     } else {
-        let target_state_has_no_rule = match rule_name_for_state {
-            Some(_) => if !dfa_has_rule_name_for(&data, dfa, target_state) {
-                dfa.sources[target_state.id()] = (*sources).clone();
-                has_overlaps.insert(target_state.id(),true);
-                true
-            } else {
-                false
-            },
-            None => false
-        };
-
-        let state_id = Literal::usize_unsuffixed(target_state.id());
-        let ret:Expr = parse_quote! {
+        let state_id   = Literal::usize_unsuffixed(target_state.id());
+        let left       = left_rule(dfa,state_ix,target_state,data,inherited);
+        let remember   = left.map(|rule| remember_rule(dfa,state_ix,rule,data));
+        let operations = apply_operations(operations);
+        Ok(parse_quote! {{
+            #remember
+            #operations
             StageStatus::ContinueWith(#state_id.into())
-        };
+        }})
+    }
+}
 
-        if target_state_has_no_rule && !rules_overlap {
-            Ok(parse_quote! {{
-                let rule_bookmark = self.bookmarks.rule_bookmark;
-                self.bookmarks.bookmark(rule_bookmark,reader);
-                #ret
-            }})
-        } else {
-            Ok(parse_quote! {{
-                #ret
-            }})
+/// Generate the block triggering the `rule`.
+///
+/// If the rule has a lookahead, the input is first rewound to the end of its body, and the
+/// bookmarks of the lookaheads are unset afterwards, so that they do not keep the input in the
+/// buffer of the reader. If `rewind_to_rule` is set, the input is rewound to the `rule_bookmark`
+/// beforehand, as the rule is accepted in an earlier state than the current one.
+pub fn rule_body
+(rule:nfa::State, data:&AutomatonData, rewind_to_rule:bool) -> Result<Block,GenError> {
+    let name      = data.name(rule).expect("The rule must have a name.");
+    let name:Expr = match parse_str(name) {
+        Ok(name) => name,
+        Err(_)   => return Err(GenError::BadExpression(name.to_string()))
+    };
+    let rewind = match data.lookahead_for_rule(rule) {
+        Some(index) => {
+            let index = Literal::usize_unsuffixed(index);
+            quote! {
+                let context_bookmark = self.bookmarks.context_bookmark(#index);
+                self.bookmarks.rewind(context_bookmark,reader);
+            }
+        },
+        None => quote! {},
+    };
+    let rewind = if data.lookaheads().is_empty() { rewind } else {
+        quote! {
+            #rewind
+            self.bookmarks.unset_context_bookmarks();
         }
+    };
+    if rewind_to_rule {
+        Ok(parse_quote! {{
+            let rule_bookmark    = self.bookmarks.rule_bookmark;
+            let matched_bookmark = self.bookmarks.matched_bookmark;
+            self.bookmarks.rewind(rule_bookmark,reader);
+            #rewind
+            self.set_current_match(reader.pop_result());
+            self.#name(reader);
+            self.bookmarks.bookmark(matched_bookmark,reader);
+            StageStatus::ExitSuccess
+        }})
+    } else {
+        Ok(parse_quote! {{
+            let matched_bookmark = self.bookmarks.matched_bookmark;
+            #rewind
+            self.set_current_match(reader.pop_result());
+            self.#name(reader);
+            self.bookmarks.bookmark(matched_bookmark,reader);
+            StageStatus::ExitSuccess
+        }})
     }
 }
 
+/// Get the rule accepted in the state `state_ix` of the `dfa` that is left for the `target` state,
+/// if the lexer may fall back to it from there, as described in [`branch_body`].
+pub fn left_rule
+( dfa       : &Dfa
+, state_ix  : usize
+, target    : State<Dfa>
+, data      : &AutomatonData
+, inherited : &[Option<nfa::State>]
+) -> Option<nfa::State> {
+    let rule = data.rule_for_dfa_state(&dfa.sources[state_ix]);
+    let rule = rule.filter(|_| !dfa_has_rule_name_for(data,dfa,target));
+    rule.filter(|rule| data.longest_match() || inherited[target.id()] == Some(*rule))
+}
+
+/// Generate the statements bookmarking the input at the end of the match of the `rule`, accepted
+/// in the state `state_ix` of the `dfa`, and remembering the rule as the last one accepted if the
+/// rules have longest-match semantics.
+///
+/// The spans of the capture groups of the rule are stored in the output registers, which the
+/// transitions that follow never write to.
+pub fn remember_rule
+(dfa:&Dfa, state_ix:usize, rule:nfa::State, data:&AutomatonData) -> TokenStream {
+    let finals   = apply_operations(&final_operations(dfa,state_ix,rule,data));
+    let rule     = Literal::usize_unsuffixed(rule.id());
+    let remember = data.longest_match().as_some(quote! {
+        self.accepted_rule = Some(#rule);
+    });
+    quote! {
+        let rule_bookmark = self.bookmarks.rule_bookmark;
+        self.bookmarks.bookmark(rule_bookmark,reader);
+        #remember
+        #finals
+    }
+}
+
+/// Get the operations storing the spans of the capture groups of the `rule`, accepted in the state
+/// `state_ix` of the `dfa`, in the output registers.
+///
+/// Only the registers of the groups of the rule are written, as the other groups may be shared
+/// with the other rules.
+pub fn final_operations
+(dfa:&Dfa, state_ix:usize, rule:nfa::State, data:&AutomatonData) -> Vec<Operation> {
+    let groups    = data.capture_groups(rule).into_iter().map(|(_,index)| index);
+    let registers = groups.flat_map(|index| vec![Tag::Start(index),Tag::End(index)]);
+    let registers = registers.map(Tag::index).collect_vec();
+    let finals    = dfa.tags.finals(State::new(state_ix),rule).iter();
+    finals.filter(|operation| registers.contains(&operation.register())).copied().collect()
+}
+
+/// Generate the statement applying the `operations` to the registers of the lexer, at the
+/// position of the current character. Nothing is generated if there are no `operations`.
+pub fn apply_operations(operations:&[Operation]) -> TokenStream {
+    if operations.is_empty() { return quote! {} }
+    let path       = quote! {::enso_flexer::automata::capture::Operation};
+    let operations = operations.iter().map(|operation| match *operation {
+        Operation::Set(register) => {
+            let register = Literal::usize_unsuffixed(register);
+            quote! {#path::Set(#register)}
+        },
+        Operation::Copy {register,from} => {
+            let register = Literal::usize_unsuffixed(register);
+            let from     = Literal::usize_unsuffixed(from);
+            quote! {#path::Copy {register:#register,from:#from}}
+        },
+        Operation::Clear(register) => {
+            let register = Literal::usize_unsuffixed(register);
+            quote! {#path::Clear(#register)}
+        },
+    });
+    quote! {
+        self.registers.apply(&[#(#operations),*],reader.result().len());
+    }
+}
+
+/// Generate the function triggering the rule remembered by the automaton `id` of the rules with
+/// longest-match semantics, identified by its state in the `data`.
+///
+/// The input is rewound to the `rule_bookmark`, which marks the end of the match of the rule.
+pub fn accept_rule_function(data:&AutomatonData, id:usize) -> Result<ImplItem,GenError> {
+    let accept = name_for_accept(id);
+    let rules  = data.public_states().iter().filter(|state| data.name(**state).is_some());
+    let arms   = rules.map(|rule| {
+        let body  = rule_body(*rule,data,true)?;
+        let index = Literal::usize_unsuffixed(rule.id());
+        Ok(parse_quote! {
+            #index => #body,
+        })
+    }).collect::<Result<Vec<Arm>,GenError>>()?;
+    Ok(parse_quote! {
+        fn #accept<R:ReaderOps>(&mut self, rule:usize, reader:&mut R) -> StageStatus {
+            match rule {
+                #(#arms)*
+                _ => unreachable_panic!("Unknown rule accepted in lexer."),
+            }
+        }
+    })
+}
+
+/// Generate the branch body dispatching on a symbol ending a line in the function of the
+/// `line_end` state, reached from the state `state_ix` through the transition on
+/// [`Symbol::line_end`].
+///
+/// If the state accepts a rule and the `line_end` state may fall back to it, the rule is
+/// remembered, just as for the targets of the transitions in [`branch_body`]. The operations of the
+/// transition on [`Symbol::line_end`] are applied afterwards.
+pub fn line_end_body
+( dfa       : &Dfa
+, id        : usize
+, line_end  : State<Dfa>
+, state_ix  : usize
+, data      : &AutomatonData
+, inherited : &[Option<nfa::State>]
+) -> Block {
+    let function   = name_for_step(id,line_end.id());
+    let rule       = left_rule(dfa,state_ix,line_end,data,inherited);
+    let remember   = rule.map(|rule| remember_rule(dfa,state_ix,rule,data));
+    let column     = dfa.alphabet.index_of_symbol(&Symbol::line_end());
+    let operations = apply_operations(dfa.tags.transition(State::new(state_ix),column));
+    parse_quote! {{
+        #remember
+        #operations
+        self.#function(reader)
+    }}
+}
+
 /// Generate the dispatch function for a given lexer state.
 ///
 /// This dispatch function is responsible for dispatching based on the sub-state of any given lexer
@@ -376,6 +680,12 @@ pub fn dispatch_in_state(dfa:&Dfa, id:usize) -> Result<ImplItem,GenError> {
     Ok(func)
 }
 
+/// Generate the name of the function triggering the rule remembered by the automaton `id`.
+pub fn name_for_accept(id:usize) -> Ident {
+    let name_str = format!("accept_rule_in_state_{}",id);
+    parse_str(name_str.as_str()).expect("Impossible to not be a valid identifier.")
+}
+
 /// Generate a name for a given step function.
 pub fn name_for_step(in_state:usize, to_state:usize) -> Ident {
     let name_str = format!("state_{}_to_{}",in_state,to_state);
@@ -383,6 +693,9 @@ pub fn name_for_step(in_state:usize, to_state:usize) -> Ident {
 }
 
 /// Generate an executable rule function for a given lexer state.
+///
+/// If the pattern of the rule has capture groups, the function reads their spans from the output
+/// registers, which the automaton has filled in while matching, before executing the callback.
 pub fn rule_for_state(state:nfa::State, automaton:&AutomatonData) -> Result<ImplItem,GenError> {
     let state_name = automaton.name(state);
     match state_name {
@@ -397,8 +710,23 @@ pub fn rule_for_state(state:nfa::State, automaton:&AutomatonData) -> Result<Impl
             if !has_reader_arg(&code) {
                 return Err(GenError::BadCallbackArgument)
             }
+            let groups   = automaton.capture_groups(state);
+            let captures = if groups.is_empty() { quote! {} } else {
+                let names   = groups.iter().map(|(name,_)| name);
+                let indices = groups.iter().map(|(_,index)| Literal::usize_unsuffixed(*index));
+                quote! {
+                    ::enso_flexer::prelude::lazy_static! {
+                        static ref GROUPS: ::enso_flexer::automata::capture::Groups =
+                            ::enso_flexer::automata::capture::Groups::new(vec![
+                                #((#names,#indices)),*
+                            ]);
+                    }
+                    self.set_captures(&GROUPS);
+                }
+            };
             let tree:ImplItem = parse_quote! {
                 fn #rule_name<R:ReaderOps>(&mut self, reader:&mut R) {
+                    #captures
                     #code
                 }
             };
@@ -456,6 +784,10 @@ pub enum GenError {
     BadLiteral(String),
     /// The provided string is not a valid rust path.
     BadPath(String),
+    /// The provided rule has a closure as its callback, which cannot be generated.
+    ClosureCallback(String),
+    /// The pattern of the provided rule cannot be used in a lexer.
+    BadPattern(String,pattern::Error),
 }
 
 
@@ -471,12 +803,35 @@ impl Display for GenError {
             GenError::BadExpression(str) => write!(f,"`{}` is not a valid rust expression.",str),
             GenError::BadLiteral(str)    => write!(f,"`{}` is not a valid rust literal.",str),
             GenError::BadPath(str)       => write!(f,"`{}` is not a valid rust path.",str),
+            GenError::ClosureCallback(str) => write!(f,
+                "The rule {} has a closure as its callback, which cannot be generated.",str
+            ),
+            GenError::BadPattern(str,error) => write!(f,
+                "The rule {} has an invalid pattern. {}",str,error
+            ),
         }
     }
 }
 
 
 
+// ==============
+// === Target ===
+// ==============
+
+/// The target of a transition in the generated code.
+#[derive(Clone,Copy,Debug,PartialEq)]
+enum Target<'a> {
+    /// The transition to the state, after consuming the symbol, with its operations on the
+    /// registers.
+    Link(State<Dfa>,&'a [Operation]),
+    /// The dispatch on the symbol ending a line in the function of the state reached through the
+    /// transition on [`Symbol::line_end`].
+    LineEnd(State<Dfa>),
+}
+
+
+
 // ==============
 // === Branch ===
 // ==============
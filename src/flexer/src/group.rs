@@ -2,9 +2,13 @@
 
 use crate::prelude::*;
 
+use crate::automata::dfa::Dfa;
+use crate::automata::dfa::Examples;
 use crate::automata::nfa::Nfa;
 use crate::automata::{nfa, state};
 use crate::automata::pattern::Pattern;
+use crate::automata::visualization::Diagram;
+use crate::automata::visualization::Format;
 use crate::group::rule::Rule;
 
 use itertools::Itertools;
@@ -61,6 +65,20 @@ impl Registry {
         group.create_rule(pattern,callback.as_ref());
     }
 
+    /// Creates a rule that matches `pattern` for the group identified by `group_id`, executing the
+    /// `closure` in the [`crate::runtime::Engine`].
+    ///
+    /// Panics if `group_id` refers to a nonexistent group.
+    pub fn create_closure_rule<L:'static,R:'static>
+    ( &mut self
+    , group   : Identifier
+    , pattern : &Pattern
+    , closure : impl Fn(&mut L,&mut R) + Send + Sync + 'static
+    ) {
+        let group = self.group_mut(group);
+        group.create_closure_rule(pattern,closure);
+    }
+
     /// Associates the provided `rule` with the group identified by `group_id`.
     ///
     /// Panics if `group_id` refers to a nonexistent group.
@@ -105,7 +123,9 @@ impl Registry {
 
     /// Converts the group identified by `group_id` into an NFA.
     ///
-    /// Returns `None` if the group does not exist, or if the conversion fails.
+    /// The epsilon links of the NFA are [eliminated](AutomatonData::eliminate_epsilons) to speed up
+    /// its conversion to a DFA, unless the rules have capture groups, as the elimination loses the
+    /// precedence of the paths that record the groups.
     pub fn to_nfa_from(&self, group_id:Identifier) -> AutomatonData {
         let group     = self.group(group_id);
         let mut nfa   = AutomatonData::default();
@@ -114,15 +134,21 @@ impl Registry {
         let build     = |rule:&Rule| nfa.new_pattern(start,&rule.pattern);
         let rules     = self.rules_for(group.id);
         let callbacks = rules.iter().map(|r| r.callback.clone()).collect_vec();
+        let patterns  = rules.iter().map(|r| r.pattern.clone()).collect_vec();
         let states    = rules.into_iter().map(build).collect_vec();
         let end       = nfa.new_state_exported();
+        nfa.longest_match = group.longest_match;
         for (ix,state) in states.into_iter().enumerate() {
             nfa.add_public_state(state);
             nfa.set_name(state,group.callback_name(ix));
             nfa.set_code(state,callbacks.get(ix).unwrap().clone());
+            nfa.set_pattern(state,patterns[ix].clone());
             nfa.connect(state,end);
         }
         nfa.add_public_state(end);
+        if !nfa.automaton.has_tags() {
+            nfa.eliminate_epsilons();
+        }
         nfa
     }
 
@@ -155,6 +181,10 @@ pub struct AutomatonData {
     transition_names : HashMap<nfa::State,String>,
     /// The code to execute on a callback, where available.
     callback_code : HashMap<nfa::State,String>,
+    /// The patterns of the rules, where available.
+    patterns : HashMap<nfa::State,Pattern>,
+    /// Whether the rules have longest-match semantics, as described in [`Group::longest_match`].
+    longest_match : bool,
 }
 
 impl AutomatonData {
@@ -168,11 +198,31 @@ impl AutomatonData {
         self.callback_code.insert(state_id,code.into());
     }
 
+    /// Set the pattern of the rule for the provided `state_id`.
+    pub fn set_pattern(&mut self, state_id:nfa::State, pattern:Pattern) {
+        self.patterns.insert(state_id,pattern);
+    }
+
     /// Add the provided `state` to the state registry.
     pub fn add_public_state(&mut self, state:nfa::State) {
         self.states.push(state);
     }
 
+    /// Eliminate the epsilon links of the automaton with [`Nfa::without_epsilons`], renumbering
+    /// the states that the names, callbacks and patterns of the rules are attached to.
+    pub fn eliminate_epsilons(&mut self) {
+        fn renumber_keys<T>(map:&mut HashMap<nfa::State,T>, renumbered:&[nfa::State]) {
+            let entries = mem::take(map).into_iter();
+            *map = entries.map(|(state,value)| (renumbered[state.id()],value)).collect()
+        }
+        let (automaton,renumbered) = self.automaton.without_epsilons();
+        renumber_keys(&mut self.transition_names,&renumbered);
+        renumber_keys(&mut self.callback_code,&renumbered);
+        renumber_keys(&mut self.patterns,&renumbered);
+        self.states    = self.states.iter().map(|state| renumbered[state.id()]).collect();
+        self.automaton = automaton;
+    }
+
     /// Get the name for the provided `state_id`, if present.
     pub fn name(&self, state_id:nfa::State) -> Option<&str> {
         self.transition_names.get(&state_id).map(|s| s.as_str())
@@ -183,6 +233,31 @@ impl AutomatonData {
         self.callback_code.get(&state_id).map(|s| s.as_str())
     }
 
+    /// Get the pattern of the rule for the provided `state_id`, if present.
+    pub fn pattern(&self, state_id:nfa::State) -> Option<&Pattern> {
+        self.patterns.get(&state_id)
+    }
+
+    /// Get the capture groups of the `rule`, paired with their indices in
+    /// [`Nfa::capture_names`].
+    ///
+    /// Only the groups of the body of a lookahead are included, as the lexers rewind the input to
+    /// the end of the body before executing the rule.
+    pub fn capture_groups(&self, rule:nfa::State) -> Vec<(&str,usize)> {
+        let pattern = self.pattern(rule).map(Pattern::simplify);
+        let body    = match pattern {
+            Some(Pattern::Lookahead {body,..}) => *body,
+            Some(pattern)                      => pattern,
+            None                               => return default(),
+        };
+        let names  = self.automaton.capture_names();
+        let groups = body.capture_names().into_iter().filter_map(|name| {
+            let index = names.iter().position(|group| group == name)?;
+            Some((names[index].as_str(),index))
+        }).collect();
+        groups
+    }
+
     /// Get a reference to the public states for this automaton.
     ///
     /// A public state is one that was explicitly defined by the user.
@@ -210,17 +285,58 @@ impl AutomatonData {
         &self.automaton
     }
 
+    /// Check whether the rules have longest-match semantics, as described in
+    /// [`Group::longest_match`].
+    pub fn longest_match(&self) -> bool {
+        self.longest_match
+    }
+
+    /// Get the state of the rule accepted in the DFA state with the provided `sources`.
+    pub fn rule_for_dfa_state(&self, sources:&[nfa::State]) -> Option<nfa::State> {
+        sources.iter().copied().find(|source| self.name(*source).is_some())
+    }
+
     /// Get the rule name for a the provided state.
     pub fn name_for_dfa_state(&self, sources:&[nfa::State]) -> Option<&str> {
-        let mut result = None;
-        for source in sources.iter() {
-            let name = self.name(*source);
-            if name.is_some() {
-                result = name;
-                break;
-            }
-        }
-        result
+        self.rule_for_dfa_state(sources).and_then(|rule| self.name(rule))
+    }
+
+    /// Get the index of the lookahead of the rule accepted in the DFA state with the provided
+    /// `sources`, if that rule is a lookahead.
+    ///
+    /// The lookaheads are indexed by their position in [`Nfa::lookaheads`].
+    pub fn lookahead_for_dfa_state(&self, sources:&[nfa::State]) -> Option<usize> {
+        self.lookahead_for_rule(self.rule_for_dfa_state(sources)?)
+    }
+
+    /// Get the index of the lookahead of the `rule`, if it is a lookahead.
+    ///
+    /// The lookaheads are indexed by their position in [`Nfa::lookaheads`].
+    pub fn lookahead_for_rule(&self, rule:nfa::State) -> Option<usize> {
+        self.automaton.lookaheads().keys().position(|end| *end == rule)
+    }
+
+    /// Get the indices of the lookaheads whose bodies end in the DFA state with the provided
+    /// `sources`.
+    pub fn lookahead_bodies_in_dfa_state(&self, sources:&[nfa::State]) -> Vec<usize> {
+        let body_ends = self.automaton.lookaheads().values().enumerate();
+        body_ends.filter(|(_,body_end)| sources.contains(body_end)).map(|(ix,_)| ix).collect()
+    }
+
+    /// Get a generator of the example inputs for the rule named `name`, using the `dfa` built
+    /// from this automaton.
+    ///
+    /// An input is accepted if the rule triggered after consuming it is the one named `name`, so
+    /// that the accepted inputs can be used to exercise that rule in the generated lexer.
+    pub fn rule_examples<'a>(&self, dfa:&'a Dfa, name:&str) -> Examples<'a> {
+        dfa.examples_by(|state| self.name_for_dfa_state(&dfa.sources[state.id()]) == Some(name))
+    }
+
+    /// Get a diagram of the automaton in the provided `format`, with the states that accept the
+    /// rules labelled with the rule names.
+    pub fn diagram(&self, format:Format) -> Diagram {
+        let diagram = Diagram::new(format);
+        self.transition_names.iter().fold(diagram,|diagram,(state,name)| diagram.rule(*state,name))
     }
 }
 
@@ -298,7 +414,12 @@ impl From<Identifier> for usize {
 ///
 /// Internally, the flexer maintains a stack of groups, where only one group can be active at any
 /// given time. Rules are matched _in order_, and hence overlaps are handled by the order in which
-/// the rules are matched, with the first callback being triggered.
+/// the rules are matched, with the first callback being triggered. Once a rule matches, the lexer
+/// keeps reading as long as a longer match is possible, falling back to the rule if there is none.
+/// By default, the rule to fall back to is decided for each state of the automaton when the lexer
+/// is generated, which picks a wrong rule if the paths following different matches merge. Groups
+/// with [`longest_match`](Group::longest_match) set remember the last match while lexing instead,
+/// so that the longest match is always triggered, with the order of the rules only breaking ties.
 ///
 /// Whenever a [`rule.pattern`](Rule::pattern) from the active group is matched against part of the
 /// input, the associated [`rule.callback`](Rule::callback) is executed. This callback may exit the
@@ -321,6 +442,10 @@ pub struct Group {
     pub state_names:HashMap<usize,String>,
     /// The callback functions for the user-defined states.
     pub state_callbacks:HashMap<usize,String>,
+    /// Whether the group has longest-match (maximal munch) semantics.
+    ///
+    /// It is not inherited by the child groups.
+    pub longest_match:bool,
 }
 
 impl Group {
@@ -330,7 +455,9 @@ impl Group {
         let rules           = default();
         let state_names     = default();
         let state_callbacks = default();
-        Group{id,name:name.into(),parent_index,rules,state_names,state_callbacks}
+        let longest_match   = false;
+        let name            = name.into();
+        Group{id,name,parent_index,rules,state_names,state_callbacks,longest_match}
     }
 
     /// Adds a new rule to the current group.
@@ -345,6 +472,13 @@ impl Group {
         self.rules.push(rule)
     }
 
+    /// Creates a new rule executing the `closure` in the [`crate::runtime::Engine`].
+    pub fn create_closure_rule<L:'static,R:'static>
+    (&mut self, pattern:&Pattern, closure:impl Fn(&mut L,&mut R) + Send + Sync + 'static) {
+        let rule = Rule::with_closure(pattern.clone(),closure);
+        self.rules.push(rule)
+    }
+
     /// The canonical name for a given rule.
     pub fn callback_name(&self, rule_ix:usize) -> String {
         format!("group_{}_rule_{}",self.id.0,rule_ix)
@@ -387,6 +521,28 @@ pub mod tests {
         assert_eq!(group.rules[0].callback,"code".to_string());
     }
 
+    #[test]
+    fn group_rule_display() {
+        let pattern = Pattern::range('a'..='z').many1() >> Pattern::all_of("!").opt();
+        let rule    = Rule::new(pattern,"self.on_word(reader)");
+        assert_eq!(rule.to_string(),"`[a-z]+!?` => `self.on_word(reader)`");
+    }
+
+    #[test]
+    fn group_create_closure_rule() {
+        let pattern   = Pattern::all_of("abcde");
+        let mut group = Group::new(0.into(),"Test Name",None);
+        group.create_closure_rule(&pattern,|count:&mut usize, _reader:&mut ()| *count += 1);
+        let closure   = group.rules[0].closure.clone().unwrap();
+        let mut count = 0;
+        (closure.callback::<usize,()>().unwrap())(&mut count,&mut ());
+        assert_eq!(count,1);
+        assert!(closure.callback::<usize,usize>().is_none());
+        assert_eq!(group.rules[0],group.rules[0].clone());
+        assert_ne!(group.rules[0],Rule::with_closure(pattern,|_:&mut usize, _:&mut ()| {}));
+        assert_eq!(group.rules[0].to_string(),"`abcde` => closure");
+    }
+
     #[test]
     fn group_callback_name() {
         let pattern_1 = Pattern::all_of("abcde");
@@ -450,4 +606,44 @@ pub mod tests {
         assert!(rules.iter().any(|r| **r == Rule::new(pattern_2.clone(),"rule_2")));
         assert!(rules.iter().any(|r| **r == Rule::new(pattern_3.clone(),"rule_3")));
     }
+
+    #[test]
+    fn automaton_data_longest_match() {
+        let mut registry = Registry::default();
+        let parent_id    = registry.define_group("PARENT",None);
+        let child_id     = registry.define_group("CHILD",Some(parent_id));
+        registry.group_mut(parent_id).longest_match = true;
+        registry.create_rule(parent_id,&Pattern::char('a'),"rule_a");
+        assert!(registry.to_nfa_from(parent_id).longest_match());
+        assert!(!registry.to_nfa_from(child_id).longest_match());
+    }
+
+    #[test]
+    fn automaton_data_diagram() {
+        let mut registry = Registry::default();
+        let group_id     = registry.define_group("GROUP",None);
+        registry.group_mut(group_id).create_rule(&Pattern::char('a'),"rule_a");
+        let data = registry.to_nfa_from(group_id);
+        let code = data.diagram(Format::Mermaid).render_nfa(&data);
+        assert!(code.contains("<br>group_0_rule_0\"))):::accepting"),"{}",code);
+    }
+
+    #[test]
+    fn automaton_data_rule_examples() {
+        let mut registry = Registry::default();
+        let group_id     = registry.define_group("GROUP",None);
+        let group        = registry.group_mut(group_id);
+        group.create_rule(&Pattern::all_of("if"),"rule_keyword");
+        group.create_rule(&Pattern::range('a'..='z').many1(),"rule_identifier");
+        let data     = registry.to_nfa_from(group_id);
+        let dfa      = Dfa::from(data.automaton());
+        let keywords = data.rule_examples(&dfa,"group_0_rule_0").accepted().collect_vec();
+        assert_eq!(keywords,vec!["if"]);
+        let identifiers = data.rule_examples(&dfa,"group_0_rule_1");
+        let identifiers = identifiers.accepted().take(1000).collect_vec();
+        assert_eq!(identifiers.len(),1000);
+        assert!(identifiers.iter().all(|input| input != "if"));
+        assert!(identifiers.contains(&"i".to_string()));
+        assert!(identifiers.contains(&"iff".to_string()));
+    }
 }
@@ -1,10 +1,24 @@
 //! An API for declaring rust-code callbacks to be executed when a given pattern is matched.
 //!
 //! A flexer rule is a [`crate::automata::pattern`] associated with rust code to be executed as a
-//! callback.
+//! callback. Alternatively, the callback can be given as a [`Closure`], executed by the
+//! [`crate::runtime::Engine`] without generating any code.
+
+use crate::prelude::*;
 
 use crate::automata::pattern::Pattern;
 
+use std::sync::Arc;
+
+
+
+// =============
+// === Types ===
+// =============
+
+/// A callback of a rule taking the lexer `L` and its reader `R`.
+pub type Callback<L,R> = Arc<dyn Fn(&mut L,&mut R) + Send + Sync>;
+
 
 
 // ==========
@@ -24,11 +38,86 @@ pub struct Rule {
     /// a [`crate::Flexer`] instance. To this end, the code you provide as a string must be valid in
     /// that context.
     pub callback:String,
+
+    /// The closure to execute when [`Rule::pattern`] matches, if the rule is executed by the
+    /// [`crate::runtime::Engine`].
+    ///
+    /// The rules with a closure cannot be used to generate code, so that their `callback` is empty.
+    pub closure:Option<Closure>,
 }
 
 impl Rule {
     /// Creates a new rule.
     pub fn new(pattern:Pattern, callback:impl Into<String>) -> Self {
-        Rule{pattern,callback:callback.into()}
+        Rule{pattern,callback:callback.into(),closure:None}
+    }
+
+    /// Creates a new rule executing the `closure`, which takes the lexer `L` and its reader `R`.
+    pub fn with_closure<L:'static,R:'static>
+    (pattern:Pattern, closure:impl Fn(&mut L,&mut R) + Send + Sync + 'static) -> Self {
+        let callback = default();
+        let closure  = Some(Closure::new(closure));
+        Rule{pattern,callback,closure}
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Display for Rule {
+    /// Quote the rule as the regex of its pattern, followed by its callback.
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.closure {
+            Some(_) => write!(f,"`{}` => closure",self.pattern),
+            None    => write!(f,"`{}` => `{}`",self.pattern,self.callback),
+        }
+    }
+}
+
+
+
+// ===============
+// === Closure ===
+// ===============
+
+/// A closure executed as the callback of a [`Rule`].
+///
+/// The type of the closure is erased, so that the rules of all lexers have the same type. It is
+/// recovered with [`Closure::callback`], given the types of the lexer and of the reader that the
+/// closure was created for. The closures are thread safe, so that the rules of a lexer can be
+/// shared between threads.
+#[derive(Clone)]
+pub struct Closure {
+    callback:Arc<dyn Any + Send + Sync>,
+}
+
+impl Closure {
+    /// Constructor.
+    pub fn new<L:'static,R:'static>
+    (closure:impl Fn(&mut L,&mut R) + Send + Sync + 'static) -> Self {
+        let callback:Callback<L,R> = Arc::new(closure);
+        let callback               = Arc::new(callback);
+        Closure{callback}
+    }
+
+    /// Get the callback of the closure, if it takes the lexer `L` and the reader `R`.
+    pub fn callback<L:'static,R:'static>(&self) -> Option<Callback<L,R>> {
+        self.callback.downcast_ref::<Callback<L,R>>().cloned()
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Debug for Closure {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,"Closure")
+    }
+}
+
+/// The closures are equal only if they are the same closure.
+impl PartialEq for Closure {
+    fn eq(&self, other:&Self) -> bool {
+        Arc::ptr_eq(&self.callback,&other.callback)
     }
 }
@@ -989,6 +989,14 @@
 //! Both of these, combined, allow the transition functions to manipulate the text being read by the
 //! lexer.
 //!
+//! The text matched by the rule is available as the `current_match` of the [`Flexer`]. If the
+//! pattern of the rule contains capture groups, created with
+//! [`automata::pattern::Pattern::capture`], the spans of the text on which they triggered are
+//! available as its `captures`, and the text itself through [`Flexer::capture`]. The spans are
+//! recorded in the `registers` of the [`Flexer`] by the transitions of the lexer's automaton while
+//! it is matching, so that they are known as soon as the rule is executed, without scanning the
+//! `current_match` again. The automata of the rules without capture groups do not record them.
+//!
 //! ## Specializing the Lexer
 //!
 //! In order to actually _use_ the lexer that you've defined, you need to specialize it to the rules
@@ -1011,6 +1019,16 @@
 //! is your lexer's token type. All of these functions are defined on your lexer type (the one whose
 //! name is provided to `specialize()`.
 //!
+//! ## Running the Lexer Without Specializing It
+//!
+//! Alternatively, the lexer can be executed by a [`runtime::Engine`] built from its groups, which
+//! runs their automata directly, without generating any code. The rules executed this way are
+//! created with [`group::Registry::create_closure_rule`], taking a closure of type
+//! `Fn(&mut Lexer,&mut R)` instead of a string of rust code, where `Lexer` is your lexer type and
+//! `R` is the type of the reader. The engine triggers the same rules as the specialized code would.
+//! This suits small lexers defined on the fly, at the cost of the performance of the specialized
+//! code.
+//!
 //! ## In Summary
 //!
 //! The flexer allows its clients to define highly optimised lexer implementations that are capable
@@ -1019,6 +1037,9 @@
 use crate::prelude::*;
 use prelude::logger::*;
 
+use crate::automata::capture::Captures;
+use crate::automata::capture::Groups;
+use crate::automata::capture::Registers;
 use crate::generate::GenError;
 use prelude::logger::AnyLogger;
 use prelude::reader::BookmarkManager;
@@ -1028,6 +1049,7 @@ pub use enso_automata::char;
 pub use enso_automata::literal;
 pub mod generate;
 pub mod group;
+pub mod runtime;
 
 /// Useful libraries for working with the flexer.
 pub mod prelude {
@@ -1090,6 +1112,19 @@ pub struct Flexer<Definition,Output,Logger> {
     pub output:Output,
     /// The text of the current match of the lexer.
     pub current_match:String,
+    /// Whether the input after the current match starts a line, so that the rules starting with
+    /// [`automata::pattern::Pattern::line_start`] can match there.
+    pub line_start:bool,
+    /// The spans of the capture groups of the rule that matched the `current_match`, within it.
+    pub captures:Captures,
+    /// The registers recording the positions of the capture groups within the current match,
+    /// updated by the transitions of the lexer's automaton.
+    pub registers:Registers,
+    /// The rule accepted last in the current match, if the active group has longest-match
+    /// semantics and the lexer has read past the end of that match.
+    ///
+    /// The rule is identified by the generated lexer, and its match ends at the `rule_bookmark`.
+    pub accepted_rule:Option<usize>,
     /// A logger for the flexer, accessible in user definitions.
     pub logger:Logger,
     /// The definition of the user-provided state for the lexer.
@@ -1109,9 +1144,16 @@ where Definition : State,
         let initial_state_id = definition.initial_state();
         let mut state_stack  = NonEmptyVec::singleton(initial_state_id);
         let current_match    = default();
+        let line_start       = true;
+        let captures         = default();
+        let registers        = default();
+        let accepted_rule    = None;
 
         state_stack.reserve(constants::STATE_STACK_RESERVATION);
-        Flexer{state_stack,status,output,current_match,logger,definition}
+        Flexer {
+            state_stack,status,output,current_match,line_start,captures,registers,accepted_rule,
+            logger,definition
+        }
     }
 }
 
@@ -1134,6 +1176,31 @@ where Definition : State,
         *self.state_stack.last()
     }
 
+    /// Set the text of the current match, remembering whether the input after it starts a line.
+    ///
+    /// It is called by the generated lexer before executing the rule that matched the `text`.
+    pub fn set_current_match(&mut self, text:String) {
+        if let Some(last) = text.chars().last() {
+            self.line_start = last == '\n';
+        }
+        self.current_match = text;
+        self.captures      = default();
+        self.accepted_rule = None;
+    }
+
+    /// Read the spans of the capture `groups` of the rule that matched the `current_match` from the
+    /// `registers`.
+    ///
+    /// It is called by the generated lexer before executing a rule with capture groups.
+    pub fn set_captures(&mut self, groups:&Groups) {
+        self.captures = groups.captures(&self.registers);
+    }
+
+    /// Get the text matched by the capture group called `name`, if it triggered.
+    pub fn capture(&self, name:&str) -> Option<&str> {
+        self.captures.get(name).map(|span| &self.current_match[span])
+    }
+
     /// Tell the lexer to enter the state described by `state`.
     pub fn push_state(&mut self, state:group::Identifier) {
         self.logger.group_begin(logger::entry::level::Debug,false,
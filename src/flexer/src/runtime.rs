@@ -0,0 +1,468 @@
+//! A runtime engine executing the automata of the groups of a lexer directly, so that the lexer can
+//! be defined without generating its code in a build step.
+//!
+//! The rules executed by the [`Engine`] have [`Closure`](crate::group::rule::Closure)s as their
+//! callbacks, as created with [`Registry::create_closure_rule`]. The engine runs the lexer just as
+//! the code generated by [`generate::specialize`](crate::generate::specialize) would, with or
+//! without the longest-match semantics of [`Group::longest_match`] (see
+//! [`generate::branch_body`](crate::generate::branch_body)).
+//!
+//! The DFAs of the groups are constructed lazily, while lexing (see [`LazyDfa`]), so that building
+//! the engine stays cheap even for the rules with large alphabets, like the ones built from Unicode
+//! classes. Each run of the engine starts with empty caches of the DFA states. The transitions of
+//! the DFAs record the capture groups of the rules in the `registers` of the [`Flexer`], just as
+//! in the generated lexers.
+//!
+//! The rules to fall back to in the groups without longest-match semantics are decided for the
+//! states of the complete DFAs of the groups, so these DFAs are built along with the engine, and
+//! the states reached in them are tracked next to the lazily constructed ones.
+
+use crate::prelude::*;
+
+use crate::Flexer;
+use crate::LexingResult;
+use crate::StageStatus;
+use crate::State;
+use crate::automata::capture::Groups;
+use crate::automata::capture::Registers;
+use crate::automata::dfa::Dfa;
+use crate::automata::dfa::lazy::Cursor;
+use crate::automata::dfa::lazy::LazyDfa;
+use crate::automata::dfa::lazy;
+use crate::automata::dfa;
+use crate::automata::nfa;
+use crate::automata::pattern;
+use crate::automata::symbol::Symbol;
+use crate::generate;
+use crate::group::AutomatonData;
+use crate::group::Group;
+use crate::group::Registry;
+use crate::group::rule::Callback;
+use crate::prelude::logger::LoggerOps;
+use crate::prelude::logger;
+use crate::prelude::reader;
+
+
+
+// ==============
+// === Engine ===
+// ==============
+
+/// An engine running the lexer `L` over the input of a reader `R`, by executing the automata of
+/// the groups of the lexer directly.
+///
+/// See the [module docs](crate::runtime) for details.
+pub struct Engine<L,R> {
+    /// The automata of the groups, indexed by the identifiers of the groups.
+    automata:Vec<Automaton<L,R>>,
+    /// The configuration of the lazily constructed DFAs of the groups.
+    config:lazy::Config,
+}
+
+impl<L:'static,R:'static> Engine<L,R> {
+    /// Build the automata of the groups in the `registry`, with the default configuration of their
+    /// DFAs.
+    ///
+    /// All the rules of the groups need closures taking the lexer `L` and the reader `R`.
+    pub fn new(registry:&Registry) -> Result<Self,Error> {
+        Self::with_config(registry,default())
+    }
+
+    /// Build the automata of the groups in the `registry`, whose DFAs are constructed lazily with
+    /// the provided `config`.
+    ///
+    /// All the rules of the groups need closures taking the lexer `L` and the reader `R`.
+    pub fn with_config(registry:&Registry, config:lazy::Config) -> Result<Self,Error> {
+        let automata = registry.all().iter().map(|group| Automaton::new(group,registry));
+        let automata = automata.collect::<Result<_,_>>()?;
+        Ok(Engine{automata,config})
+    }
+}
+
+impl<L,R,Definition,Output,Logger> Engine<L,R>
+where L          : crate::Definition + DerefMut<Target=Flexer<Definition,Output,Logger>>,
+      R          : ReaderOps,
+      Definition : State,
+      Output     : Clone + Default,
+      Logger     : AnyLogger<Owned=Logger> + LoggerOps<logger::entry::level::Debug> {
+    /// Run the `lexer` over the input of the `reader`, just as the `run` function generated by
+    /// [`generate::specialize`](crate::generate::specialize).
+    pub fn run(&self, lexer:&mut L, mut reader:R) -> LexingResult<Output> {
+        let dfas     = self.automata.iter().map(|automaton| automaton.dfa(self.config));
+        let mut dfas = dfas.collect_vec();
+        lexer.set_up();
+        lexer.line_start = true;
+        reader.advance_char(lexer.bookmarks_mut());
+        while self.run_current_state(lexer,&mut reader,&mut dfas) == StageStatus::ExitSuccess {}
+        let output = mem::take(&mut lexer.output);
+        let result = match lexer.status {
+            StageStatus::ExitFinished => LexingResult::success(output),
+            StageStatus::ExitFail     => LexingResult::failure(output),
+            _                         => LexingResult::partial(output),
+        };
+        lexer.tear_down();
+        result
+    }
+
+    /// Execute the `lexer` in its current state until it matches a rule or fails, running the
+    /// automata of the groups on their `dfas`.
+    fn run_current_state(&self, lexer:&mut L, reader:&mut R, dfas:&mut [LazyDfa]) -> StageStatus {
+        lexer.status     = StageStatus::Initial;
+        let mut finished = false;
+        let mut reached  = None;
+        while lexer.status.continue_as().is_some() {
+            let dfa      = &mut dfas[usize::from(lexer.current_state())];
+            lexer.status = self.step(lexer,dfa,&mut reached,reader);
+            if finished && reader.finished(lexer.bookmarks()) {
+                lexer.status = StageStatus::ExitFinished
+            }
+            finished = reader.character().is_eof();
+            if lexer.status.should_continue() {
+                match reader.character().char {
+                    Ok(char)                       => reader.append_result(char),
+                    Err(reader::Error::EOF)        => {},
+                    Err(reader::Error::EndOfGroup) => {
+                        let current_state = lexer.current_state();
+                        let group_name    = lexer.groups().group(current_state).name.as_str();
+                        panic!("Missing rules for state {}.", group_name)
+                    },
+                    Err(_) => panic!("Unexpected error!"),
+                }
+                reader.advance_char(lexer.bookmarks_mut());
+            }
+        }
+        lexer.status
+    }
+
+    /// Execute the transition of the automaton of the current group on its `dfa`, from the states
+    /// `reached` so far, or from its start state if there are none, on the current character of
+    /// the `reader`.
+    ///
+    /// The `reached` states are moved on, as the sub-state of the lexer cannot identify the states
+    /// that are not cached by the `dfa`.
+    fn step
+    (&self, lexer:&mut L, dfa:&mut LazyDfa, reached:&mut Option<Reached>, reader:&mut R)
+    -> StageStatus {
+        let automaton = &self.automata[usize::from(lexer.current_state())];
+        let position  = reader.result().len();
+        let mut state = match reached.take() {
+            Some(state) => state,
+            None        => {
+                let start      = automaton.start(dfa);
+                let line_start = lexer.line_start;
+                let registers  = &mut lexer.registers;
+                let line_start = line_start.and_option_from(|| {
+                    automaton.next(dfa,&start,&Symbol::line_start(),registers,position)
+                });
+                line_start.unwrap_or(start)
+            },
+        };
+        automaton.bookmark_bodies(dfa,&state.cursor,lexer,reader);
+        let mut rule = automaton.rule_in(dfa,&state.cursor);
+        let symbol   = Symbol::from(u64::from(reader.character()));
+        automaton.store_captures(dfa,&state.cursor,rule,lexer,position);
+        if symbol == Symbol::from('\n') || symbol == Symbol::eof() {
+            let symbol    = Symbol::line_end();
+            let registers = &mut lexer.registers;
+            if let Some(line_end) = automaton.next(dfa,&state,&symbol,registers,position) {
+                let line_end_rule = automaton.rule_in(dfa,&line_end.cursor);
+                automaton.remember_rule(rule,line_end_rule,line_end.complete,lexer,reader);
+                automaton.bookmark_bodies(dfa,&line_end.cursor,lexer,reader);
+                automaton.store_captures(dfa,&line_end.cursor,line_end_rule,lexer,position);
+                state = line_end;
+                rule  = line_end_rule;
+            }
+        }
+        let registers = &mut lexer.registers;
+        if let Some(target) = automaton.next(dfa,&state,&symbol,registers,position) {
+            let target_rule = automaton.rule_in(dfa,&target.cursor);
+            automaton.remember_rule(rule,target_rule,target.complete,lexer,reader);
+            *reached = Some(target);
+            return StageStatus::ContinueWith(default())
+        }
+        match rule {
+            Some(rule) => automaton.accept(rule,false,lexer,reader),
+            None       => match automaton.fallback(state.complete,lexer) {
+                Some(rule) => automaton.accept(rule,true,lexer,reader),
+                None       => StageStatus::ExitFail,
+            }
+        }
+    }
+}
+
+
+// === Trait Impls ===
+
+impl<L,R> Debug for Engine<L,R> {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        let nfas = self.automata.iter().map(|automaton| automaton.data.automaton()).collect_vec();
+        f.debug_struct("Engine").field("nfas",&nfas).field("config",&self.config).finish()
+    }
+}
+
+
+
+// ===============
+// === Reached ===
+// ===============
+
+/// The states reached by the automaton of a group on the input read in the current match.
+#[derive(Clone,Debug)]
+struct Reached {
+    /// The state of the lazily constructed DFA.
+    cursor:Cursor,
+    /// The state of the complete DFA, tracked only for the rules without longest-match semantics
+    /// (see [`Inherited`]).
+    complete:Option<dfa::State>,
+}
+
+
+
+// =================
+// === Inherited ===
+// =================
+
+/// The complete DFA of a group without longest-match semantics, together with the rules that its
+/// states fall back to, just as in the generated lexers (see [`generate::inherited_rules`]).
+#[derive(Clone,Debug)]
+struct Inherited {
+    /// The minimized DFA of the rules, as generated by [`generate::minimize_dfa`].
+    dfa:Dfa,
+    /// The rules inherited by the states of the `dfa`.
+    rules:Vec<Option<nfa::State>>,
+}
+
+impl Inherited {
+    /// Build the complete DFA of the rules described by `data`.
+    fn new(data:&AutomatonData) -> Self {
+        let dfa   = generate::minimize_dfa(&Dfa::from(data.automaton()),data);
+        let rules = generate::inherited_rules(&dfa,data);
+        Inherited{dfa,rules}
+    }
+
+    /// Get the state reached from the `state` on the `symbol`.
+    fn next(&self, state:dfa::State, symbol:&Symbol) -> dfa::State {
+        if state.is_invalid() { return state }
+        self.dfa.links[(state.id(),self.dfa.alphabet.index_of_symbol(symbol))]
+    }
+
+    /// Get the rule inherited by the `state`.
+    fn rule(&self, state:dfa::State) -> Option<nfa::State> {
+        self.rules.get(state.id()).copied().flatten()
+    }
+}
+
+
+
+// =================
+// === Automaton ===
+// =================
+
+/// The automaton of a group, together with the data needed to execute its rules.
+struct Automaton<L,R> {
+    /// The NFA of the rules, with the rules attached to its states.
+    data:AutomatonData,
+    /// The callbacks of the rules.
+    callbacks:HashMap<nfa::State,Callback<L,R>>,
+    /// The capture groups of the rules that have them.
+    groups:HashMap<nfa::State,Groups>,
+    /// The complete DFA of the rules, if they have no longest-match semantics.
+    inherited:Option<Inherited>,
+}
+
+impl<L:'static,R:'static> Automaton<L,R> {
+    /// Build the automaton of the `group` from the `registry`.
+    fn new(group:&Group, registry:&Registry) -> Result<Self,Error> {
+        let data          = registry.to_nfa_from(group.id);
+        let rule_states   = data.public_states().iter().filter(|s| data.name(**s).is_some());
+        let mut callbacks = HashMap::new();
+        let mut groups    = HashMap::new();
+        for (state,rule) in rule_states.zip(registry.rules_for(group.id)) {
+            let callback = rule.closure.as_ref().and_then(|closure| closure.callback());
+            let callback = callback.ok_or_else(|| Error::MissingClosure(rule.to_string()))?;
+            rule.pattern.check().map_err(|err| Error::BadPattern(rule.to_string(),err))?;
+            callbacks.insert(*state,callback);
+            let capture_groups = data.capture_groups(*state);
+            if !capture_groups.is_empty() {
+                groups.insert(*state,Groups::new(capture_groups));
+            }
+        }
+        let inherited = (!data.longest_match()).as_some_from(|| Inherited::new(&data));
+        Ok(Automaton{data,callbacks,groups,inherited})
+    }
+
+    /// Build the DFA of the rules, which constructs its states lazily with the provided `config`.
+    fn dfa(&self, config:lazy::Config) -> LazyDfa {
+        LazyDfa::with_config(self.data.automaton().clone(),config)
+    }
+}
+
+impl<L,R,Definition,Output,Logger> Automaton<L,R>
+where L          : DerefMut<Target=Flexer<Definition,Output,Logger>>,
+      R          : ReaderOps,
+      Definition : State,
+      Output     : Clone,
+      Logger     : AnyLogger<Owned=Logger> + LoggerOps<logger::entry::level::Debug> {
+    /// Get the start states of the automaton, in the lazily constructed `dfa`.
+    fn start(&self, dfa:&mut LazyDfa) -> Reached {
+        let cursor   = dfa.start_state();
+        let complete = self.inherited.as_ref().map(|_| Dfa::START_STATE);
+        Reached{cursor,complete}
+    }
+
+    /// Get the states reached from the `state` on the `symbol`, if any, applying the operations of
+    /// the transition of the `dfa` to the `registers` at the `position` in the input.
+    fn next
+    ( &self
+    , dfa       : &mut LazyDfa
+    , state     : &Reached
+    , symbol    : &Symbol
+    , registers : &mut Registers
+    , position  : usize
+    ) -> Option<Reached> {
+        let cursor    = dfa.next_state_with(&state.cursor,symbol,registers,position)?;
+        let inherited = self.inherited.as_ref();
+        let complete  = state.complete.and_then(|state| Some(inherited?.next(state,symbol)));
+        Some(Reached{cursor,complete})
+    }
+
+    /// Get the rule inherited by the `state` of the complete DFA, if it is tracked.
+    fn inherited_rule(&self, state:Option<dfa::State>) -> Option<nfa::State> {
+        self.inherited.as_ref()?.rule(state?)
+    }
+
+    /// Get the state of the rule identified by `id`, as remembered in the `accepted_rule` field of
+    /// the [`Flexer`].
+    fn rule(&self, id:usize) -> Option<nfa::State> {
+        self.callbacks.keys().copied().find(|rule| rule.id() == id)
+    }
+
+    /// Get the rule to fall back to in a state that accepts no rule, which is the rule inherited by
+    /// its `complete` state, or the last one remembered while lexing if the rules have
+    /// longest-match semantics.
+    fn fallback(&self, complete:Option<dfa::State>, lexer:&mut L) -> Option<nfa::State> {
+        if self.data.longest_match() {
+            lexer.accepted_rule.take().and_then(|id| self.rule(id))
+        } else {
+            self.inherited_rule(complete)
+        }
+    }
+
+    /// Get the rule accepted in the state of the `dfa` under the `cursor`.
+    fn rule_in(&self, dfa:&LazyDfa, cursor:&Cursor) -> Option<nfa::State> {
+        self.data.rule_for_dfa_state(&dfa.sources(cursor))
+    }
+
+    /// Bookmark the input for each of the lookaheads whose bodies end in the state of the `dfa`
+    /// under the `cursor`.
+    fn bookmark_bodies(&self, dfa:&LazyDfa, cursor:&Cursor, lexer:&mut L, reader:&mut R) {
+        let bookmarks = lexer.bookmarks_mut();
+        for index in self.data.lookahead_bodies_in_dfa_state(&dfa.sources(cursor)) {
+            let context_bookmark = bookmarks.context_bookmark(index);
+            bookmarks.bookmark(context_bookmark,reader);
+        }
+    }
+
+    /// Store the spans of the capture groups of the `rule`, accepted in the state of the `dfa`
+    /// under the `cursor`, in the output registers of the `lexer`, at the `position` of the state
+    /// in the input.
+    ///
+    /// The spans are stored before every transition from the state, as the transition may remove
+    /// the state from the cache of the `dfa`. The transitions never write to the output registers,
+    /// and every state accepting a rule is left by remembering or accepting a rule, so that the
+    /// registers hold the spans of the rule that is accepted in the end.
+    fn store_captures
+    (&self, dfa:&LazyDfa, cursor:&Cursor, rule:Option<nfa::State>, lexer:&mut L, position:usize) {
+        if let Some(rule) = rule.filter(|rule| self.groups.contains_key(rule)) {
+            lexer.registers.apply(&dfa.finals(cursor,rule),position);
+        }
+    }
+
+    /// Bookmark the input if a transition leaves a state accepting the `rule` for a state that
+    /// accepts no rule, as given by the `target_rule`, and that may fall back to the `rule`, as in
+    /// the generated lexers (see [`generate::left_rule`]). The `rule` is remembered as the last one
+    /// accepted if the rules have longest-match semantics, and is otherwise inherited by the
+    /// `complete` target state.
+    fn remember_rule
+    ( &self
+    , rule        : Option<nfa::State>
+    , target_rule : Option<nfa::State>
+    , complete    : Option<dfa::State>
+    , lexer       : &mut L
+    , reader      : &mut R
+    ) {
+        let longest_match = self.data.longest_match();
+        let rule          = rule.filter(|_| target_rule.is_none());
+        let rule          = rule.filter(|rule| {
+            longest_match || self.inherited_rule(complete) == Some(*rule)
+        });
+        if let Some(rule) = rule {
+            let bookmarks     = lexer.bookmarks_mut();
+            let rule_bookmark = bookmarks.rule_bookmark;
+            bookmarks.bookmark(rule_bookmark,reader);
+            if longest_match {
+                lexer.accepted_rule = Some(rule.id());
+            }
+        }
+    }
+
+    /// Trigger the `rule`, just as the generated lexers do (see
+    /// [`generate::rule_body`](crate::generate::rule_body)).
+    ///
+    /// If `rewind_to_rule` is set, the input is first rewound to the `rule_bookmark`, as the rule
+    /// was accepted in an earlier state than the current one.
+    fn accept
+    (&self, rule:nfa::State, rewind_to_rule:bool, lexer:&mut L, reader:&mut R) -> StageStatus {
+        let bookmarks        = lexer.bookmarks_mut();
+        let matched_bookmark = bookmarks.matched_bookmark;
+        if rewind_to_rule {
+            let rule_bookmark = bookmarks.rule_bookmark;
+            bookmarks.rewind(rule_bookmark,reader);
+        }
+        if let Some(index) = self.data.lookahead_for_rule(rule) {
+            let context_bookmark = bookmarks.context_bookmark(index);
+            bookmarks.rewind(context_bookmark,reader);
+        }
+        if !self.data.lookaheads().is_empty() {
+            bookmarks.unset_context_bookmarks();
+        }
+        lexer.set_current_match(reader.pop_result());
+        if let Some(groups) = self.groups.get(&rule) {
+            lexer.set_captures(groups);
+        }
+        (self.callbacks[&rule])(lexer,reader);
+        lexer.bookmarks_mut().bookmark(matched_bookmark,reader);
+        StageStatus::ExitSuccess
+    }
+}
+
+
+
+// =============
+// === Error ===
+// =============
+
+/// Errors that arise when building an [`Engine`].
+#[derive(Clone,Debug,PartialEq)]
+pub enum Error {
+    /// The provided rule has no closure taking the lexer and the reader of the engine.
+    MissingClosure(String),
+    /// The pattern of the provided rule cannot be used in a lexer.
+    BadPattern(String,pattern::Error),
+}
+
+
+// === Trait Impls ===
+
+impl Display for Error {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingClosure(rule) => write!(f,
+                "The rule {} has no closure taking the lexer and the reader of the engine.",rule
+            ),
+            Error::BadPattern(rule,error) => write!(f,
+                "The rule {} has an invalid pattern. {}",rule,error
+            ),
+        }
+    }
+}
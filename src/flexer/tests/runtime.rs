@@ -0,0 +1,377 @@
+//! This file contains the tests of a lexer defined with closures as the callbacks of its rules, and
+//! executed by the runtime engine without generating its code.
+
+#![allow(missing_docs)]
+
+use enso_flexer::*;
+
+use crate::prelude::logger::AnyLogger;
+use crate::prelude::logger::Disabled;
+use crate::prelude::reader::BookmarkManager;
+use crate::prelude::reader::decoder::DecoderUTF8;
+use enso_flexer::automata::dfa::lazy;
+use enso_flexer::automata::pattern::Pattern;
+use enso_flexer::automata::pattern;
+use enso_flexer::generate;
+use enso_flexer::group::Registry;
+use enso_flexer::group;
+use enso_flexer::prelude::*;
+use enso_flexer::runtime::Engine;
+use enso_flexer::runtime;
+
+
+
+// ====================
+// === Type Aliases ===
+// ====================
+
+type Logger = Disabled;
+
+type Input = Reader<DecoderUTF8,&'static [u8]>;
+
+
+
+// ===========
+// === AST ===
+// ===========
+
+/// A token of the language.
+#[derive(Clone,Debug,PartialEq)]
+pub enum Token {
+    Word(String),
+    Assignment(String,String),
+    Number(String),
+    Dimension(String),
+    Comment(String),
+    Text(String),
+    Unrecognized(String),
+}
+
+/// A representation of a stream of tokens.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct TokenStream {
+    tokens:Vec<Token>
+}
+
+
+
+// ==================
+// === Test Lexer ===
+// ==================
+
+/// The lexer of the language, with the rules defined in [`TestState::new`].
+#[derive(Debug)]
+pub struct TestLexer {
+    lexer:Flexer<TestState,TokenStream,Logger>
+}
+
+impl Deref for TestLexer {
+    type Target = Flexer<TestState,TokenStream,Logger>;
+    fn deref(&self) -> &Self::Target {
+        &self.lexer
+    }
+}
+
+impl DerefMut for TestLexer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lexer
+    }
+}
+
+impl TestLexer {
+    pub fn new() -> Self {
+        let logger = Logger::new("TestLexer");
+        let lexer  = Flexer::new(logger);
+        TestLexer{lexer}
+    }
+
+    /// Push a token built from the text of the current match.
+    fn push_match(&mut self, token:impl Fn(String) -> Token) {
+        let token = token(self.current_match.clone());
+        self.output.tokens.push(token);
+    }
+}
+
+impl enso_flexer::Definition for TestLexer {
+    fn define() -> Self {
+        TestLexer::new()
+    }
+
+    fn groups(&self) -> &Registry {
+        self.lexer.groups()
+    }
+
+    fn set_up(&mut self) {}
+
+    fn tear_down(&mut self) {}
+}
+
+impl Default for TestLexer {
+    fn default() -> Self {
+        TestLexer::new()
+    }
+}
+
+
+
+// ===================
+// === Lexer State ===
+// ===================
+
+/// The stateful components of the lexer.
+#[derive(Debug)]
+pub struct TestState {
+    lexer_states:group::Registry,
+    initial_state:group::Identifier,
+    string_state:group::Identifier,
+    bookmarks:BookmarkManager,
+}
+
+impl enso_flexer::State for TestState {
+    fn new(_logger:&impl AnyLogger) -> Self {
+        let mut lexer_states = group::Registry::default();
+        let initial_state    = lexer_states.define_group("ROOT",None);
+        let string_state     = lexer_states.define_group("STRING",None);
+        let bookmarks        = BookmarkManager::new();
+        let mut rule         = |group,regex:&str,callback:fn(&mut TestLexer)| {
+            let pattern = Pattern::parse(regex).unwrap();
+            let closure = move |lexer:&mut TestLexer, _reader:&mut Input| callback(lexer);
+            lexer_states.create_closure_rule(group,&pattern,closure);
+        };
+        rule(initial_state,"[a-z]+",|lexer| lexer.push_match(Token::Word));
+        rule(initial_state,"(?<name>[a-z]+)=(?<value>[0-9]+)",|lexer| {
+            let name  = lexer.capture("name").unwrap_or_default().to_string();
+            let value = lexer.capture("value").unwrap_or_default().to_string();
+            lexer.output.tokens.push(Token::Assignment(name,value));
+        });
+        rule(initial_state,"[0-9]+(?=px)",|lexer| lexer.push_match(Token::Dimension));
+        rule(initial_state,"[0-9]+",|lexer| lexer.push_match(Token::Number));
+        rule(initial_state,"^#[^\n]*",|lexer| lexer.push_match(Token::Comment));
+        rule(initial_state,"[ \n]",|_| {});
+        rule(initial_state,"\"",|lexer| {
+            let string_state = lexer.string_state;
+            lexer.push_state(string_state);
+        });
+        rule(initial_state,"\\z",|_| {});
+        rule(initial_state,".",|lexer| lexer.push_match(Token::Unrecognized));
+        rule(string_state,"[^\"]+",|lexer| lexer.push_match(Token::Text));
+        rule(string_state,"\"",|lexer| { lexer.pop_state(); });
+        rule(string_state,"\\z",|_| {});
+        TestState{lexer_states,initial_state,string_state,bookmarks}
+    }
+
+    fn initial_state(&self) -> group::Identifier {
+        self.initial_state
+    }
+
+    fn groups(&self) -> &group::Registry {
+        &self.lexer_states
+    }
+
+    fn groups_mut(&mut self) -> &mut group::Registry {
+        &mut self.lexer_states
+    }
+
+    fn bookmarks(&self) -> &BookmarkManager {
+        &self.bookmarks
+    }
+
+    fn bookmarks_mut(&mut self) -> &mut BookmarkManager {
+        &mut self.bookmarks
+    }
+
+    fn specialize(&self) -> Result<String,GenError> {
+        generate::specialize(self,"TestLexer","TokenStream")
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+/// Executes the lexer on the provided input string slice.
+fn run_test_on(str:&'static str) -> Vec<Token> {
+    run_groups_on(TestLexer::new().groups(),str)
+}
+
+/// Executes the lexer with the groups of the `registry` in place of its own ones on the provided
+/// input string slice.
+fn run_groups_on(registry:&Registry, str:&'static str) -> Vec<Token> {
+    run_with_config_on(registry,default(),str)
+}
+
+/// Executes the lexer with the groups of the `registry`, whose DFAs are constructed with the
+/// `config`, on the provided input string slice.
+fn run_with_config_on(registry:&Registry, config:lazy::Config, str:&'static str) -> Vec<Token> {
+    let reader     = Reader::new(str.as_bytes(),DecoderUTF8());
+    let mut lexer  = TestLexer::new();
+    let engine     = Engine::with_config(registry,config).unwrap();
+    let run_result = engine.run(&mut lexer,reader);
+    match run_result.kind {
+        ResultKind::Success => run_result.tokens.tokens,
+        _                   => default()
+    }
+}
+
+/// The groups of a lexer in which the paths following the matches of different rules converge on
+/// the same state of the automaton, as in the `longest_match` lexer of the `flexer-testing` crates,
+/// with or without [`longest_match`](group::Group::longest_match) semantics.
+///
+/// The words `a`, `b` and `(ac|bd)cc` are lexed as [`Token::Word`], [`Token::Number`] and
+/// [`Token::Text`] respectively.
+fn converging_groups(longest_match:bool) -> Registry {
+    let mut registry = Registry::default();
+    let group        = registry.define_group("ROOT",None);
+    registry.group_mut(group).longest_match = longest_match;
+    let mut rule = |regex:&str,callback:fn(&mut TestLexer)| {
+        let pattern = Pattern::parse(regex).unwrap();
+        let closure = move |lexer:&mut TestLexer, _reader:&mut Input| callback(lexer);
+        registry.create_closure_rule(group,&pattern,closure);
+    };
+    rule("a",|lexer| lexer.push_match(Token::Word));
+    rule("b",|lexer| lexer.push_match(Token::Number));
+    rule("(ac|bd)cc",|lexer| lexer.push_match(Token::Text));
+    rule("\\z",|_| {});
+    rule(".",|lexer| lexer.push_match(Token::Unrecognized));
+    registry
+}
+
+#[test]
+fn test_words_and_assignments() {
+    let expected = vec![
+        Token::Word("ab".into()),
+        Token::Assignment("cd".into(),"12".into()),
+        Token::Word("e".into()),
+        Token::Unrecognized("=".into()),
+    ];
+    assert_eq!(run_test_on("ab cd=12 e="),expected);
+}
+
+#[test]
+fn test_lookahead() {
+    let expected = vec![
+        Token::Dimension("12".into()),
+        Token::Word("px".into()),
+        Token::Number("3".into()),
+        Token::Word("p".into()),
+    ];
+    assert_eq!(run_test_on("12px 3p"),expected);
+}
+
+#[test]
+fn test_line_start() {
+    let expected = vec![
+        Token::Comment("#a b".into()),
+        Token::Word("c".into()),
+        Token::Unrecognized("#".into()),
+        Token::Word("d".into()),
+    ];
+    assert_eq!(run_test_on("#a b\nc#d"),expected);
+}
+
+#[test]
+fn test_groups() {
+    let expected = vec![
+        Token::Word("a".into()),
+        Token::Text("b c".into()),
+        Token::Word("d".into()),
+        Token::Text("e".into()),
+    ];
+    assert_eq!(run_test_on("a\"b c\"d\"e"),expected);
+}
+
+#[test]
+fn test_converging_paths() {
+    for &longest_match in &[false,true] {
+        let registry = converging_groups(longest_match);
+        let expected = vec![Token::Text("accc".into()),Token::Text("bdcc".into())];
+        assert_eq!(run_groups_on(&registry,"acccbdcc"),expected);
+        let expected = vec![
+            Token::Word("a".into()),
+            Token::Unrecognized("c".into()),
+            Token::Unrecognized("c".into()),
+            Token::Number("b".into()),
+        ];
+        assert_eq!(run_groups_on(&registry,"accb"),expected);
+    }
+}
+
+#[test]
+fn test_first_match_on_converging_paths() {
+    let registry = converging_groups(false);
+    let expected = vec![
+        Token::Word("b".into()),
+        Token::Unrecognized("d".into()),
+        Token::Unrecognized("c".into()),
+    ];
+    assert_eq!(run_groups_on(&registry,"bdc"),expected);
+}
+
+#[test]
+fn test_longest_match_on_converging_paths() {
+    let registry = converging_groups(true);
+    let expected = vec![
+        Token::Number("b".into()),
+        Token::Unrecognized("d".into()),
+        Token::Unrecognized("c".into()),
+    ];
+    assert_eq!(run_groups_on(&registry,"bdc"),expected);
+}
+
+#[test]
+fn test_small_cache() {
+    let input    = "ab cd=12 e=\n#a\n12px\"b c\"d";
+    let groups   = TestLexer::new();
+    let expected = run_test_on(input);
+    assert_eq!(expected.len(),9);
+    for max_cache_clears in 0..=1 {
+        let cache_capacity = 2;
+        let config         = lazy::Config{cache_capacity,max_cache_clears};
+        assert_eq!(run_with_config_on(groups.groups(),config,input),expected);
+    }
+}
+
+#[test]
+fn test_thread_safety() {
+    fn assert_send_sync<T:Send+Sync>() {}
+    assert_send_sync::<Registry>();
+    assert_send_sync::<Engine<TestLexer,Input>>();
+}
+
+#[test]
+fn test_missing_closure() {
+    let mut registry = Registry::default();
+    let group        = registry.define_group("ROOT",None);
+    registry.create_rule(group,&Pattern::char('a'),"self.on_a(reader)");
+    let error = Engine::<TestLexer,Input>::new(&registry).unwrap_err();
+    assert_eq!(error,runtime::Error::MissingClosure("`a` => `self.on_a(reader)`".into()));
+}
+
+#[test]
+fn test_invalid_pattern() {
+    let mut registry = Registry::default();
+    let group        = registry.define_group("ROOT",None);
+    let ambiguous    = Pattern::char('a').many().followed_by(&Pattern::all_of("ab"));
+    registry.create_closure_rule(group,&ambiguous,|_:&mut TestLexer, _:&mut Input| {});
+    let rule     = registry.rules_for(group)[0].to_string();
+    let error    = Engine::<TestLexer,Input>::new(&registry).unwrap_err();
+    let expected = pattern::Error::AmbiguousLookahead(ambiguous);
+    assert_eq!(error,runtime::Error::BadPattern(rule,expected));
+    let mut registry = Registry::default();
+    let group        = registry.define_group("ROOT",None);
+    let nested       = Pattern::char('a').followed_by(&Pattern::char('b'));
+    registry.create_rule(group,&(&nested | Pattern::char('c')),"self.on_a(reader)");
+    let rule     = registry.rules_for(group)[0].to_string();
+    let error    = generate::specialize_groups(&registry,"TestLexer","Vec<Token>").unwrap_err();
+    let expected = pattern::Error::NestedLookahead(nested);
+    assert_eq!(error,GenError::BadPattern(rule,expected));
+}
+
+#[test]
+fn test_specialize() {
+    let lexer = TestLexer::new();
+    let error = lexer.specialize().unwrap_err();
+    assert_eq!(error,GenError::ClosureCallback("`[a-z]+` => closure".into()));
+}
@@ -363,16 +363,20 @@ pub struct BookmarkManager {
     pub matched_bookmark: BookmarkId,
     /// A bookmark used by the flexer to deal with overlapping rules that may fail later.
     pub rule_bookmark: BookmarkId,
+    /// The bookmarks used by the flexer to mark the ends of the bodies of lookahead rules, created
+    /// on demand.
+    context_bookmarks: Vec<BookmarkId>,
 }
 
 #[allow(missing_docs)]
 impl BookmarkManager {
     /// Create a new bookmark manager, with no associated bookmarks.
     pub fn new() -> BookmarkManager {
-        let mut bookmarks    = Vec::new();
-        let matched_bookmark = BookmarkManager::make_bookmark(&mut bookmarks);
-        let rule_bookmark    = BookmarkManager::make_bookmark(&mut bookmarks);
-        BookmarkManager {bookmarks,matched_bookmark,rule_bookmark}
+        let mut bookmarks     = Vec::new();
+        let matched_bookmark  = BookmarkManager::make_bookmark(&mut bookmarks);
+        let rule_bookmark     = BookmarkManager::make_bookmark(&mut bookmarks);
+        let context_bookmarks = Vec::new();
+        BookmarkManager {bookmarks,matched_bookmark,rule_bookmark,context_bookmarks}
     }
 
     /// Create a new bookmark in the manager, returning a handle to it.
@@ -400,6 +404,24 @@ impl BookmarkManager {
         self.bookmarks[bookmark.id].set    = false
     }
 
+    /// Get the bookmark used by the flexer for the lookahead with the provided `index`, adding it
+    /// to the manager if it does not exist yet.
+    pub fn context_bookmark(&mut self, index:usize) -> BookmarkId {
+        while self.context_bookmarks.len() <= index {
+            let bookmark = self.add_bookmark();
+            self.context_bookmarks.push(bookmark);
+        }
+        self.context_bookmarks[index]
+    }
+
+    /// Unsets all the bookmarks used by the flexer for the lookaheads, so that they do not prevent
+    /// the reader from discarding the input before them.
+    pub fn unset_context_bookmarks(&mut self) {
+        for bookmark in &self.context_bookmarks {
+            self.bookmarks[bookmark.id] = Bookmark::default();
+        }
+    }
+
     /// Decrease the offset for all bookmarks by the specified `amount` in preparation for
     /// truncating the reader's buffer.
     pub fn decrease_bookmark_offsets(&mut self, amount:usize) {
@@ -568,6 +590,20 @@ mod tests {
         assert_eq!(reader.buffer.len(), BUFFER_SIZE);
     }
 
+    #[test]
+    fn test_context_bookmarks() {
+        let mut mgr    = bookmark_manager();
+        let mut reader = Reader::new("abc".as_bytes(), DecoderUTF8());
+        let bookmark   = mgr.context_bookmark(1);
+        assert_eq!(mgr.context_bookmark(1),bookmark);
+        assert_ne!(mgr.context_bookmark(0),bookmark);
+        reader.advance_char(&mut mgr);
+        mgr.bookmark(bookmark,&mut reader);
+        assert_eq!(mgr.min_offset(),Some(0));
+        mgr.unset_context_bookmarks();
+        assert_eq!(mgr.min_offset(),None);
+    }
+
     #[bench]
     fn bench_reader(bencher:&mut Bencher) {
         let run = || {
@@ -0,0 +1,253 @@
+#!/usr/bin/env node
+
+// Generates the Unicode range tables of the `enso-automata` crate from the files of the Unicode
+// Character Database (UCD).
+//
+// Usage: tools/generate-unicode-tables.js <ucd-directory> [<output-file>]
+//
+// The tables are pinned to the version 14.0.0 of the UCD. The UCD directory needs the following
+// files of that version, unmodified:
+//
+// - https://www.unicode.org/Public/14.0.0/ucd/UnicodeData.txt
+// - https://www.unicode.org/Public/14.0.0/ucd/DerivedCoreProperties.txt
+// - https://www.unicode.org/Public/14.0.0/ucd/PropList.txt
+// - https://www.unicode.org/Public/14.0.0/ucd/CaseFolding.txt
+//
+// The version of the files is read from the header of `DerivedCoreProperties.txt`, and the script
+// fails if it is not the pinned one. Moving to a newer version of Unicode means updating
+// `unicodeVersion` below, together with the tests of `src/automata/src/pattern/unicode.rs` that
+// depend on it. The output defaults to `src/automata/src/pattern/unicode/tables.rs`.
+
+const fss  = require('fs')
+const path = require('path')
+
+const unicodeVersion = [14,0,0]
+const maxCodePoint   = 0x10FFFF
+const lineWidth      = 100
+const indent         = '    '
+const defaultOut     = path.join(
+    path.dirname(__dirname),'src','automata','src','pattern','unicode','tables.rs'
+)
+
+const generalCategories = [
+    ['Lu','Uppercase_Letter'],
+    ['Ll','Lowercase_Letter'],
+    ['Lt','Titlecase_Letter'],
+    ['Lm','Modifier_Letter'],
+    ['Lo','Other_Letter'],
+    ['Mn','Nonspacing_Mark'],
+    ['Mc','Spacing_Mark'],
+    ['Me','Enclosing_Mark'],
+    ['Nd','Decimal_Number'],
+    ['Nl','Letter_Number'],
+    ['No','Other_Number'],
+    ['Pc','Connector_Punctuation'],
+    ['Pd','Dash_Punctuation'],
+    ['Ps','Open_Punctuation'],
+    ['Pe','Close_Punctuation'],
+    ['Pi','Initial_Punctuation'],
+    ['Pf','Final_Punctuation'],
+    ['Po','Other_Punctuation'],
+    ['Sm','Math_Symbol'],
+    ['Sc','Currency_Symbol'],
+    ['Sk','Modifier_Symbol'],
+    ['So','Other_Symbol'],
+    ['Zs','Space_Separator'],
+    ['Zl','Line_Separator'],
+    ['Zp','Paragraph_Separator'],
+    ['Cc','Control'],
+    ['Cf','Format'],
+    ['Cs','Surrogate'],
+    ['Co','Private_Use'],
+    ['Cn','Unassigned'],
+]
+
+const properties = [
+    ['XID_Start','DerivedCoreProperties.txt'],
+    ['XID_Continue','DerivedCoreProperties.txt'],
+    ['White_Space','PropList.txt'],
+]
+
+
+
+// =================
+// === UCD Files ===
+// =================
+
+// Read the data lines of the UCD file `name`, split into their trimmed fields.
+function readFields(ucdDir, name) {
+    let contents = fss.readFileSync(path.join(ucdDir,name),'utf8')
+    return contents.split('\n')
+        .map(line => line.split('#')[0].trim())
+        .filter(line => line.length > 0)
+        .map(line => line.split(';').map(field => field.trim()))
+}
+
+// Parse a code point, or a range of code points written as `XXXX..YYYY`.
+function parseRange(text) {
+    let [start,end] = text.split('..').map(code => parseInt(code,16))
+    return [start,end === undefined ? start : end]
+}
+
+// Read the version of the database from the header of `DerivedCoreProperties.txt`, checking that
+// it is the pinned `unicodeVersion`.
+function readVersion(ucdDir) {
+    let header = fss.readFileSync(path.join(ucdDir,'DerivedCoreProperties.txt'),'utf8')
+    let found  = header.match(/DerivedCoreProperties-(\d+)\.(\d+)\.(\d+)\.txt/)
+    if (found === null) {
+        throw new Error('The version of the UCD files is not stated in their headers.')
+    }
+    let version = found.slice(1,4).map(part => parseInt(part,10))
+    if (version.join('.') !== unicodeVersion.join('.')) {
+        throw new Error(
+            `The UCD files are of version ${version.join('.')}, but the tables are pinned to`
+                + ` version ${unicodeVersion.join('.')}.`
+        )
+    }
+    return version
+}
+
+// Read the general categories of the code points from `UnicodeData.txt`, as a map from the
+// categories to their code points. The code points missing from the file are unassigned.
+function readGeneralCategories(ucdDir) {
+    let categories = new Map(generalCategories.map(([short,_]) => [short,[]]))
+    let assigned   = new Uint8Array(maxCodePoint + 1)
+    let rangeStart = null
+    for (let [code,name,category] of readFields(ucdDir,'UnicodeData.txt')) {
+        let codePoint = parseInt(code,16)
+        if (name.endsWith(', First>')) {
+            rangeStart = codePoint
+            continue
+        }
+        let start  = name.endsWith(', Last>') ? rangeStart : codePoint
+        rangeStart = null
+        categories.get(category).push([start,codePoint])
+        assigned.fill(1,start,codePoint + 1)
+    }
+    let unassigned = categories.get('Cn')
+    for (let codePoint = 0; codePoint <= maxCodePoint; codePoint++) {
+        if (!assigned[codePoint]) {
+            unassigned.push([codePoint,codePoint])
+        }
+    }
+    return categories
+}
+
+// Read the code points with the binary `property` from the UCD file `name`.
+function readProperty(ucdDir, name, property) {
+    return readFields(ucdDir,name)
+        .filter(([_,prop]) => prop === property)
+        .map(([range,_]) => parseRange(range))
+}
+
+// Read the simple case folding from `CaseFolding.txt`, which consists of the mappings with the
+// statuses `C` (common) and `S` (simple).
+function readSimpleCaseFolding(ucdDir) {
+    return readFields(ucdDir,'CaseFolding.txt')
+        .filter(([_,status]) => status === 'C' || status === 'S')
+        .map(([code,_,folded]) => [parseInt(code,16),parseInt(folded,16)])
+        .sort(([left,_],[right,__]) => left - right)
+}
+
+
+
+// ==============
+// === Tables ===
+// ==============
+
+// Sort the `ranges` and merge the ones that overlap or are adjacent.
+function normalize(ranges) {
+    let sorted = [...ranges].sort(([left,_],[right,__]) => left - right)
+    let result = []
+    for (let [start,end] of sorted) {
+        let last = result[result.length - 1]
+        if (last !== undefined && start <= last[1] + 1) {
+            last[1] = Math.max(last[1],end)
+        } else {
+            result.push([start,end])
+        }
+    }
+    return result
+}
+
+// Format a code point in the style of the tables.
+function hex(codePoint) {
+    return '0x' + codePoint.toString(16).toUpperCase()
+}
+
+// Render the table `name` of the `pairs`, documented with the lines of `docs`.
+function renderTable(name, docs, pairs) {
+    let lines   = docs.map(line => line.length > 0 ? `/// ${line}` : '///')
+    let current = indent
+    lines.push(`pub const ${name} : &[(u32,u32)] = &[`)
+    for (let [left,right] of pairs) {
+        let item = `(${hex(left)},${hex(right)}),`
+        if (current !== indent && current.length + 1 + item.length > lineWidth) {
+            lines.push(current)
+            current = indent
+        }
+        current += current === indent ? item : ` ${item}`
+    }
+    if (current !== indent) {
+        lines.push(current)
+    }
+    lines.push('];')
+    return lines.join('\n')
+}
+
+// Render the whole module of the tables for the UCD of the given `version`.
+function renderModule(version, categories, properties, caseFolding) {
+    let versionText = version.join('.')
+    let header = [
+        '//! Compact range tables for the Unicode character properties used by [`super`].',
+        '//!',
+        '//! The tables are generated by `tools/generate-unicode-tables.js` from the files of the'
+            + ' Unicode',
+        `//! Character Database, version ${versionText}, published at`
+            + ` <https://www.unicode.org/Public/${versionText}/ucd/>.`,
+        '//! They are not meant to be edited by hand. Every table is a sorted list of disjoint,',
+        '//! non-adjacent and inclusive ranges of code points.',
+        '',
+        '/// The version of the Unicode Character Database that the tables follow.',
+        `pub const UNICODE_VERSION : (u32,u32,u32) = (${version.join(',')});`,
+    ].join('\n')
+    let tables = [header]
+    for (let [short,long] of generalCategories) {
+        let docs = [`The code points in the general category \`${short}\` (\`${long}\`).`]
+        tables.push(renderTable(short.toUpperCase(),docs,normalize(categories.get(short))))
+    }
+    for (let [property,ranges] of properties) {
+        let docs = [`The code points with the \`${property}\` property.`]
+        tables.push(renderTable(property.toUpperCase(),docs,normalize(ranges)))
+    }
+    let foldingDocs = [
+        'The simple case folding of the code points, as pairs of a code point and its folded form.',
+        '',
+        'Only the code points that are not folded to themselves are listed.',
+    ]
+    tables.push(renderTable('SIMPLE_CASE_FOLDING',foldingDocs,caseFolding))
+    return tables.join('\n\n') + '\n'
+}
+
+
+
+// ============
+// === Main ===
+// ============
+
+function main() {
+    let args = process.argv.slice(2)
+    if (args.length < 1 || args.length > 2) {
+        console.error('Usage: generate-unicode-tables.js <ucd-directory> [<output-file>]')
+        process.exit(1)
+    }
+    let [ucdDir,output] = [args[0],args[1] || defaultOut]
+    let version         = readVersion(ucdDir)
+    let categories      = readGeneralCategories(ucdDir)
+    let props           = properties.map(([prop,file]) => [prop,readProperty(ucdDir,file,prop)])
+    let caseFolding     = readSimpleCaseFolding(ucdDir)
+    fss.writeFileSync(output,renderModule(version,categories,props,caseFolding))
+    console.log(`Generated the tables for Unicode ${version.join('.')} in '${output}'.`)
+}
+
+main()